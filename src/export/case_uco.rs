@@ -0,0 +1,572 @@
+use std::{collections::{HashMap, HashSet}, fs, io::Write, path::Path};
+use chrono::{DateTime, FixedOffset};
+use serde_json::{json, Map, Value as JsonValue};
+use uuid::Uuid;
+use crate::models::{parse_datetime, CaseField, DeviceInfo, ExtractionInfo, File, Model, Project};
+use super::percent_encode;
+
+
+
+/// Types exported as `uco-observable:Message`.
+const MESSAGE_TYPES: [&str; 3] = ["InstantMessage", "Email", "SMS"];
+
+
+fn context() -> JsonValue {
+    json!({
+        "kb": "urn:ufdr:kb:",
+        "case-investigation": "https://ontology.caseontology.org/case/investigation/",
+        "uco-action": "https://ontology.unifiedcyberontology.org/uco/action/",
+        "uco-core": "https://ontology.unifiedcyberontology.org/uco/core/",
+        "uco-identity": "https://ontology.unifiedcyberontology.org/uco/identity/",
+        "uco-observable": "https://ontology.unifiedcyberontology.org/uco/observable/",
+        "uco-types": "https://ontology.unifiedcyberontology.org/uco/types/",
+        "uco-vocabulary": "https://ontology.unifiedcyberontology.org/uco/vocabulary/",
+        "xsd": "http://www.w3.org/2001/XMLSchema#",
+    })
+}
+
+
+/// Writes the project as one CASE/UCO JSON-LD document. Errors if the file
+/// exists.
+pub fn export_project<P: AsRef<Path>>(project: &Project, path: P) -> Result<(), Box<dyn std::error::Error>> {
+    let file = fs::File::create_new(path)?;
+    write_case(project, std::io::BufWriter::new(file))
+}
+
+pub fn write_case<W: Write>(project: &Project, mut writer: W) -> Result<(), Box<dyn std::error::Error>> {
+    serde_json::to_writer_pretty(&mut writer, &project_to_case(project))?;
+    writer.write_all(b"\n")?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Maps the project onto CASE/UCO: the case information becomes an
+/// `Investigation`, each extraction an `InvestigativeAction` on a `Device`
+/// whose result is a `ProvenanceRecord` listing the files, messages,
+/// contacts and calls taken from it. Parties become phone or application
+/// accounts shared between the objects referring to them. Deleted objects
+/// are tagged `Deleted`.
+pub fn project_to_case(project: &Project) -> JsonValue {
+    let mut graph = Graph::default();
+    let mut provenance: HashMap<u64, Vec<String>> = HashMap::new();
+
+    for file in project.tagged_files.iter().flat_map(|tagged_files| tagged_files.files.iter()) {
+        let id = graph.file(file);
+        provenance.entry(file.extraction_id).or_default().push(id);
+    }
+    for model in project.model_graph().models() {
+        let id = match model.dtype.as_str() {
+            dtype if MESSAGE_TYPES.contains(&dtype) => graph.message(model),
+            "Contact" => graph.contact(model),
+            "Call" => graph.call(model),
+            _ => continue,
+        };
+        provenance.entry(model.extraction_id).or_default().push(id);
+    }
+
+    let mut actions = Vec::new();
+    for info in &project.source_extractions.infos {
+        let device_info = project.device_info(info.id);
+        let objects = provenance.remove(&info.id).unwrap_or_default();
+        actions.push(graph.extraction(project, info, device_info.as_ref(), objects));
+    }
+
+    let case_info = project.case_information.case_info();
+    let mut investigation = node(&format!("investigation-{}", percent_encode(&project.id)), "case-investigation:Investigation");
+    let name = case_info.get(CaseField::CaseNumber).or(case_info.get(CaseField::CaseName)).unwrap_or(&project.name);
+    investigation.insert("uco-core:name".to_string(), json!(name));
+    let description: Vec<String> = project
+        .case_information
+        .fields
+        .iter()
+        .filter(|field| !field.value_text().is_empty())
+        .map(|field| format!("{}: {}", field.name, field.value_text()))
+        .collect();
+    if !description.is_empty() {
+        investigation.insert("uco-core:description".to_string(), json!(description));
+    }
+    if !actions.is_empty() {
+        investigation.insert("uco-core:object".to_string(), JsonValue::Array(actions.iter().map(|id| reference(id)).collect()));
+    }
+    graph.nodes.insert(0, JsonValue::Object(investigation));
+
+    json!({
+        "@context": context(),
+        "@graph": graph.nodes,
+    })
+}
+
+
+#[derive(Default)]
+struct Graph {
+    nodes: Vec<JsonValue>,
+    /// `@id`s of accounts, applications and email addresses by their key.
+    shared: HashMap<String, String>,
+    ids: HashSet<String>,
+}
+
+impl Graph {
+    fn push(&mut self, object: Map<String, JsonValue>) -> String {
+        let id = object["@id"].as_str().unwrap_or_default().to_string();
+        if self.ids.insert(id.clone()) {
+            self.nodes.push(JsonValue::Object(object));
+        }
+        id
+    }
+
+    /// The part of an object's `@id` after `kind`: its report id, or a UUID
+    /// if another object of the same kind already has that id.
+    fn object_stem(&self, kind: &str, id: &str) -> String {
+        let stem = percent_encode(id);
+        if self.ids.contains(&format!("kb:{}-{}", kind, stem)) {
+            Uuid::new_v4().to_string()
+        } else {
+            stem
+        }
+    }
+
+    fn file(&mut self, file: &File) -> String {
+        let stem = self.object_stem("file", &file.id);
+        let mut object = observable(&format!("file-{}", stem), "uco-observable:File", &file.deleted);
+        let mut facet = facet_node("uco-observable:FileFacet");
+        let name = file.path.rsplit(['/', '\\']).next().unwrap_or(&file.path);
+        facet.insert("uco-observable:fileName".to_string(), json!(name));
+        facet.insert("uco-observable:filePath".to_string(), json!(file.path));
+        facet.insert("uco-observable:sizeInBytes".to_string(), typed("xsd:integer", file.size));
+        let properties = [
+            ("CreationTime", "uco-observable:observableCreatedTime"),
+            ("ModifyTime", "uco-observable:modifiedTime"),
+            ("AccessTime", "uco-observable:accessedTime"),
+        ];
+        for timestamp in file.access_info.iter().flat_map(|access_info| access_info.timestamps.iter()) {
+            let property = properties.iter().find(|(name, _)| *name == timestamp.name).map(|(_, property)| *property);
+            if let (Some(property), Some(time)) = (property, parse_datetime(&timestamp.text)) {
+                facet.insert(property.to_string(), date_time(time));
+            }
+        }
+        let mut facets = vec![JsonValue::Object(facet)];
+        let hashes: Vec<JsonValue> = ["MD5", "SHA1", "SHA256"]
+            .iter()
+            .filter_map(|algorithm| {
+                let value = file.hash(algorithm)?;
+                Some(json!({
+                    "@id": format!("kb:hash-{}-{}", algorithm.to_lowercase(), stem),
+                    "@type": "uco-types:Hash",
+                    "uco-types:hashMethod": typed("uco-vocabulary:HashNameVocab", *algorithm),
+                    "uco-types:hashValue": typed("xsd:hexBinary", value.to_uppercase()),
+                }))
+            })
+            .collect();
+        if !hashes.is_empty() {
+            let mut content = facet_node("uco-observable:ContentDataFacet");
+            content.insert("uco-observable:hash".to_string(), JsonValue::Array(hashes));
+            facets.push(JsonValue::Object(content));
+        }
+        object.insert("uco-core:hasFacet".to_string(), JsonValue::Array(facets));
+        self.push(object)
+    }
+
+    fn message(&mut self, model: &Model) -> String {
+        let stem = self.object_stem("message", &model.id);
+        let mut object = observable(&format!("message-{}", stem), "uco-observable:Message", &model.deleted_state);
+        let mut facet = facet_node("uco-observable:MessageFacet");
+        if let Some(body) = model.field_text("Body") {
+            facet.insert("uco-observable:messageText".to_string(), json!(body));
+        }
+        if let Some(time) = model.field_text("TimeStamp").and_then(parse_datetime) {
+            facet.insert("uco-observable:sentTime".to_string(), date_time(time));
+        }
+        if let Some(from) = model.nested_models("From").first().and_then(|party| self.account(party)) {
+            facet.insert("uco-observable:from".to_string(), reference(&from));
+        }
+        let to: Vec<JsonValue> = model.nested_models("To").iter().filter_map(|party| self.account(party)).map(|id| reference(&id)).collect();
+        if !to.is_empty() {
+            facet.insert("uco-observable:to".to_string(), JsonValue::Array(to));
+        }
+        if let Some(application) = self.application(model) {
+            facet.insert("uco-observable:application".to_string(), reference(&application));
+        }
+        object.insert("uco-core:hasFacet".to_string(), json!([facet]));
+        self.push(object)
+    }
+
+    fn call(&mut self, model: &Model) -> String {
+        let stem = self.object_stem("call", &model.id);
+        let mut object = observable(&format!("call-{}", stem), "uco-observable:Call", &model.deleted_state);
+        let mut facet = facet_node("uco-observable:CallFacet");
+        let direction = model.field_text("Direction");
+        if let Some(call_type) = model.field_text("Type").or(direction) {
+            facet.insert("uco-observable:callType".to_string(), json!(call_type));
+        }
+        if let Some(time) = model.field_text("TimeStamp").and_then(parse_datetime) {
+            facet.insert("uco-observable:startTime".to_string(), date_time(time));
+        }
+        if let Some(seconds) = model.field_text("Duration").and_then(duration_seconds) {
+            facet.insert("uco-observable:duration".to_string(), typed("xsd:integer", seconds));
+        }
+        let (mut from, mut to) = (Vec::new(), Vec::new());
+        for party in model.nested_models("Parties") {
+            let incoming = match party.field_text("Role") {
+                Some(role) => role == "From",
+                None => direction == Some("Incoming"),
+            };
+            if let Some(id) = self.account(party) {
+                if incoming { from.push(reference(&id)) } else { to.push(reference(&id)) }
+            }
+        }
+        if let Some(from) = from.into_iter().next() {
+            facet.insert("uco-observable:from".to_string(), from);
+        }
+        if !to.is_empty() {
+            facet.insert("uco-observable:to".to_string(), JsonValue::Array(to));
+        }
+        if let Some(application) = self.application(model) {
+            facet.insert("uco-observable:application".to_string(), reference(&application));
+        }
+        object.insert("uco-core:hasFacet".to_string(), json!([facet]));
+        self.push(object)
+    }
+
+    fn contact(&mut self, model: &Model) -> String {
+        let stem = self.object_stem("contact", &model.id);
+        let mut object = observable(&format!("contact-{}", stem), "uco-observable:Contact", &model.deleted_state);
+        let mut facet = facet_node("uco-observable:ContactFacet");
+        if let Some(name) = model.field_text("Name") {
+            facet.insert("uco-observable:displayName".to_string(), json!(name));
+        }
+        let (mut phones, mut emails) = (Vec::new(), Vec::new());
+        for (index, entry) in model.nested_models("Entries").iter().enumerate() {
+            let Some(value) = entry.field_text("Value") else { continue };
+            let id = format!("kb:contact-{}-entry-{}", stem, index);
+            match entry.dtype.as_str() {
+                "PhoneNumber" => {
+                    let account = self.phone_account(value, None);
+                    phones.push(json!({
+                        "@id": id,
+                        "@type": "uco-observable:ContactPhone",
+                        "uco-observable:contactPhoneNumber": reference(&account),
+                    }));
+                }
+                "EmailAddress" => {
+                    let address = self.email_address(value);
+                    emails.push(json!({
+                        "@id": id,
+                        "@type": "uco-observable:ContactEmail",
+                        "uco-observable:emailAddress": reference(&address),
+                    }));
+                }
+                _ => (),
+            }
+        }
+        if !phones.is_empty() {
+            facet.insert("uco-observable:contactPhone".to_string(), JsonValue::Array(phones));
+        }
+        if !emails.is_empty() {
+            facet.insert("uco-observable:contactEmail".to_string(), JsonValue::Array(emails));
+        }
+        if let Some(application) = self.application(model) {
+            facet.insert("uco-observable:sourceApplication".to_string(), reference(&application));
+        }
+        object.insert("uco-core:hasFacet".to_string(), json!([facet]));
+        self.push(object)
+    }
+
+    /// A phone account for phone numbers, an application account otherwise.
+    fn account(&mut self, party: &Model) -> Option<String> {
+        let identifier = party.field_text("Identifier")?.trim();
+        let name = party.field_text("Name");
+        let phone_like = identifier.chars().any(|c| c.is_ascii_digit())
+            && identifier.chars().all(|c| c.is_ascii_digit() || " +-().".contains(c));
+        if phone_like {
+            return Some(self.phone_account(identifier, name));
+        }
+        let key = format!("account:{}", identifier.to_lowercase());
+        if let Some(id) = self.shared.get(&key) {
+            return Some(id.clone());
+        }
+        let mut object = node(&format!("account-{}", self.shared.len()), "uco-observable:ApplicationAccount");
+        if let Some(name) = name {
+            object.insert("uco-core:name".to_string(), json!(name));
+        }
+        let mut facet = facet_node("uco-observable:AccountFacet");
+        facet.insert("uco-observable:accountIdentifier".to_string(), json!(identifier));
+        object.insert("uco-core:hasFacet".to_string(), json!([facet]));
+        let id = self.push(object);
+        self.shared.insert(key, id.clone());
+        Some(id)
+    }
+
+    fn phone_account(&mut self, number: &str, name: Option<&str>) -> String {
+        let digits: String = number.chars().filter(|c| c.is_ascii_digit() || *c == '+').collect();
+        let key = format!("phone:{}", digits);
+        if let Some(id) = self.shared.get(&key) {
+            return id.clone();
+        }
+        let mut object = node(&format!("phone-account-{}", self.shared.len()), "uco-observable:PhoneAccount");
+        if let Some(name) = name {
+            object.insert("uco-core:name".to_string(), json!(name));
+        }
+        let mut facet = facet_node("uco-observable:PhoneAccountFacet");
+        facet.insert("uco-observable:phoneNumber".to_string(), json!(number.trim()));
+        object.insert("uco-core:hasFacet".to_string(), json!([facet]));
+        let id = self.push(object);
+        self.shared.insert(key, id.clone());
+        id
+    }
+
+    fn email_address(&mut self, address: &str) -> String {
+        let key = format!("email:{}", address.trim().to_lowercase());
+        if let Some(id) = self.shared.get(&key) {
+            return id.clone();
+        }
+        let mut object = node(&format!("email-address-{}", self.shared.len()), "uco-observable:EmailAddress");
+        let mut facet = facet_node("uco-observable:EmailAddressFacet");
+        facet.insert("uco-observable:addressValue".to_string(), json!(address.trim()));
+        object.insert("uco-core:hasFacet".to_string(), json!([facet]));
+        let id = self.push(object);
+        self.shared.insert(key, id.clone());
+        id
+    }
+
+    /// The application named by the model's `Source` field.
+    fn application(&mut self, model: &Model) -> Option<String> {
+        let source = model.field_text("Source")?;
+        let key = format!("application:{}", source);
+        if let Some(id) = self.shared.get(&key) {
+            return Some(id.clone());
+        }
+        let mut object = node(&format!("application-{}", percent_encode(source)), "uco-observable:Application");
+        object.insert("uco-core:name".to_string(), json!(source));
+        let id = self.push(object);
+        self.shared.insert(key, id.clone());
+        Some(id)
+    }
+
+    /// The device, the extraction action and its provenance record; returns
+    /// the action's `@id`.
+    fn extraction(&mut self, project: &Project, info: &ExtractionInfo, device_info: Option<&DeviceInfo>, objects: Vec<String>) -> String {
+        let stem = format!("{}-{}", percent_encode(&project.id), info.id);
+        let mut device = node(&format!("device-{}", stem), "uco-observable:Device");
+        device.insert("uco-core:name".to_string(), json!(info.device_name));
+        let mut facet = facet_node("uco-observable:DeviceFacet");
+        let model = device_info.and_then(|device_info| device_info.model_number.as_deref()).unwrap_or(&info.device_name);
+        facet.insert("uco-observable:model".to_string(), json!(model));
+        let other = |needle: &str| {
+            device_info?.other.iter().find(|(name, _)| name.to_lowercase().contains(needle)).map(|(_, value)| value.clone())
+        };
+        if let Some(serial_number) = other("serial") {
+            facet.insert("uco-observable:serialNumber".to_string(), json!(serial_number));
+        }
+        if let Some(manufacturer) = device_info.and_then(|device_info| device_info.manufacturer.as_deref()) {
+            let mut identity = node(&format!("manufacturer-{}", percent_encode(manufacturer)), "uco-identity:Organization");
+            identity.insert("uco-core:name".to_string(), json!(manufacturer));
+            let id = self.push(identity);
+            facet.insert("uco-observable:manufacturer".to_string(), reference(&id));
+        }
+        let mut facets = vec![JsonValue::Object(facet)];
+        if let Some(imei) = other("imei") {
+            let mut mobile = facet_node("uco-observable:MobileDeviceFacet");
+            mobile.insert("uco-observable:IMEI".to_string(), json!(imei));
+            facets.push(JsonValue::Object(mobile));
+        }
+        if let Some(os_version) = device_info.and_then(|device_info| device_info.os_version.as_deref()) {
+            let mut operating_system = facet_node("uco-observable:OperatingSystemFacet");
+            operating_system.insert("uco-observable:version".to_string(), json!(os_version));
+            facets.push(JsonValue::Object(operating_system));
+        }
+        device.insert("uco-core:hasFacet".to_string(), JsonValue::Array(facets));
+        let device = self.push(device);
+
+        let mut record = node(&format!("provenance-record-{}", stem), "case-investigation:ProvenanceRecord");
+        record.insert("uco-core:description".to_string(), json!(format!("Objects from extraction {}", info.name)));
+        if !objects.is_empty() {
+            record.insert("uco-core:object".to_string(), JsonValue::Array(objects.iter().map(|id| reference(id)).collect()));
+        }
+        let record = self.push(record);
+
+        let mut action = node(&format!("extraction-{}", stem), "case-investigation:InvestigativeAction");
+        action.insert("uco-core:name".to_string(), json!(format!("{} extraction", info.dtype)));
+        action.insert("uco-core:description".to_string(), json!(info.name));
+        if let Some(start) = device_info.and_then(|device_info| device_info.extraction_start) {
+            action.insert("uco-action:startTime".to_string(), date_time(start));
+        }
+        if let Some(end) = device_info.and_then(|device_info| device_info.extraction_end) {
+            action.insert("uco-action:endTime".to_string(), date_time(end));
+        }
+        action.insert("uco-action:object".to_string(), json!([reference(&device)]));
+        action.insert("uco-action:result".to_string(), json!([reference(&record)]));
+        self.push(action)
+    }
+}
+
+fn node(id: &str, dtype: &str) -> Map<String, JsonValue> {
+    let mut object = Map::new();
+    object.insert("@id".to_string(), json!(format!("kb:{}", id)));
+    object.insert("@type".to_string(), json!(dtype));
+    object
+}
+
+fn observable(id: &str, dtype: &str, deleted_state: &str) -> Map<String, JsonValue> {
+    let mut object = node(id, dtype);
+    if deleted_state == "Deleted" {
+        object.insert("uco-core:tag".to_string(), json!(["Deleted"]));
+    }
+    object
+}
+
+/// Facets are blank nodes.
+fn facet_node(dtype: &str) -> Map<String, JsonValue> {
+    let mut object = Map::new();
+    object.insert("@type".to_string(), json!(dtype));
+    object
+}
+
+fn reference(id: &str) -> JsonValue {
+    json!({"@id": id})
+}
+
+fn typed<T: Into<JsonValue>>(dtype: &str, value: T) -> JsonValue {
+    json!({"@type": dtype, "@value": value.into()})
+}
+
+fn date_time(time: DateTime<FixedOffset>) -> JsonValue {
+    typed("xsd:dateTime", time.to_rfc3339())
+}
+
+/// Seconds of a `HH:MM:SS` time span, `None` if it does not fit.
+fn duration_seconds(text: &str) -> Option<u64> {
+    text.trim()
+        .split(':')
+        .try_fold(0u64, |seconds, part| seconds.checked_mul(60)?.checked_add(part.split('.').next()?.parse().ok()?))
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::models::{FileBuilder, ModelBuilder, ProjectBuilder};
+    use super::*;
+
+    fn project() -> Project {
+        let file = FileBuilder::new("/DCIM/IMG_0001.JPG")
+            .id("file-0")
+            .size(4)
+            .deleted("Deleted")
+            .timestamp("ModifyTime", "2020-07-01T08:00:00.000+00:00")
+            .metadata_item("MetaData", "MD5", "d41d8cd98f00b204e9800998ecf8427e");
+        let party = |identifier: &str, role: &str| ModelBuilder::new("Party").field("Identifier", identifier).field("Role", role);
+        ProjectBuilder::new("Test")
+            .id("project-0")
+            .extraction("Logical", "Logical", "iPhone")
+            .case_field("Case Number", "CaseNumber", "2024-001")
+            .metadata_item("Device Info", "DeviceInfoDetectedManufacturer", "Apple", Some(0))
+            .metadata_item("Device Info", "DeviceInfoOSVersion", "17.1", Some(0))
+            .metadata_item("Extraction Data", "DeviceInfoExtractionStartDateTime", "2020-08-01T10:00:00+00:00", Some(0))
+            .file(file)
+            .model(
+                ModelBuilder::new("InstantMessage")
+                    .id("message-0")
+                    .field("Source", "WhatsApp")
+                    .field("Body", "hi")
+                    .typed_field("TimeStamp", "TimeStamp", "2020-07-01T09:30:00.000+00:00")
+                    .model("From", party("+43 123", "From").field("Name", "Irene"))
+                    .models("To", "Party", [party("sherlock@example.com", "To")]),
+            )
+            .model(
+                ModelBuilder::new("Call")
+                    .id("call-0")
+                    .field("Direction", "Incoming")
+                    .typed_field("Duration", "TimeSpan", "00:01:05")
+                    .models("Parties", "Party", [party("+43-123", "From")]),
+            )
+            .model(
+                ModelBuilder::new("Contact")
+                    .id("contact-0")
+                    .field("Name", "Irene")
+                    .models("Entries", "ContactEntry", [ModelBuilder::new("PhoneNumber").field("Value", "+43123")]),
+            )
+            .build()
+    }
+
+    fn find<'a>(graph: &'a [JsonValue], id: &str) -> Result<&'a JsonValue, String> {
+        graph.iter().find(|node| node["@id"] == id).ok_or(format!("missing {}", id))
+    }
+
+    #[test]
+    fn test_case_uco_0() -> Result<(), Box<dyn std::error::Error>> {
+        let document = project_to_case(&project());
+        let graph = document["@graph"].as_array().ok_or("@graph")?;
+
+        let investigation = &graph[0];
+        assert_eq!(investigation["@type"], "case-investigation:Investigation");
+        assert_eq!(investigation["uco-core:name"], "2024-001");
+        assert_eq!(investigation["uco-core:object"], json!([{"@id": "kb:extraction-project-0-0"}]));
+
+        let action = find(graph, "kb:extraction-project-0-0")?;
+        assert_eq!(action["uco-action:startTime"], json!({"@type": "xsd:dateTime", "@value": "2020-08-01T10:00:00+00:00"}));
+        let device = find(graph, "kb:device-project-0-0")?;
+        assert_eq!(device["uco-core:hasFacet"][0]["uco-observable:manufacturer"], json!({"@id": "kb:manufacturer-Apple"}));
+        let record = find(graph, "kb:provenance-record-project-0-0")?;
+        assert_eq!(record["uco-core:object"], json!([
+            {"@id": "kb:file-file-0"}, {"@id": "kb:message-message-0"}, {"@id": "kb:call-call-0"}, {"@id": "kb:contact-contact-0"},
+        ]));
+
+        let file = find(graph, "kb:file-file-0")?;
+        assert_eq!(file["uco-core:tag"], json!(["Deleted"]));
+        assert_eq!(file["uco-core:hasFacet"][0]["uco-observable:fileName"], "IMG_0001.JPG");
+        assert_eq!(file["uco-core:hasFacet"][1]["uco-observable:hash"][0]["uco-types:hashMethod"], json!({"@type": "uco-vocabulary:HashNameVocab", "@value": "MD5"}));
+
+        // The sender, the caller and the contact's number are one account.
+        let message = find(graph, "kb:message-message-0")?;
+        let sender = &message["uco-core:hasFacet"][0]["uco-observable:from"];
+        assert_eq!(&find(graph, "kb:call-call-0")?["uco-core:hasFacet"][0]["uco-observable:from"], sender);
+        assert_eq!(&find(graph, "kb:contact-contact-0")?["uco-core:hasFacet"][0]["uco-observable:contactPhone"][0]["uco-observable:contactPhoneNumber"], sender);
+        let account = find(graph, sender["@id"].as_str().ok_or("from")?)?;
+        assert_eq!(account["@type"], "uco-observable:PhoneAccount");
+        assert_eq!(account["uco-core:name"], "Irene");
+        assert_eq!(find(graph, "kb:call-call-0")?["uco-core:hasFacet"][0]["uco-observable:duration"], json!({"@type": "xsd:integer", "@value": 65}));
+
+        // Every reference resolves to a node in the graph.
+        let ids: HashSet<&str> = graph.iter().filter_map(|node| node["@id"].as_str()).collect();
+        let mut stack: Vec<&JsonValue> = graph.iter().collect();
+        while let Some(value) = stack.pop() {
+            match value {
+                JsonValue::Object(object) if object.len() == 1 && object.contains_key("@id") => {
+                    assert!(ids.contains(object["@id"].as_str().unwrap_or_default()), "dangling {}", object["@id"]);
+                }
+                JsonValue::Object(object) => stack.extend(object.values()),
+                JsonValue::Array(values) => stack.extend(values),
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_case_uco_ids() -> Result<(), Box<dyn std::error::Error>> {
+        let project = ProjectBuilder::new("Test")
+            .extraction("Logical", "Logical", "iPhone")
+            .file(FileBuilder::new("/a").id("a/b"))
+            .file(FileBuilder::new("/b").id("a b"))
+            .file(FileBuilder::new("/c").id("a b"))
+            .build();
+        let document = project_to_case(&project);
+        let files: Vec<&str> = document["@graph"]
+            .as_array()
+            .ok_or("@graph")?
+            .iter()
+            .filter(|node| node["@type"] == "uco-observable:File")
+            .filter_map(|node| node["@id"].as_str())
+            .collect();
+        assert_eq!(files.len(), 3);
+        assert_eq!(&files[..2], ["kb:file-a%2Fb", "kb:file-a%20b"]);
+        assert!(Uuid::parse_str(files[2].trim_start_matches("kb:file-")).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_duration_seconds() {
+        assert_eq!(duration_seconds("01:01:05.5"), Some(3665));
+        assert_eq!(duration_seconds("307445734561825861:00"), None);
+        assert_eq!(duration_seconds("1:x"), None);
+    }
+}
@@ -0,0 +1,199 @@
+use std::{collections::HashMap, fs, io::Write, path::{Path, PathBuf}};
+use crate::models::{Model, Project};
+use super::{field_column, file_stem, BASE_COLUMNS};
+
+
+/// Writes one CSV file per model type. Nested models are flattened into
+/// dotted columns such as `From.Identifier`; multi-valued cells are joined
+/// with `separator`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvExporter {
+    pub separator: String,
+}
+
+impl Default for CsvExporter {
+    fn default() -> Self {
+        CsvExporter { separator: "; ".to_string() }
+    }
+}
+
+impl CsvExporter {
+    pub fn new() -> Self {
+        CsvExporter::default()
+    }
+
+    pub fn separator(mut self, separator: &str) -> Self {
+        self.separator = separator.to_string();
+        self
+    }
+
+    /// Columns of `model` in order of appearance, base columns excluded.
+    /// Fields named like a base column become `field:<name>`.
+    pub fn flatten(&self, model: &Model) -> Vec<(String, String)> {
+        let mut columns: Vec<(String, Vec<String>)> = Vec::new();
+        collect_columns(model, "", &mut columns, &mut HashMap::new());
+        columns
+            .into_iter()
+            .map(|(name, values)| (name, values.join(&self.separator)))
+            .collect()
+    }
+
+    pub fn write_models<'a, W, I>(&self, models: I, writer: W) -> Result<(), Box<dyn std::error::Error>>
+    where
+        W: Write,
+        I: IntoIterator<Item = &'a Model>,
+    {
+        let rows: Vec<(&Model, Vec<(String, String)>)> = models.into_iter().map(|model| (model, self.flatten(model))).collect();
+        let mut header: Vec<String> = BASE_COLUMNS.iter().map(|column| column.to_string()).collect();
+        let mut positions: HashMap<&str, usize> = BASE_COLUMNS.iter().enumerate().map(|(position, column)| (*column, position)).collect();
+        for (_, columns) in &rows {
+            for (name, _) in columns {
+                if !positions.contains_key(name.as_str()) {
+                    positions.insert(name, header.len());
+                    header.push(name.clone());
+                }
+            }
+        }
+        let mut csv_writer = ::csv::Writer::from_writer(writer);
+        csv_writer.write_record(&header)?;
+        for (model, columns) in &rows {
+            let mut record = vec![String::new(); header.len()];
+            record[..BASE_COLUMNS.len()].clone_from_slice(&[
+                model.id.clone(),
+                model.deleted_state.clone(),
+                model.decoding_confidence.clone(),
+                model.extraction_id.to_string(),
+            ]);
+            for (name, value) in columns {
+                record[positions[name.as_str()]].clone_from(value);
+            }
+            csv_writer.write_record(&record)?;
+        }
+        csv_writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes `<type>.csv` for every model type into `dir`, returns the
+    /// paths written.
+    pub fn export_project<P: AsRef<Path>>(&self, project: &Project, dir: P) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let mut paths: Vec<PathBuf> = Vec::new();
+        for model_type in project.decoded_data.iter().flat_map(|decoded_data| decoded_data.model_types.iter()) {
+            let path = dir.join(format!("{}.csv", file_stem(&model_type.dtype)));
+            if paths.contains(&path) {
+                return Err(format!("duplicate model type {}", model_type.dtype).into());
+            }
+            self.write_models(&model_type.models, fs::File::create(&path)?)?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
+
+/// Appends the cells of `model` to `columns`; `positions` maps column
+/// names to their index in `columns`.
+fn collect_columns(model: &Model, prefix: &str, columns: &mut Vec<(String, Vec<String>)>, positions: &mut HashMap<String, usize>) {
+    let mut push = |name: String, value: Option<&str>| {
+        let position = *positions.entry(name).or_insert_with_key(|name| {
+            columns.push((name.clone(), Vec::new()));
+            columns.len() - 1
+        });
+        if let Some(value) = value.filter(|value| !value.is_empty()) {
+            columns[position].1.push(value.to_string());
+        }
+    };
+    for field in &model.fields {
+        push(field_column(prefix, &field.name), Some(field.value_text()));
+    }
+    for multi_field in &model.multi_fields {
+        let name = field_column(prefix, &multi_field.name);
+        push(name.clone(), None);
+        for value in &multi_field.values {
+            push(name.clone(), Some(&value.text));
+        }
+    }
+    for model_field in &model.model_fields {
+        for nested in &model_field.models {
+            collect_columns(nested, &format!("{}{}.", prefix, model_field.name), columns, positions);
+        }
+    }
+    for multi_model_field in &model.multi_model_fields {
+        for nested in &multi_model_field.models {
+            collect_columns(nested, &format!("{}{}.", prefix, multi_model_field.name), columns, positions);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use quick_xml::{events::Event, Reader};
+    use crate::models::DecodedData;
+    use super::*;
+
+    fn parse_decoded_data(xml_str: &str) -> Result<DecodedData, String> {
+        let mut reader = Reader::from_reader(Cursor::new(xml_str));
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(e)) if e.name().as_ref() == b"decodedData" => {
+                    return DecodedData::parse_one(&mut reader).map_err(|e| e.to_string());
+                },
+                Ok(Event::Eof) => {
+                    return Err("eof".to_string());
+                }
+                _ => (),
+            }
+            buf.clear();
+        }
+    }
+
+    const XML_STR: &str = r#"
+    <decodedData>
+        <modelType type="InstantMessage">
+            <model type="InstantMessage" id="message-0" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                <field name="Body" type="String"><value type="String"><![CDATA[hi, there]]></value></field>
+                <modelField name="From" type="Party">
+                    <model type="Party" id="party-0" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                        <field name="Identifier" type="String"><value type="String"><![CDATA[+43123]]></value></field>
+                        <field name="Name" type="String"><value type="String"><![CDATA[Moriarty]]></value></field>
+                    </model>
+                </modelField>
+                <multiModelField name="To" type="Party">
+                    <model type="Party" id="party-1" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                        <field name="Identifier" type="String"><value type="String"><![CDATA[+43456]]></value></field>
+                    </model>
+                    <model type="Party" id="party-2" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                        <field name="Identifier" type="String"><value type="String"><![CDATA[+43789]]></value></field>
+                    </model>
+                </multiModelField>
+            </model>
+            <model type="InstantMessage" id="message-1" deleted_state="Deleted" decoding_confidence="High" isrelated="False" extractionId="1">
+                <field name="Subject" type="String"><value type="String"><![CDATA[re]]></value></field>
+                <field name="id" type="String"><value type="String"><![CDATA[42]]></value></field>
+            </model>
+        </modelType>
+    </decodedData>
+    "#;
+
+    #[test]
+    fn test_csv_exporter_0() -> Result<(), Box<dyn std::error::Error>> {
+        let decoded_data = parse_decoded_data(XML_STR)?;
+        let mut out = Vec::new();
+        CsvExporter::new().separator("|").write_models(&decoded_data.model_types[0].models, &mut out)?;
+        assert_eq!(String::from_utf8(out)?, concat!(
+            "id,deleted_state,decoding_confidence,extraction_id,Body,From.Identifier,From.Name,To.Identifier,Subject,field:id\n",
+            "message-0,Intact,High,0,\"hi, there\",+43123,Moriarty,+43456|+43789,,\n",
+            "message-1,Deleted,High,1,,,,,re,42\n",
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_stem_0() {
+        assert_eq!(file_stem("InstantMessage"), "InstantMessage");
+        assert_eq!(file_stem("../Evil Type"), "___Evil_Type");
+    }
+}
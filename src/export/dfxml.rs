@@ -0,0 +1,128 @@
+use std::{fs, io::Write, path::Path};
+use quick_xml::{events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event}, Writer};
+use crate::models::{parse_datetime, File, Project};
+use super::write_text_element;
+
+
+
+const DFXML_NAMESPACE: &str = "http://www.forensicswiki.org/wiki/Category:Digital_Forensics_XML";
+const UFDR_NAMESPACE: &str = "urn:ufdr-rs:dfxml";
+
+
+/// DFXML time elements and the `AccessInfo` timestamps they are read from.
+const TIMES: [(&str, &[&str]); 4] = [
+    ("mtime", &["ModifyTime"]),
+    ("atime", &["AccessTime"]),
+    ("ctime", &["ChangeTime", "MetadataChangeTime"]),
+    ("crtime", &["CreationTime"]),
+];
+
+
+/// Writes the tagged files as DFXML. Errors if the file exists.
+pub fn export_project<P: AsRef<Path>>(project: &Project, path: P) -> Result<(), Box<dyn std::error::Error>> {
+    let file = fs::File::create_new(path)?;
+    write_dfxml(project, std::io::BufWriter::new(file))
+}
+
+/// One `fileobject` per tagged file. Deleted files get `alloc` 0; the file
+/// id, extraction and embedded flag, which DFXML has no element for, are
+/// written in the `ufdr` namespace.
+pub fn write_dfxml<W: Write>(project: &Project, inner: W) -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer = Writer::new_with_indent(inner, b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    writer.write_event(Event::Start(BytesStart::new("dfxml").with_attributes([
+        ("xmlns", DFXML_NAMESPACE),
+        ("xmlns:dc", "http://purl.org/dc/elements/1.1/"),
+        ("xmlns:ufdr", UFDR_NAMESPACE),
+        ("version", "1.2.0"),
+    ])))?;
+    writer.write_event(Event::Start(BytesStart::new("metadata")))?;
+    write_text_element(&mut writer, "dc:type", "File listing")?;
+    writer.write_event(Event::End(BytesEnd::new("metadata")))?;
+    writer.write_event(Event::Start(BytesStart::new("creator").with_attributes([("version", "1.0")])))?;
+    write_text_element(&mut writer, "program", env!("CARGO_PKG_NAME"))?;
+    write_text_element(&mut writer, "version", env!("CARGO_PKG_VERSION"))?;
+    writer.write_event(Event::End(BytesEnd::new("creator")))?;
+    writer.write_event(Event::Start(BytesStart::new("source")))?;
+    write_text_element(&mut writer, "image_filename", &project.name)?;
+    writer.write_event(Event::End(BytesEnd::new("source")))?;
+    for file in project.tagged_files.iter().flat_map(|tagged_files| tagged_files.files.iter()) {
+        write_fileobject(&mut writer, file)?;
+    }
+    writer.write_event(Event::End(BytesEnd::new("dfxml")))?;
+    writer.get_mut().write_all(b"\n")?;
+    writer.get_mut().flush()?;
+    Ok(())
+}
+
+fn write_fileobject<W: Write>(writer: &mut Writer<W>, file: &File) -> Result<(), Box<dyn std::error::Error>> {
+    writer.write_event(Event::Start(BytesStart::new("fileobject")))?;
+    write_text_element(writer, "filename", &file.path)?;
+    write_text_element(writer, "filesize", &file.size.to_string())?;
+    write_text_element(writer, "alloc", if file.deleted == "Deleted" { "0" } else { "1" })?;
+    for (element, names) in TIMES {
+        let time = file
+            .access_info
+            .iter()
+            .flat_map(|access_info| access_info.timestamps.iter())
+            .find(|timestamp| names.contains(&timestamp.name.as_str()))
+            .and_then(|timestamp| parse_datetime(&timestamp.text));
+        if let Some(time) = time {
+            write_text_element(writer, element, &time.to_rfc3339())?;
+        }
+    }
+    for (algorithm, kind) in [("MD5", "md5"), ("SHA1", "sha1"), ("SHA256", "sha256")] {
+        if let Some(hash) = file.hash(algorithm) {
+            writer.write_event(Event::Start(BytesStart::new("hashdigest").with_attributes([("type", kind)])))?;
+            writer.write_event(Event::Text(BytesText::new(&hash.to_lowercase())))?;
+            writer.write_event(Event::End(BytesEnd::new("hashdigest")))?;
+        }
+    }
+    write_text_element(writer, "ufdr:id", &file.id)?;
+    write_text_element(writer, "ufdr:extraction_id", &file.extraction_id.to_string())?;
+    write_text_element(writer, "ufdr:embedded", if file.embedded == "True" { "1" } else { "0" })?;
+    writer.write_event(Event::End(BytesEnd::new("fileobject")))?;
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::models::{FileBuilder, ProjectBuilder};
+    use super::*;
+
+    #[test]
+    fn test_dfxml_0() -> Result<(), Box<dyn std::error::Error>> {
+        let file = FileBuilder::new("/DCIM/IMG & 0001.JPG")
+            .id("file-0")
+            .size(4)
+            .extraction_id(1)
+            .deleted("Deleted")
+            .embedded(true)
+            .timestamp("CreationTime", "2020-07-01T08:00:00.000+00:00")
+            .timestamp("ModifyTime", "2020-07-01T09:00:00.000+02:00")
+            .metadata_item("MetaData", "MD5", "D41D8CD98F00B204E9800998ECF8427E")
+            .metadata_item("MetaData", "SHA-256", "e3b0c442");
+        let project = ProjectBuilder::new("Test").file(file).build();
+        let mut out = Vec::new();
+        write_dfxml(&project, &mut out)?;
+        let xml = String::from_utf8(out)?;
+        assert!(xml.contains("<creator version=\"1.0\">\n    <program>ufdr-rs</program>"));
+        assert!(xml.contains("\
+  <fileobject>
+    <filename>/DCIM/IMG &amp; 0001.JPG</filename>
+    <filesize>4</filesize>
+    <alloc>0</alloc>
+    <mtime>2020-07-01T09:00:00+02:00</mtime>
+    <crtime>2020-07-01T08:00:00+00:00</crtime>
+    <hashdigest type=\"md5\">d41d8cd98f00b204e9800998ecf8427e</hashdigest>
+    <hashdigest type=\"sha256\">e3b0c442</hashdigest>
+    <ufdr:id>file-0</ufdr:id>
+    <ufdr:extraction_id>1</ufdr:extraction_id>
+    <ufdr:embedded>1</ufdr:embedded>
+  </fileobject>
+</dfxml>
+"));
+        Ok(())
+    }
+}
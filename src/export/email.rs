@@ -0,0 +1,463 @@
+use std::{collections::{BTreeMap, HashMap, HashSet}, fs, path::{Path, PathBuf}};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, FixedOffset, Utc};
+use crate::{models::{parse_datetime, File, Model, Project}, package::Package};
+use super::{content_type, file_stem, path_ends_with, percent_encode};
+
+
+
+/// An attachment ready to be embedded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmailAttachment {
+    /// Id of the `Attachment` model it was resolved from.
+    pub attachment_id: String,
+    pub filename: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+
+/// Rebuilds `Email` models as RFC 5322 messages, written either as one
+/// `.eml` per email or as one mbox per account. Attachments are embedded
+/// when the package holds them and listed in `X-UFDR-Missing-Attachment`
+/// headers otherwise.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EmailExporter {
+    pub mbox: bool,
+}
+
+impl EmailExporter {
+    pub fn new() -> Self {
+        EmailExporter::default()
+    }
+
+    pub fn mbox(mut self, mbox: bool) -> Self {
+        self.mbox = mbox;
+        self
+    }
+
+    /// Writes `<id>.eml` files, or `<account>.mbox` files grouped by the
+    /// `Account` field, falling back to `Source`, into `dir`. Names that
+    /// collide once made file-safe get a `-2`, `-3`, ... suffix.
+    pub fn export_project<P: AsRef<Path>>(
+        &self,
+        project: &Project,
+        mut package: Option<&mut Package>,
+        dir: P,
+    ) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let files: Vec<&File> = project.tagged_files.iter().flat_map(|tagged_files| tagged_files.files.iter()).collect();
+        let mut outputs: BTreeMap<&str, String> = BTreeMap::new();
+        let mut names = HashSet::new();
+        let mut paths = Vec::new();
+        let mut extraction_times = HashMap::new();
+        for email in project.model_graph().models().filter(|model| model.dtype == "Email") {
+            let attachments = match package.as_deref_mut() {
+                Some(package) => resolve_attachments(email, &files, package)?,
+                None => vec![],
+            };
+            let extraction_time = *extraction_times.entry(email.extraction_id).or_insert_with(|| {
+                let device_info = project.device_info(email.extraction_id)?;
+                device_info.extraction_end.or(device_info.extraction_start)
+            });
+            let message = email_to_eml(email, extraction_time, &attachments);
+            if self.mbox {
+                let account = email.field_text("Account").or(email.field_text("Source")).unwrap_or("Unknown");
+                outputs.entry(account).or_default().push_str(&mbox_entry(email, extraction_time, &message));
+            } else {
+                let path = dir.join(unique_name(&mut names, &email.id, "eml"));
+                fs::write(&path, message)?;
+                paths.push(path);
+            }
+        }
+        for (account, mbox) in outputs {
+            let path = dir.join(unique_name(&mut names, account, "mbox"));
+            fs::write(&path, mbox)?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
+
+/// `<stem>.<extension>` for `name`, suffixed until it is not in `names`.
+fn unique_name(names: &mut HashSet<String>, name: &str, extension: &str) -> String {
+    let stem = file_stem(name);
+    let mut unique = format!("{}.{}", stem, extension);
+    let mut suffix = 1;
+    while names.contains(&unique) {
+        suffix += 1;
+        unique = format!("{}-{}.{}", stem, suffix, extension);
+    }
+    names.insert(unique.clone());
+    unique
+}
+
+/// Attachments found in the package, either under the attachment's `URL`
+/// or as the tagged file whose path ends with its `Filename`, files of the
+/// email's own extraction first.
+pub fn resolve_attachments(email: &Model, files: &[&File], package: &mut Package) -> Result<Vec<EmailAttachment>, Box<dyn std::error::Error>> {
+    let mut attachments = Vec::new();
+    for attachment in attachment_models(email) {
+        let filename = attachment.field_text("Filename");
+        let local_path = attachment
+            .field_text("URL")
+            .filter(|url| package.contains(url))
+            .or_else(|| {
+                let filename = filename?;
+                files
+                    .iter()
+                    .filter(|file| path_ends_with(&file.path, filename))
+                    .min_by_key(|file| file.extraction_id != email.extraction_id)
+                    .and_then(|file| file.local_path())
+                    .filter(|local_path| package.contains(local_path))
+            });
+        if let Some(local_path) = local_path {
+            let filename = filename.unwrap_or_else(|| local_path.rsplit(['/', '\\']).next().unwrap_or(local_path));
+            attachments.push(EmailAttachment {
+                attachment_id: attachment.id.clone(),
+                filename: filename.to_string(),
+                content_type: attachment.field_text("ContentType").unwrap_or_else(|| content_type(filename)).to_string(),
+                data: package.read(local_path)?,
+            });
+        }
+    }
+    Ok(attachments)
+}
+
+fn attachment_models(email: &Model) -> impl Iterator<Item = &Model> {
+    email.nested_models("Attachments").iter().chain(email.nested_models("Attachment"))
+}
+
+/// The email as an RFC 5322 message with CRLF line endings, multipart when
+/// there are attachments. Emails without a `TimeStamp` are dated with
+/// `extraction_time`, marked by `X-UFDR-Date-Source: Extraction`, and have
+/// no `Date` only when that is unknown too.
+pub fn email_to_eml(email: &Model, extraction_time: Option<DateTime<FixedOffset>>, attachments: &[EmailAttachment]) -> String {
+    let mut headers = Vec::new();
+    for (name, field) in [("From", "From"), ("To", "To"), ("Cc", "Cc"), ("Bcc", "Bcc")] {
+        let addresses: Vec<String> = email.nested_models(field).iter().filter_map(address).collect();
+        if !addresses.is_empty() {
+            headers.push(header(name, &addresses.join(", ")));
+        }
+    }
+    if let Some(subject) = email.field_text("Subject") {
+        headers.push(header("Subject", &encode_word(subject)));
+    }
+    match email.field_text("TimeStamp").and_then(parse_datetime) {
+        Some(time) => headers.push(header("Date", &time.to_rfc2822())),
+        None => {
+            if let Some(time) = extraction_time {
+                headers.push(header("Date", &time.to_rfc2822()));
+                headers.push(header("X-UFDR-Date-Source", "Extraction"));
+            }
+        }
+    }
+    headers.push(header("Message-ID", &format!("<{}@ufdr.invalid>", file_stem(&email.id))));
+    headers.push(header("MIME-Version", "1.0"));
+    for attachment in attachment_models(email) {
+        let name = attachment.field_text("Filename").or(attachment.field_text("URL")).unwrap_or(&attachment.id);
+        if !attachments.iter().any(|found| found.attachment_id == attachment.id) {
+            headers.push(header("X-UFDR-Missing-Attachment", &encode_word(name)));
+        }
+    }
+    if email.deleted_state != "Intact" {
+        headers.push(header("X-UFDR-Deleted-State", &email.deleted_state));
+    }
+
+    let body = email.field_text("Body").unwrap_or_default();
+    let (body_headers, body) = body_part(body);
+    let mut message = headers.join("");
+    if attachments.is_empty() {
+        message.push_str(&body_headers);
+        message.push_str("\r\n");
+        message.push_str(&body);
+        return message;
+    }
+    let boundary = format!("=_ufdr_{}", file_stem(&email.id));
+    message.push_str(&header("Content-Type", &format!("multipart/mixed; boundary=\"{}\"", boundary)));
+    message.push_str("\r\nThis is a multi-part message in MIME format.\r\n");
+    message.push_str(&format!("--{}\r\n{}\r\n{}", boundary, body_headers, body));
+    for attachment in attachments {
+        message.push_str(&format!("--{}\r\n", boundary));
+        message.push_str(&header("Content-Type", &format!("{}; {}", attachment.content_type, parameter("name", &attachment.filename))));
+        message.push_str(&header("Content-Disposition", &format!("attachment; {}", parameter("filename", &attachment.filename))));
+        message.push_str(&header("Content-Transfer-Encoding", "base64"));
+        message.push_str("\r\n");
+        message.push_str(&base64_lines(&attachment.data));
+    }
+    message.push_str(&format!("--{}--\r\n", boundary));
+    message
+}
+
+/// Headers and encoded content of the text part; HTML bodies are sent as
+/// `text/html`, non-ASCII or long-lined ones in base64.
+fn body_part(body: &str) -> (String, String) {
+    let lowercase = body.trim_start().to_lowercase();
+    let subtype = if lowercase.starts_with("<!doctype html") || lowercase.starts_with("<html") { "html" } else { "plain" };
+    let mut headers = header("Content-Type", &format!("text/{}; charset=utf-8", subtype));
+    let seven_bit = body.is_ascii() && body.lines().all(|line| line.len() <= 998);
+    if seven_bit {
+        headers.push_str(&header("Content-Transfer-Encoding", "7bit"));
+        let mut text = body.lines().collect::<Vec<_>>().join("\r\n");
+        text.push_str("\r\n");
+        (headers, text)
+    } else {
+        headers.push_str(&header("Content-Transfer-Encoding", "base64"));
+        (headers, base64_lines(body.as_bytes()))
+    }
+}
+
+/// `"Name" <identifier>`, or the bare identifier.
+fn address(party: &Model) -> Option<String> {
+    let identifier = party.field_text("Identifier")?.trim();
+    match party.field_text("Name").filter(|name| *name != identifier) {
+        Some(name) if name.is_ascii() => Some(format!("\"{}\" <{}>", name.replace(['\\', '"'], ""), identifier)),
+        Some(name) => Some(format!("{} <{}>", encode_word(name), identifier)),
+        None => Some(format!("<{}>", identifier)),
+    }
+}
+
+/// RFC 2047 `=?UTF-8?B?...?=` words for non-ASCII text.
+fn encode_word(text: &str) -> String {
+    if text.is_ascii() {
+        return text.to_string();
+    }
+    let mut words = Vec::new();
+    let mut chunk = String::new();
+    for c in text.chars() {
+        if chunk.len() + c.len_utf8() > 45 {
+            words.push(format!("=?UTF-8?B?{}?=", STANDARD.encode(&chunk)));
+            chunk.clear();
+        }
+        chunk.push(c);
+    }
+    if !chunk.is_empty() {
+        words.push(format!("=?UTF-8?B?{}?=", STANDARD.encode(&chunk)));
+    }
+    words.join(" ")
+}
+
+/// `name="value"` for ASCII values, the RFC 2231 `name*=UTF-8''...` form
+/// otherwise, split into `name*0*`, `name*1*`, ... sections to keep
+/// header lines short.
+fn parameter(name: &str, value: &str) -> String {
+    if value.is_ascii() {
+        return format!("{}=\"{}\"", name, value.replace(['"', '\\'], "'"));
+    }
+    let encoded = percent_encode(value);
+    let mut sections = Vec::new();
+    let mut section = String::new();
+    let mut rest = encoded.as_str();
+    while !rest.is_empty() {
+        let length = if rest.starts_with('%') { 3 } else { 1 };
+        if section.len() + length > 60 {
+            sections.push(std::mem::take(&mut section));
+        }
+        section.push_str(&rest[..length]);
+        rest = &rest[length..];
+    }
+    sections.push(section);
+    if sections.len() == 1 {
+        return format!("{}*=UTF-8''{}", name, sections[0]);
+    }
+    sections
+        .iter()
+        .enumerate()
+        .map(|(index, section)| match index {
+            0 => format!("{}*0*=UTF-8''{}", name, section),
+            index => format!("{}*{}*={}", name, index, section),
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// `Name: value` folded at spaces to lines of at most 78 characters.
+fn header(name: &str, value: &str) -> String {
+    let value = value.replace(['\r', '\n'], " ");
+    let mut folded = format!("{}:", name);
+    let mut length = folded.len();
+    for word in value.split(' ') {
+        if length + 1 + word.len() > 78 && length > name.len() + 1 {
+            folded.push_str("\r\n");
+            length = 0;
+        }
+        folded.push(' ');
+        folded.push_str(word);
+        length += 1 + word.len();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+fn base64_lines(data: &[u8]) -> String {
+    let encoded = STANDARD.encode(data);
+    let mut lines = String::with_capacity(encoded.len() + encoded.len() / 76 * 2 + 2);
+    for chunk in encoded.as_bytes().chunks(76) {
+        lines.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        lines.push_str("\r\n");
+    }
+    lines
+}
+
+/// The message in mboxrd form: a `From ` separator line, LF line endings
+/// and `From ` lines in the body quoted with `>`. Dated like the message.
+fn mbox_entry(email: &Model, extraction_time: Option<DateTime<FixedOffset>>, message: &str) -> String {
+    let sender = email
+        .nested_models("From")
+        .first()
+        .and_then(|party| party.field_text("Identifier"))
+        .filter(|identifier| !identifier.contains(char::is_whitespace))
+        .unwrap_or("MAILER-DAEMON");
+    let time = email.field_text("TimeStamp").and_then(parse_datetime).or(extraction_time).map(|time| time.with_timezone(&Utc));
+    let date = time.map(|time| time.format("%a %b %e %H:%M:%S %Y").to_string()).unwrap_or_else(|| "Thu Jan  1 00:00:00 1970".to_string());
+    let mut entry = format!("From {} {}\n", sender, date);
+    for line in message.split("\r\n") {
+        if line.trim_start_matches('>').starts_with("From ") {
+            entry.push('>');
+        }
+        entry.push_str(line);
+        entry.push('\n');
+    }
+    entry
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::models::{FileBuilder, ModelBuilder, ProjectBuilder};
+    use super::*;
+
+    fn email(id: &str) -> ModelBuilder {
+        let party = |identifier: &str, name: &str| ModelBuilder::new("Party").field("Identifier", identifier).field("Name", name);
+        ModelBuilder::new("Email")
+            .id(id)
+            .field("Account", "irene@example.com")
+            .field("Subject", "Briony Lodge")
+            .field("Body", "Dear Sherlock,\nFrom the lodge.")
+            .typed_field("TimeStamp", "TimeStamp", "2020-07-01T07:45:53.000+00:00")
+            .model("From", party("irene@example.com", "Irene Adler"))
+            .models("To", "Party", [party("sherlock@example.com", "Sherlock Holmes")])
+            .models("Cc", "Party", [party("watson@example.com", "Dr. Jöhn Watson")])
+    }
+
+    #[test]
+    fn test_email_eml_0() {
+        let email = email("email-0").build();
+        let eml = email_to_eml(&email, None, &[]);
+        assert_eq!(eml, "\
+From: \"Irene Adler\" <irene@example.com>\r
+To: \"Sherlock Holmes\" <sherlock@example.com>\r
+Cc: =?UTF-8?B?RHIuIErDtmhuIFdhdHNvbg==?= <watson@example.com>\r
+Subject: Briony Lodge\r
+Date: Wed, 1 Jul 2020 07:45:53 +0000\r
+Message-ID: <email-0@ufdr.invalid>\r
+MIME-Version: 1.0\r
+Content-Type: text/plain; charset=utf-8\r
+Content-Transfer-Encoding: 7bit\r
+\r
+Dear Sherlock,\r
+From the lodge.\r
+");
+    }
+
+    #[test]
+    fn test_email_eml_1() {
+        let email = ModelBuilder::new("Email").id("email-0").field("Subject", "Undated").build();
+        let extraction_time = parse_datetime("2020-08-01T10:00:00+00:00");
+        let attachments = [EmailAttachment {
+            attachment_id: "attachment-0".to_string(),
+            filename: "Brief an Jöhn Watson über den Fall in der Baker Street.pdf".to_string(),
+            content_type: "application/pdf".to_string(),
+            data: b"pdf".to_vec(),
+        }];
+        let eml = email_to_eml(&email, extraction_time, &attachments);
+        assert!(eml.contains("Date: Sat, 1 Aug 2020 10:00:00 +0000\r\nX-UFDR-Date-Source: Extraction\r\n"));
+        assert!(eml.contains("\
+Content-Type: application/pdf;\r
+ name*0*=UTF-8''Brief%20an%20J%C3%B6hn%20Watson%20%C3%BCber%20den%20Fall%20i;\r
+ name*1*=n%20der%20Baker%20Street.pdf\r
+Content-Disposition: attachment;\r
+ filename*0*=UTF-8''Brief%20an%20J%C3%B6hn%20Watson%20%C3%BCber%20den%20Fall%20i;\r
+ filename*1*=n%20der%20Baker%20Street.pdf\r
+"));
+        assert!(!email_to_eml(&email, None, &[]).contains("Date:"));
+    }
+
+    #[test]
+    fn test_email_export_0() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = std::env::temp_dir().join(format!("ufdr-email-{}", std::process::id()));
+        fs::create_dir_all(dir.join("package/files/Documents"))?;
+        fs::write(dir.join("package/files/Documents/photo.jpg"), b"jpeg")?;
+        let file = FileBuilder::new("/mail/attachments/photo.jpg").id("file-0").size(4).local_path("files\\Documents\\photo.jpg");
+        let project = ProjectBuilder::new("Test")
+            .file(file)
+            .model(email("email-0").models("Attachments", "Attachment", [
+                ModelBuilder::new("Attachment").field("Filename", "photo.jpg"),
+                ModelBuilder::new("Attachment").field("Filename", "missing.pdf"),
+            ]))
+            .model(email("email-1").deleted_state("Deleted"))
+            .build();
+        let mut package = Package::open(dir.join("package"))?;
+
+        let paths = EmailExporter::new().export_project(&project, Some(&mut package), dir.join("eml"))?;
+        assert_eq!(paths, vec![dir.join("eml/email-0.eml"), dir.join("eml/email-1.eml")]);
+        let eml = fs::read_to_string(&paths[0])?;
+        assert!(eml.contains("X-UFDR-Missing-Attachment: missing.pdf\r\nContent-Type: multipart/mixed; boundary=\"=_ufdr_email-0\"\r\n"));
+        assert!(eml.contains("\
+--=_ufdr_email-0\r
+Content-Type: image/jpeg; name=\"photo.jpg\"\r
+Content-Disposition: attachment; filename=\"photo.jpg\"\r
+Content-Transfer-Encoding: base64\r
+\r
+anBlZw==\r
+--=_ufdr_email-0--\r
+"));
+        assert!(fs::read_to_string(&paths[1])?.contains("X-UFDR-Deleted-State: Deleted\r\n"));
+
+        let paths = EmailExporter::new().mbox(true).export_project(&project, None, dir.join("mbox"))?;
+        assert_eq!(paths, vec![dir.join("mbox/irene_example_com.mbox")]);
+        let mbox = fs::read_to_string(&paths[0])?;
+        assert!(mbox.starts_with("From irene@example.com Wed Jul  1 07:45:53 2020\nFrom: \"Irene Adler\""));
+        assert_eq!(mbox.matches("\n>From the lodge.\n").count(), 2);
+        assert!(mbox.contains("X-UFDR-Missing-Attachment: photo.jpg\n"));
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_email_export_1() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = std::env::temp_dir().join(format!("ufdr-email-1-{}", std::process::id()));
+        fs::create_dir_all(dir.join("package/files/Documents"))?;
+        for (name, data) in [("myphoto.jpg", "mine"), ("other.jpg", "other"), ("photo.jpg", "jpeg"), ("scan.pdf", "scan")] {
+            fs::write(dir.join("package/files/Documents").join(name), data)?;
+        }
+        let file = |path: &str, local_path: &str, extraction_id: u64| FileBuilder::new(path).extraction_id(extraction_id).local_path(local_path);
+        let project = ProjectBuilder::new("Test")
+            .file(file("/x/myphoto.jpg", "files\\Documents\\myphoto.jpg", 0))
+            .file(file("/other/photo.jpg", "files\\Documents\\other.jpg", 1))
+            .file(file("/mail/photo.jpg", "files\\Documents\\photo.jpg", 0))
+            .model(email("email_0").models("Attachments", "Attachment", [
+                ModelBuilder::new("Attachment").field("Filename", "photo.jpg"),
+                ModelBuilder::new("Attachment").field("URL", "files\\Documents\\scan.pdf"),
+                ModelBuilder::new("Attachment").field("Filename", "photo.jpg").field("URL", "files\\Documents\\gone.jpg"),
+            ]))
+            .model(ModelBuilder::new("Email").id("email.0").field("Account", "irene_example.com").field("Subject", "Second"))
+            .build();
+        let mut package = Package::open(dir.join("package"))?;
+
+        let paths = EmailExporter::new().export_project(&project, Some(&mut package), dir.join("eml"))?;
+        assert_eq!(paths, vec![dir.join("eml/email_0.eml"), dir.join("eml/email_0-2.eml")]);
+        let eml = fs::read_to_string(&paths[0])?;
+        assert_eq!(eml.matches("X-UFDR-Missing-Attachment").count(), 0);
+        assert_eq!(eml.matches("\r\nanBlZw==\r\n").count(), 2);
+        assert!(eml.contains("filename=\"scan.pdf\"\r\nContent-Transfer-Encoding: base64\r\n\r\nc2Nhbg==\r\n"));
+
+        let paths = EmailExporter::new().mbox(true).export_project(&project, None, dir.join("mbox"))?;
+        assert_eq!(paths, vec![dir.join("mbox/irene_example_com.mbox"), dir.join("mbox/irene_example_com-2.mbox")]);
+        assert!(!fs::read_to_string(&paths[0])?.contains("Subject: Second"));
+        assert!(fs::read_to_string(&paths[1])?.contains("Subject: Second"));
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,328 @@
+use std::{collections::HashSet, io::Write};
+use chrono::{DateTime, FixedOffset};
+use quick_xml::{events::{BytesDecl, BytesEnd, BytesStart, Event}, Writer};
+use serde_json::{json, Value as JsonValue};
+use crate::models::{parse_datetime, File, Model, Project};
+use super::write_text_element;
+
+
+
+/// Fields tried in order for the time of a located model.
+const TIME_FIELDS: [&str; 6] = ["TimeStamp", "StartTime", "StartDate", "LastConnection", "TimeCreated", "Captured"];
+
+
+/// A located model or tagged file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoPoint<'a> {
+    pub id: &'a str,
+    pub dtype: &'a str,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub elevation: Option<f64>,
+    pub timestamp: Option<DateTime<FixedOffset>>,
+    pub source: Option<&'a str>,
+    pub name: Option<&'a str>,
+    pub deleted_state: &'a str,
+    pub extraction_id: u64,
+}
+
+
+/// Collects models carrying `Latitude`/`Longitude` fields, directly or in a
+/// nested model such as `Position`, and tagged files with coordinates in
+/// their metadata, and writes them as GeoJSON or KML. With `since` or
+/// `until` set, points without a timestamp are left out.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GeoExporter {
+    pub since: Option<DateTime<FixedOffset>>,
+    pub until: Option<DateTime<FixedOffset>>,
+}
+
+impl GeoExporter {
+    pub fn new() -> Self {
+        GeoExporter::default()
+    }
+
+    /// Keeps points at or after `since`.
+    pub fn since(mut self, since: DateTime<FixedOffset>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Keeps points at or before `until`.
+    pub fn until(mut self, until: DateTime<FixedOffset>) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// Points in document order, models before tagged files. A coordinate
+    /// model nested in a modelField counts towards its parent.
+    pub fn points<'a>(&self, project: &'a Project) -> Vec<GeoPoint<'a>> {
+        let graph = project.model_graph();
+        let claimed: HashSet<*const Model> = graph
+            .models()
+            .flat_map(|model| model.model_fields.iter().flat_map(|model_field| model_field.models.iter()))
+            .filter(|model| coordinates(model).is_some())
+            .map(|model| model as *const Model)
+            .collect();
+
+        let mut points = Vec::new();
+        for model in graph.models().filter(|model| !claimed.contains(&(*model as *const Model))) {
+            let nested = model
+                .model_fields
+                .iter()
+                .flat_map(|model_field| model_field.models.iter())
+                .find(|nested| coordinates(nested).is_some());
+            let Some((latitude, longitude, elevation)) = coordinates(model).or_else(|| nested.and_then(coordinates)) else {
+                continue;
+            };
+            let timestamp = [Some(model), nested]
+                .into_iter()
+                .flatten()
+                .find_map(|holder| TIME_FIELDS.iter().find_map(|name| holder.field_text(name).and_then(parse_datetime)));
+            points.push(GeoPoint {
+                id: &model.id,
+                dtype: &model.dtype,
+                latitude,
+                longitude,
+                elevation,
+                timestamp,
+                source: model.field_text("Source"),
+                name: model.field_text("Name").or(model.field_text("Description")),
+                deleted_state: &model.deleted_state,
+                extraction_id: model.extraction_id,
+            });
+        }
+        for file in project.tagged_files.iter().flat_map(|tagged_files| tagged_files.files.iter()) {
+            if let Some(point) = file_point(file) {
+                points.push(point);
+            }
+        }
+        points.retain(|point| self.keeps(point));
+        points
+    }
+
+    fn keeps(&self, point: &GeoPoint) -> bool {
+        if self.since.is_none() && self.until.is_none() {
+            return true;
+        }
+        match point.timestamp {
+            Some(timestamp) => self.since.is_none_or(|since| timestamp >= since) && self.until.is_none_or(|until| timestamp <= until),
+            None => false,
+        }
+    }
+
+    /// A GeoJSON `FeatureCollection` of `Point` features.
+    pub fn to_geojson(&self, project: &Project) -> JsonValue {
+        let features: Vec<JsonValue> = self
+            .points(project)
+            .iter()
+            .map(|point| {
+                let mut coordinates = vec![point.longitude, point.latitude];
+                coordinates.extend(point.elevation);
+                json!({
+                    "type": "Feature",
+                    "geometry": {"type": "Point", "coordinates": coordinates},
+                    "properties": {
+                        "id": point.id,
+                        "type": point.dtype,
+                        "timestamp": point.timestamp.map(|timestamp| timestamp.to_rfc3339()),
+                        "source": point.source,
+                        "name": point.name,
+                        "deleted_state": point.deleted_state,
+                        "extraction_id": point.extraction_id,
+                    },
+                })
+            })
+            .collect();
+        json!({"type": "FeatureCollection", "features": features})
+    }
+
+    pub fn write_geojson<W: Write>(&self, project: &Project, mut writer: W) -> Result<(), Box<dyn std::error::Error>> {
+        serde_json::to_writer_pretty(&mut writer, &self.to_geojson(project))?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// A KML document with one `Placemark` per point, its properties in
+    /// `ExtendedData`.
+    pub fn write_kml<W: Write>(&self, project: &Project, inner: W) -> Result<(), Box<dyn std::error::Error>> {
+        let mut writer = Writer::new_with_indent(inner, b' ', 2);
+        writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+        writer.write_event(Event::Start(BytesStart::new("kml").with_attributes([("xmlns", "http://www.opengis.net/kml/2.2")])))?;
+        writer.write_event(Event::Start(BytesStart::new("Document")))?;
+        write_text_element(&mut writer, "name", &project.name)?;
+        for point in self.points(project) {
+            writer.write_event(Event::Start(BytesStart::new("Placemark")))?;
+            let name = match point.name {
+                Some(name) => format!("{}: {}", point.dtype, name),
+                None => point.dtype.to_string(),
+            };
+            write_text_element(&mut writer, "name", &name)?;
+            if let Some(timestamp) = point.timestamp {
+                writer.write_event(Event::Start(BytesStart::new("TimeStamp")))?;
+                write_text_element(&mut writer, "when", &timestamp.to_rfc3339())?;
+                writer.write_event(Event::End(BytesEnd::new("TimeStamp")))?;
+            }
+            writer.write_event(Event::Start(BytesStart::new("ExtendedData")))?;
+            let extraction_id = point.extraction_id.to_string();
+            let data = [
+                ("id", Some(point.id)),
+                ("type", Some(point.dtype)),
+                ("source", point.source),
+                ("deleted_state", Some(point.deleted_state)),
+                ("extraction_id", Some(extraction_id.as_str())),
+            ];
+            for (name, value) in data {
+                if let Some(value) = value {
+                    writer.write_event(Event::Start(BytesStart::new("Data").with_attributes([("name", name)])))?;
+                    write_text_element(&mut writer, "value", value)?;
+                    writer.write_event(Event::End(BytesEnd::new("Data")))?;
+                }
+            }
+            writer.write_event(Event::End(BytesEnd::new("ExtendedData")))?;
+            writer.write_event(Event::Start(BytesStart::new("Point")))?;
+            let coordinates = match point.elevation {
+                Some(elevation) => format!("{},{},{}", point.longitude, point.latitude, elevation),
+                None => format!("{},{}", point.longitude, point.latitude),
+            };
+            write_text_element(&mut writer, "coordinates", &coordinates)?;
+            writer.write_event(Event::End(BytesEnd::new("Point")))?;
+            writer.write_event(Event::End(BytesEnd::new("Placemark")))?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("Document")))?;
+        writer.write_event(Event::End(BytesEnd::new("kml")))?;
+        writer.get_mut().write_all(b"\n")?;
+        writer.get_mut().flush()?;
+        Ok(())
+    }
+}
+
+/// `(latitude, longitude, elevation)` from the model's own fields, if both
+/// coordinates parse and are in range.
+fn coordinates(model: &Model) -> Option<(f64, f64, Option<f64>)> {
+    let number = |name: &str| model.field_text(name).and_then(|text| text.trim().parse::<f64>().ok());
+    let elevation = number("Elevation").or_else(|| number("Altitude"));
+    valid(number("Latitude")?, number("Longitude")?).map(|(latitude, longitude)| (latitude, longitude, elevation))
+}
+
+fn valid(latitude: f64, longitude: f64) -> Option<(f64, f64)> {
+    ((-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude)).then_some((latitude, longitude))
+}
+
+/// Tagged files with `Latitude` and `Longitude` metadata items, e.g. photos
+/// with EXIF positions, timed by their `CreationTime`.
+fn file_point(file: &File) -> Option<GeoPoint<'_>> {
+    let number = |name: &str| {
+        file.metadata
+            .iter()
+            .flat_map(|metadata| metadata.items.iter())
+            .find(|item| item.name.eq_ignore_ascii_case(name))
+            .and_then(|item| item.text.trim().parse::<f64>().ok())
+    };
+    let (latitude, longitude) = valid(number("Latitude")?, number("Longitude")?)?;
+    let timestamp = file
+        .access_info
+        .iter()
+        .flat_map(|access_info| access_info.timestamps.iter())
+        .find(|timestamp| timestamp.name == "CreationTime")
+        .and_then(|timestamp| parse_datetime(&timestamp.text));
+    Some(GeoPoint {
+        id: &file.id,
+        dtype: "File",
+        latitude,
+        longitude,
+        elevation: number("Altitude"),
+        timestamp,
+        source: None,
+        name: Some(&file.path),
+        deleted_state: &file.deleted,
+        extraction_id: file.extraction_id,
+    })
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::models::{FileBuilder, ModelBuilder, ProjectBuilder};
+    use super::*;
+
+    fn project() -> Project {
+        let coordinate = |latitude: &str, longitude: &str| {
+            ModelBuilder::new("Coordinate").typed_field("Latitude", "Double", latitude).typed_field("Longitude", "Double", longitude)
+        };
+        let photo = FileBuilder::new("/DCIM/IMG_0001.JPG")
+            .id("file-0")
+            .fs("Photos")
+            .size(4)
+            .timestamp("CreationTime", "2020-07-03T10:00:00.000+00:00")
+            .metadata_item("MetaData", "Latitude", "51.5237")
+            .metadata_item("MetaData", "Longitude", "-0.1585");
+        ProjectBuilder::new("Test")
+            .model(
+                ModelBuilder::new("Location")
+                    .id("location-0")
+                    .field("Source", "Google Maps")
+                    .field("Name", "Baker Street")
+                    .typed_field("TimeStamp", "TimeStamp", "2020-07-01T10:00:00.000+00:00")
+                    .model("Position", coordinate("51.5237", "-0.1585").typed_field("Elevation", "Double", "30")),
+            )
+            .model(
+                ModelBuilder::new("WirelessNetwork")
+                    .id("wifi-0")
+                    .field("SSId", "Speedy")
+                    .model("Position", coordinate("48.2", "16.37").typed_field("TimeStamp", "TimeStamp", "2020-07-02T10:00:00.000+00:00")),
+            )
+            .model(ModelBuilder::new("CellTower").id("cell-0").typed_field("Latitude", "Double", "1").typed_field("Longitude", "Double", "2"))
+            .model(ModelBuilder::new("Location").id("location-1").model("Position", coordinate("91", "0")))
+            .file(photo)
+            .build()
+    }
+
+    #[test]
+    fn test_geo_points_0() -> Result<(), Box<dyn std::error::Error>> {
+        let project = project();
+        let ids: Vec<&str> = GeoExporter::new().points(&project).iter().map(|point| point.id).collect();
+        assert_eq!(ids, vec!["location-0", "wifi-0", "cell-0", "file-0"]);
+
+        let since = parse_datetime("2020-07-02T00:00:00+00:00").ok_or("since")?;
+        let until = parse_datetime("2020-07-02T23:59:59+00:00").ok_or("until")?;
+        let points = GeoExporter::new().since(since).until(until).points(&project);
+        assert_eq!(points.iter().map(|point| point.id).collect::<Vec<_>>(), vec!["wifi-0"]);
+        assert_eq!((points[0].latitude, points[0].longitude), (48.2, 16.37));
+        Ok(())
+    }
+
+    #[test]
+    fn test_geojson_kml_0() -> Result<(), Box<dyn std::error::Error>> {
+        let project = project();
+        let until = parse_datetime("2020-07-01T12:00:00+00:00").ok_or("until")?;
+        let exporter = GeoExporter::new().until(until);
+        assert_eq!(exporter.to_geojson(&project), json!({
+            "type": "FeatureCollection",
+            "features": [{
+                "type": "Feature",
+                "geometry": {"type": "Point", "coordinates": [-0.1585, 51.5237, 30.0]},
+                "properties": {
+                    "id": "location-0",
+                    "type": "Location",
+                    "timestamp": "2020-07-01T10:00:00+00:00",
+                    "source": "Google Maps",
+                    "name": "Baker Street",
+                    "deleted_state": "Intact",
+                    "extraction_id": 0,
+                },
+            }],
+        }));
+
+        let mut out = Vec::new();
+        exporter.write_kml(&project, &mut out)?;
+        let kml = String::from_utf8(out)?;
+        assert!(kml.contains("<name>Location: Baker Street</name>"));
+        assert!(kml.contains("<when>2020-07-01T10:00:00+00:00</when>"));
+        assert!(kml.contains("<Data name=\"source\">\n          <value>Google Maps</value>"));
+        assert!(kml.contains("<coordinates>-0.1585,51.5237,30</coordinates>"));
+        assert_eq!(kml.matches("<Placemark>").count(), 1);
+        Ok(())
+    }
+}
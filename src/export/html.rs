@@ -0,0 +1,478 @@
+use std::{collections::{HashMap, HashSet}, fmt::Write as _, fs, io, path::{Component, Path, PathBuf, Prefix}};
+use quick_xml::escape::escape;
+use crate::{models::{DeviceInfo, File, Model, NameDirectory, Project, Transcript}, package::Package};
+use super::{csv::CsvExporter, file_stem, percent_encode, transcript};
+
+
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+h1, h2 { font-weight: normal; }
+nav { margin-bottom: 1em; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.5em; text-align: left; vertical-align: top; }
+th { background: #eee; }
+.deleted { color: #a00; }
+.pages a, .pages span { margin-right: 0.5em; }
+.conversation { max-width: 50em; }
+.message { border: 1px solid #ccc; border-radius: 0.5em; padding: 0.5em; margin: 0.5em 0; }
+.message .meta { font-size: 0.8em; color: #666; }
+.message .attachment { font-size: 0.9em; font-style: italic; }
+";
+
+
+/// Renders a project as static HTML pages: an index with case, device and
+/// extraction information, one paged table per model type, one page per
+/// chat and a paged listing of tagged files. Pages embed their style and
+/// reference no external assets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmlExporter {
+    pub page_size: usize,
+    /// Prefix of links to tagged files, prepended to their `Local Path`,
+    /// e.g. a [`file_url`]. Empty when the pages are written next to the
+    /// report's `files` directory or the files are copied out of a package.
+    pub files_root: String,
+}
+
+impl Default for HtmlExporter {
+    fn default() -> Self {
+        HtmlExporter { page_size: 100, files_root: String::new() }
+    }
+}
+
+impl HtmlExporter {
+    pub fn new() -> Self {
+        HtmlExporter::default()
+    }
+
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    pub fn files_root(mut self, files_root: &str) -> Self {
+        self.files_root = files_root.to_string();
+        self
+    }
+
+    /// Writes the pages into `dir`, returns the paths written with
+    /// `index.html` first. Tagged files held by `package` are copied into
+    /// `dir` under their `Local Path` and linked relative to the pages, use
+    /// this for `.ufdr` zips whose files cannot be linked to in place.
+    pub fn export_project<P: AsRef<Path>>(
+        &self,
+        project: &Project,
+        package: Option<&mut Package>,
+        dir: P,
+    ) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let mut pages: Vec<(String, String)> = Vec::new();
+        let model_types: Vec<(&str, &[Model])> = project
+            .decoded_data
+            .iter()
+            .flat_map(|decoded_data| decoded_data.model_types.iter())
+            .map(|model_type| (model_type.dtype.as_str(), model_type.models.as_slice()))
+            .collect();
+        let files: Vec<&File> = project.tagged_files.iter().flat_map(|tagged_files| tagged_files.files.iter()).collect();
+
+        let directory = NameDirectory::from_project(project);
+
+        pages.push(("index.html".to_string(), self.index_page(project, &model_types, files.len())));
+        for (dtype, models) in &model_types {
+            pages.extend(self.model_type_pages(project, dtype, models));
+            if *dtype == "Chat" {
+                for (index, chat) in models.iter().enumerate() {
+                    pages.push((chat_page_name(index), self.chat_page(project, &Transcript::new(chat, &directory))));
+                }
+            }
+        }
+        pages.extend(self.files_pages(project, &files));
+
+        let mut paths = Vec::with_capacity(pages.len());
+        for (name, html) in pages {
+            let path = dir.join(name);
+            if paths.contains(&path) {
+                return Err(format!("duplicate page {}", path.display()).into());
+            }
+            fs::write(&path, html)?;
+            paths.push(path);
+        }
+        if let Some(package) = package {
+            let mut copied = HashSet::new();
+            for local_path in files.iter().filter_map(|file| file.local_path()) {
+                if !package.contains(local_path) || !copied.insert(local_path) {
+                    continue;
+                }
+                let bytes = package.read(local_path)?;
+                let path = dir.join(local_path.replace('\\', "/"));
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&path, bytes)?;
+                paths.push(path);
+            }
+        }
+        Ok(paths)
+    }
+
+    fn index_page(&self, project: &Project, model_types: &[(&str, &[Model])], file_count: usize) -> String {
+        let mut body = String::new();
+        let _ = write!(body, "<h1>{}</h1>", escape(&project.name));
+
+        body.push_str("<h2>Case information</h2>");
+        let case_rows: Vec<(String, String)> = project
+            .case_information
+            .fields
+            .iter()
+            .map(|field| (field.name.clone(), field.value_text().to_string()))
+            .collect();
+        body.push_str(&key_value_table(&case_rows));
+
+        body.push_str("<h2>Extractions</h2><table><tr><th>Id</th><th>Name</th><th>Type</th><th>Device</th><th>Models</th><th>Files</th></tr>");
+        for view in project.extraction_views() {
+            let _ = write!(
+                body,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                view.info.id,
+                escape(&view.info.name),
+                escape(&view.info.dtype),
+                escape(&view.info.device_name),
+                view.models.len(),
+                view.files.len(),
+            );
+        }
+        body.push_str("</table>");
+
+        for device_info in project.device_infos() {
+            let name = project
+                .source_extractions
+                .get(device_info.extraction_id)
+                .map(|info| info.name.as_str())
+                .unwrap_or_default();
+            let _ = write!(body, "<h2>Device info: {}</h2>", escape(name));
+            body.push_str(&key_value_table(&device_info_rows(&device_info)));
+        }
+
+        body.push_str("<h2>Decoded data</h2><table><tr><th>Type</th><th>Models</th></tr>");
+        for (dtype, models) in model_types {
+            let _ = write!(
+                body,
+                "<tr><td><a href=\"{}\">{}</a></td><td>{}</td></tr>",
+                escape(model_type_page_name(dtype, 1)),
+                escape(*dtype),
+                models.len(),
+            );
+        }
+        if file_count > 0 {
+            let _ = write!(body, "<tr><td><a href=\"{}\">Tagged files</a></td><td>{}</td></tr>", files_page_name(1), file_count);
+        }
+        body.push_str("</table>");
+        page(&project.name, &body)
+    }
+
+    fn model_type_pages(&self, project: &Project, dtype: &str, models: &[Model]) -> Vec<(String, String)> {
+        let exporter = CsvExporter::new();
+        let rows: Vec<Vec<(String, String)>> = models.iter().map(|model| exporter.flatten(model)).collect();
+        let mut header: Vec<&str> = Vec::new();
+        let mut positions: HashMap<&str, usize> = HashMap::new();
+        for columns in &rows {
+            for (name, _) in columns {
+                positions.entry(name).or_insert_with(|| {
+                    header.push(name);
+                    header.len() - 1
+                });
+            }
+        }
+        let cells: Vec<Vec<&str>> = rows
+            .iter()
+            .map(|columns| {
+                let mut cells = vec![""; header.len()];
+                for (name, value) in columns {
+                    cells[positions[name.as_str()]] = value;
+                }
+                cells
+            })
+            .collect();
+        let page_count = models.len().div_ceil(self.page_size).max(1);
+        let mut pages = Vec::with_capacity(page_count);
+        for page_number in 1..=page_count {
+            let start = (page_number - 1) * self.page_size;
+            let end = (start + self.page_size).min(models.len());
+            let mut body = String::new();
+            let _ = write!(body, "<nav><a href=\"index.html\">Index</a></nav><h1>{}</h1>", escape(dtype));
+            body.push_str(&pager(page_count, page_number, |n| model_type_page_name(dtype, n)));
+            body.push_str("<table><tr><th>Id</th><th>Deleted state</th><th>Extraction</th>");
+            for name in &header {
+                let _ = write!(body, "<th>{}</th>", escape(*name));
+            }
+            body.push_str("</tr>");
+            for (index, model) in models.iter().enumerate().take(end).skip(start) {
+                let id = if dtype == "Chat" {
+                    format!("<a href=\"{}\">{}</a>", chat_page_name(index), escape(&model.id))
+                } else {
+                    escape(&model.id).to_string()
+                };
+                let extraction = model.extraction(project).map(|info| info.name.as_str()).unwrap_or_default();
+                let _ = write!(
+                    body,
+                    "<tr{}><td>{}</td><td>{}</td><td>{}</td>",
+                    deleted_class(&model.deleted_state),
+                    id,
+                    escape(&model.deleted_state),
+                    escape(extraction),
+                );
+                for value in &cells[index] {
+                    let _ = write!(body, "<td>{}</td>", escape(*value));
+                }
+                body.push_str("</tr>");
+            }
+            body.push_str("</table>");
+            pages.push((model_type_page_name(dtype, page_number), page(dtype, &body)));
+        }
+        pages
+    }
+
+    fn chat_page(&self, project: &Project, transcript: &Transcript) -> String {
+        let mut body = String::new();
+        let _ = write!(
+            body,
+            "<nav><a href=\"index.html\">Index</a> &gt; <a href=\"{}\">Chat</a></nav><h1>{}</h1>",
+            escape(model_type_page_name("Chat", 1)),
+            escape(&transcript.title),
+        );
+        if let Some(info) = transcript.chat.extraction(project) {
+            let _ = write!(body, "<p>Extraction: {}</p>", escape(&info.name));
+        }
+        body.push_str(&transcript::to_html(transcript));
+        page(&transcript.title, &body)
+    }
+
+    fn files_pages(&self, project: &Project, files: &[&File]) -> Vec<(String, String)> {
+        if files.is_empty() {
+            return vec![];
+        }
+        let page_count = files.len().div_ceil(self.page_size);
+        let mut pages = Vec::with_capacity(page_count);
+        for page_number in 1..=page_count {
+            let mut body = String::new();
+            body.push_str("<nav><a href=\"index.html\">Index</a></nav><h1>Tagged files</h1>");
+            body.push_str(&pager(page_count, page_number, files_page_name));
+            body.push_str("<table><tr><th>Path</th><th>Size</th><th>Deleted</th><th>Extraction</th><th>Timestamps</th><th>MD5</th><th>SHA256</th></tr>");
+            for file in files.iter().skip((page_number - 1) * self.page_size).take(self.page_size) {
+                let path = match file.local_path() {
+                    Some(local_path) => format!("<a href=\"{}{}\">{}</a>", escape(&self.files_root), encode_path(local_path), escape(&file.path)),
+                    None => escape(&file.path).to_string(),
+                };
+                let timestamps: Vec<String> = file
+                    .access_info
+                    .iter()
+                    .flat_map(|access_info| access_info.timestamps.iter())
+                    .map(|timestamp| format!("{}: {}", escape(&timestamp.name), escape(&timestamp.text)))
+                    .collect();
+                let extraction = file.extraction(project).map(|info| info.name.as_str()).unwrap_or_default();
+                let _ = write!(
+                    body,
+                    "<tr{}><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    deleted_class(&file.deleted),
+                    path,
+                    file.size,
+                    escape(&file.deleted),
+                    escape(extraction),
+                    timestamps.join("<br>"),
+                    escape(file.hash("MD5").unwrap_or_default()),
+                    escape(file.hash("SHA256").unwrap_or_default()),
+                );
+            }
+            body.push_str("</table>");
+            pages.push((files_page_name(page_number), page("Tagged files", &body)));
+        }
+        pages
+    }
+}
+
+pub(super) fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title><style>{}</style></head><body>{}</body></html>\n",
+        escape(title),
+        STYLE,
+        body,
+    )
+}
+
+fn model_type_page_name(dtype: &str, page: usize) -> String {
+    format!("type-{}-{}.html", file_stem(dtype), page)
+}
+
+fn chat_page_name(index: usize) -> String {
+    format!("chat-{}.html", index + 1)
+}
+
+fn files_page_name(page: usize) -> String {
+    format!("files-{}.html", page)
+}
+
+/// The `file://` URL of the directory `dir`, ending in `/`, for use as
+/// [`HtmlExporter::files_root`]. Relative paths are made absolute first.
+pub fn file_url(dir: &Path) -> io::Result<String> {
+    let dir = std::path::absolute(if dir.as_os_str().is_empty() { Path::new(".") } else { dir })?;
+    let mut url = String::from("file://");
+    for component in dir.components() {
+        match component {
+            Component::Prefix(prefix) => match prefix.kind() {
+                Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => {
+                    let _ = write!(url, "/{}:", letter as char);
+                }
+                Prefix::UNC(server, share) | Prefix::VerbatimUNC(server, share) => {
+                    url.push_str(&percent_encode(&server.to_string_lossy()));
+                    url.push('/');
+                    url.push_str(&percent_encode(&share.to_string_lossy()));
+                }
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("no file URL for {}", dir.display()))),
+            },
+            Component::Normal(segment) => {
+                url.push('/');
+                url.push_str(&percent_encode(&segment.to_string_lossy()));
+            }
+            _ => {}
+        }
+    }
+    url.push('/');
+    Ok(url)
+}
+
+/// Percent-encodes each segment of a `Local Path` for use in a relative URL,
+/// backslashes becoming segment separators.
+fn encode_path(local_path: &str) -> String {
+    local_path.split(['\\', '/']).map(percent_encode).collect::<Vec<_>>().join("/")
+}
+
+
+fn deleted_class(deleted_state: &str) -> &'static str {
+    if deleted_state == "Deleted" { " class=\"deleted\"" } else { "" }
+}
+
+fn pager(page_count: usize, current: usize, name: impl Fn(usize) -> String) -> String {
+    if page_count < 2 {
+        return String::new();
+    }
+    let mut html = String::from("<div class=\"pages\">");
+    for n in 1..=page_count {
+        if n == current {
+            let _ = write!(html, "<span>{}</span>", n);
+        } else {
+            let _ = write!(html, "<a href=\"{}\">{}</a>", escape(name(n)), n);
+        }
+    }
+    html.push_str("</div>");
+    html
+}
+
+fn key_value_table(rows: &[(String, String)]) -> String {
+    let mut html = String::from("<table>");
+    for (key, value) in rows {
+        let _ = write!(html, "<tr><th>{}</th><td>{}</td></tr>", escape(key), escape(value));
+    }
+    html.push_str("</table>");
+    html
+}
+
+fn device_info_rows(device_info: &DeviceInfo) -> Vec<(String, String)> {
+    let mut rows: Vec<(String, String)> = Vec::new();
+    let mut push = |key: &str, value: Option<String>| {
+        if let Some(value) = value {
+            rows.push((key.to_string(), value));
+        }
+    };
+    push("Name", device_info.name.clone());
+    push("Manufacturer", device_info.manufacturer.clone());
+    push("Model number", device_info.model_number.clone());
+    push("OS", device_info.os_type.clone());
+    push("OS version", device_info.os_version.clone());
+    push("Baseband version", device_info.baseband_version.clone());
+    push("Time zone", device_info.time_zone.clone());
+    push("Language", device_info.locale_language.clone());
+    push("Storage capacity", device_info.storage_capacity.map(|bytes| format!("{} bytes", bytes)));
+    push("Storage available", device_info.storage_available.map(|bytes| format!("{} bytes", bytes)));
+    push("Wi-Fi address", device_info.wifi_address.map(|address| address.to_string()));
+    push("Bluetooth address", device_info.bluetooth_address.map(|address| address.to_string()));
+    push("Cloud backup", device_info.cloud_backup_enabled.map(|enabled| enabled.to_string()));
+    push("Phone date/time", device_info.phone_date_time.map(|time| time.to_rfc3339()));
+    push("Extraction start", device_info.extraction_start.map(|time| time.to_rfc3339()));
+    push("Extraction end", device_info.extraction_end.map(|time| time.to_rfc3339()));
+    rows.extend(device_info.other.iter().cloned());
+    rows
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::models::{FileBuilder, ModelBuilder, ProjectBuilder};
+    use super::*;
+
+    #[test]
+    fn test_html_exporter_0() -> Result<(), Box<dyn std::error::Error>> {
+        let party = |name: &str| ModelBuilder::new("Party").field("Identifier", "+43123").field("Name", name);
+        let chat = ModelBuilder::new("Chat")
+            .field("Name", "Baker <Street>")
+            .models("Messages", "InstantMessage", [
+                ModelBuilder::new("InstantMessage").field("Body", "hi & bye").model("From", party("Irene")),
+                ModelBuilder::new("InstantMessage").field("Body", "gone").deleted_state("Deleted"),
+            ]);
+        let project = ProjectBuilder::new("Test")
+            .extraction("Logical", "Logical", "iPhone")
+            .case_field("Case Number", "CaseNumber", "2024-001")
+            .metadata_item("Device Info", "DeviceInfoOSVersion", "17.1", Some(0))
+            .model(chat)
+            .models((0..3).map(|i| ModelBuilder::new("Contact").field("Name", &format!("contact {}", i))))
+            .file(FileBuilder::new("/DCIM/a b#1.jpg").local_path("files\\Image\\a b#1%\u{e9}.jpg"))
+            .build();
+        let dir = std::env::temp_dir().join(format!("ufdr-html-{}", std::process::id()));
+        let paths = HtmlExporter::new().page_size(2).export_project(&project, None, &dir)?;
+        let names: Vec<String> = paths.iter().filter_map(|path| path.file_name()).map(|name| name.to_string_lossy().into_owned()).collect();
+        assert_eq!(names, vec!["index.html", "type-Chat-1.html", "chat-1.html", "type-Contact-1.html", "type-Contact-2.html", "files-1.html"]);
+
+        let index = fs::read_to_string(&paths[0])?;
+        assert!(index.contains("<th>Case Number</th><td>2024-001</td>"));
+        assert!(index.contains("<th>OS version</th><td>17.1</td>"));
+        assert!(index.contains("<a href=\"type-Contact-1.html\">Contact</a>"));
+        let chat = fs::read_to_string(&paths[2])?;
+        assert!(chat.contains("<h1>Baker &lt;Street&gt;</h1>"));
+        assert!(chat.contains("Irene (+43123)"));
+        assert!(chat.contains("hi &amp; bye"));
+        assert!(chat.contains("class=\"message deleted\""));
+        let contacts = fs::read_to_string(&paths[4])?;
+        assert!(contacts.contains("contact 2") && !contacts.contains("contact 1"));
+        let files = fs::read_to_string(&paths[5])?;
+        assert!(files.contains("<a href=\"files/Image/a%20b%231%25%C3%A9.jpg\">/DCIM/a b#1.jpg</a>"));
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_html_exporter_package() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = std::env::temp_dir().join(format!("ufdr-html-package-{}", std::process::id()));
+        fs::create_dir_all(dir.join("package/files/Image"))?;
+        fs::write(dir.join("package/files/Image/a.jpg"), b"jpeg")?;
+        let project = ProjectBuilder::new("Test")
+            .extraction("Logical", "Logical", "iPhone")
+            .file(FileBuilder::new("/DCIM/a.jpg").local_path("files\\Image\\a.jpg"))
+            .file(FileBuilder::new("/DCIM/b.jpg").local_path("files\\Image\\b.jpg"))
+            .build();
+        let mut package = Package::open(dir.join("package"))?;
+        let paths = HtmlExporter::new().export_project(&project, Some(&mut package), dir.join("html"))?;
+        assert_eq!(paths.last(), Some(&dir.join("html/files/Image/a.jpg")));
+        assert_eq!(fs::read(dir.join("html/files/Image/a.jpg"))?, b"jpeg");
+        assert!(!dir.join("html/files/Image/b.jpg").exists());
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_url() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(file_url(Path::new("/tmp/a b/files"))?, "file:///tmp/a%20b/files/");
+        assert!(file_url(Path::new(""))?.starts_with("file:///"));
+        Ok(())
+    }
+}
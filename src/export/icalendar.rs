@@ -0,0 +1,354 @@
+use std::{collections::BTreeMap, fs, io::Write, path::Path};
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, Offset, TimeZone, Utc};
+use chrono_tz::{OffsetComponents, OffsetName, Tz};
+use uuid::Uuid;
+use crate::models::{parse_datetime, Model, Project};
+use super::{escape_text, fold_line};
+
+
+
+const PRODID: &str = "-//ufdr-rs//ufdr-rs//EN";
+
+
+/// Writes `CalendarEntry` models as one iCalendar `.ics` file. Errors if
+/// the file exists.
+pub fn export_project<P: AsRef<Path>>(project: &Project, path: P) -> Result<(), Box<dyn std::error::Error>> {
+    let file = fs::File::create_new(path)?;
+    write_calendar(project, std::io::BufWriter::new(file))
+}
+
+/// Writes the calendar. Times are given in the time zone from the "Device
+/// Info" of the entry's extraction, with a `VTIMEZONE` describing its
+/// offsets over the years the entries span, and in UTC when the device
+/// time zone is missing or not an IANA name. Entries without any time
+/// to take `DTSTAMP` from are stamped with the time of the export.
+pub fn write_calendar<W: Write>(project: &Project, mut writer: W) -> Result<(), Box<dyn std::error::Error>> {
+    let exported = Utc::now().fixed_offset();
+    let mut zones: BTreeMap<u64, Option<Tz>> = BTreeMap::new();
+    let mut years: BTreeMap<&str, (Tz, i32, i32)> = BTreeMap::new();
+    let mut events = Vec::new();
+    for entry in project.model_graph().models().filter(|model| model.dtype == "CalendarEntry") {
+        let zone = *zones.entry(entry.extraction_id).or_insert_with(|| device_time_zone(project, entry.extraction_id));
+        let event = event_lines(project, entry, zone, exported);
+        if let Some(zone) = zone {
+            for time in ["StartDate", "EndDate"].iter().filter_map(|name| entry.field_text(name)).filter_map(parse_datetime) {
+                let year = time.with_timezone(&zone).year();
+                let span = years.entry(zone.name()).or_insert((zone, year, year));
+                *span = (zone, span.1.min(year), span.2.max(year));
+            }
+        }
+        events.push(event);
+    }
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODID),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    for (zone, first, last) in years.into_values() {
+        lines.extend(timezone_lines(zone, first, last));
+    }
+    lines.extend(events.into_iter().flatten());
+    lines.push("END:VCALENDAR".to_string());
+    for line in lines {
+        writer.write_all(fold_line(&line).as_bytes())?;
+        writer.write_all(b"\r\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn device_time_zone(project: &Project, extraction_id: u64) -> Option<Tz> {
+    let device_info = project.device_info(extraction_id)?;
+    device_info.time_zone?.trim().parse().ok()
+}
+
+fn event_lines(project: &Project, entry: &Model, zone: Option<Tz>, exported: DateTime<FixedOffset>) -> Vec<String> {
+    let mut lines = vec!["BEGIN:VEVENT".to_string()];
+    match Uuid::parse_str(&entry.id) {
+        Ok(uuid) => lines.push(format!("UID:{}", uuid)),
+        Err(_) => lines.push(format!("UID:{}", escape_text(&entry.id))),
+    }
+
+    let start = entry.field_text("StartDate").and_then(parse_datetime);
+    let end = entry.field_text("EndDate").and_then(parse_datetime);
+    // DTSTAMP is the last revision of the entry on the device, it is
+    // required so the export time stands in when no time is known.
+    let stamp = ["TimeModified", "TimeCreated"]
+        .iter()
+        .find_map(|name| entry.field_text(name).and_then(parse_datetime))
+        .or_else(|| {
+            project.device_info(entry.extraction_id)?.extraction_end
+        })
+        .or(start)
+        .unwrap_or(exported);
+    lines.push(format!("DTSTAMP:{}", utc(stamp)));
+
+    let all_day = ["AllDay", "IsAllDay"]
+        .iter()
+        .any(|name| entry.field_text(name).is_some_and(|text| text.eq_ignore_ascii_case("true")));
+    for (property, time) in [("DTSTART", start), ("DTEND", end)] {
+        if let Some(time) = time {
+            lines.push(format!("{}{}", property, time_value(time, zone, all_day)));
+        }
+    }
+
+    if let Some(rrule) = rrule(entry, zone, all_day) {
+        lines.push(format!("RRULE:{}", rrule));
+    }
+    if let Some(summary) = entry.field_text("Subject").or(entry.field_text("Title")) {
+        lines.push(format!("SUMMARY:{}", escape_text(summary)));
+    }
+    if let Some(description) = entry.field_text("Details").or(entry.field_text("Description")) {
+        lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+    }
+    let location = entry
+        .field_text("Location")
+        .or_else(|| entry.nested_models("Location").first().and_then(|location| location.field_text("Name")));
+    if let Some(location) = location {
+        lines.push(format!("LOCATION:{}", escape_text(location)));
+    }
+    for attendee in entry.nested_models("Attendees") {
+        if let Some(line) = attendee_line(attendee) {
+            lines.push(line);
+        }
+    }
+    if let Some(category) = entry.field_text("Category") {
+        lines.push(format!("CATEGORIES:{}", escape_text(category)));
+    }
+    if let Some(source) = entry.field_text("Source") {
+        lines.push(format!("X-UFDR-SOURCE:{}", escape_text(source)));
+    }
+    if entry.deleted_state != "Intact" {
+        lines.push(format!("X-UFDR-DELETED-STATE:{}", escape_text(&entry.deleted_state)));
+    }
+    lines.push("END:VEVENT".to_string());
+    lines
+}
+
+/// The value of `DTSTART`/`DTEND` including its parameters.
+fn time_value(time: DateTime<FixedOffset>, zone: Option<Tz>, all_day: bool) -> String {
+    match (zone, all_day) {
+        (Some(zone), true) => format!(";VALUE=DATE:{}", time.with_timezone(&zone).format("%Y%m%d")),
+        (None, true) => format!(";VALUE=DATE:{}", time.format("%Y%m%d")),
+        (Some(zone), false) => format!(";TZID={}:{}", zone.name(), time.with_timezone(&zone).format("%Y%m%dT%H%M%S")),
+        (None, false) => format!(":{}", utc(time)),
+    }
+}
+
+fn utc(time: DateTime<FixedOffset>) -> String {
+    time.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// `RepeatRule` is either an RRULE already or a frequency such as `Weekly`,
+/// combined with `RepeatInterval` and `RepeatUntil`.
+fn rrule(entry: &Model, zone: Option<Tz>, all_day: bool) -> Option<String> {
+    let rule = entry.field_text("RepeatRule")?.trim();
+    if rule.to_ascii_uppercase().contains("FREQ=") {
+        return Some(rule.trim_start_matches("RRULE:").to_string());
+    }
+    let frequency = match rule.to_ascii_lowercase().as_str() {
+        "daily" => "DAILY",
+        "weekly" => "WEEKLY",
+        "monthly" => "MONTHLY",
+        "yearly" | "annually" => "YEARLY",
+        _ => return None,
+    };
+    let mut rrule = format!("FREQ={}", frequency);
+    if let Some(interval) = entry.field_text("RepeatInterval").and_then(|text| text.trim().parse::<u32>().ok()).filter(|interval| *interval > 1) {
+        rrule.push_str(&format!(";INTERVAL={}", interval));
+    }
+    if let Some(until) = entry.field_text("RepeatUntil").and_then(parse_datetime) {
+        let until = match (all_day, zone) {
+            (true, Some(zone)) => until.with_timezone(&zone).format("%Y%m%d").to_string(),
+            (true, None) => until.format("%Y%m%d").to_string(),
+            (false, _) => utc(until),
+        };
+        rrule.push_str(&format!(";UNTIL={}", until));
+    }
+    Some(rrule)
+}
+
+/// Email identifiers become `mailto:` and phone numbers `tel:` addresses;
+/// other parties are left out as they have no calendar address.
+fn attendee_line(party: &Model) -> Option<String> {
+    let identifier = party.field_text("Identifier")?.trim();
+    let address = if identifier.contains('@') {
+        format!("mailto:{}", identifier)
+    } else if identifier.chars().any(|c| c.is_ascii_digit()) && identifier.chars().all(|c| c.is_ascii_digit() || " +-().".contains(c)) {
+        format!("tel:{}", identifier.chars().filter(|c| c.is_ascii_digit() || *c == '+').collect::<String>())
+    } else {
+        return None;
+    };
+    match party.field_text("Name") {
+        Some(name) => Some(format!("ATTENDEE;CN=\"{}\":{}", name.replace('"', "'"), address)),
+        None => Some(format!("ATTENDEE:{}", address)),
+    }
+}
+
+/// A `VTIMEZONE` with the offset in force at the start of `first` and
+/// every transition up to the end of `last`.
+fn timezone_lines(zone: Tz, first: i32, last: i32) -> Vec<String> {
+    let start_of = |year: i32| NaiveDate::from_ymd_opt(year, 1, 1).and_then(|date| date.and_hms_opt(0, 0, 0)).map(|time| time.and_utc());
+    let (Some(mut time), Some(end)) = (start_of(first), start_of(last + 1)) else {
+        return vec![];
+    };
+    let mut lines = vec!["BEGIN:VTIMEZONE".to_string(), format!("TZID:{}", zone.name())];
+    let mut offset = zone.offset_from_utc_datetime(&time.naive_utc());
+    lines.extend(observance_lines(time, offset, offset));
+    while time < end {
+        let next = time + Duration::days(1);
+        let next_offset = zone.offset_from_utc_datetime(&next.naive_utc());
+        if next_offset != offset {
+            // Narrow the change down to the second.
+            let (mut before, mut after) = (time, next);
+            while after - before > Duration::seconds(1) {
+                let middle = before + (after - before) / 2;
+                if zone.offset_from_utc_datetime(&middle.naive_utc()) == offset {
+                    before = middle;
+                } else {
+                    after = middle;
+                }
+            }
+            let changed = zone.offset_from_utc_datetime(&after.naive_utc());
+            lines.extend(observance_lines(after, offset, changed));
+            offset = changed;
+        }
+        time = next;
+    }
+    lines.push("END:VTIMEZONE".to_string());
+    lines
+}
+
+fn observance_lines<O: Offset + OffsetComponents + OffsetName>(at: DateTime<Utc>, from: O, to: O) -> Vec<String> {
+    let component = if to.dst_offset().is_zero() { "STANDARD" } else { "DAYLIGHT" };
+    let local = at.with_timezone(&from.fix());
+    let mut lines = vec![
+        format!("BEGIN:{}", component),
+        format!("DTSTART:{}", local.format("%Y%m%dT%H%M%S")),
+        format!("TZOFFSETFROM:{}", utc_offset(from.fix())),
+        format!("TZOFFSETTO:{}", utc_offset(to.fix())),
+    ];
+    if let Some(name) = to.abbreviation() {
+        lines.push(format!("TZNAME:{}", escape_text(name)));
+    }
+    lines.push(format!("END:{}", component));
+    lines
+}
+
+/// `+0100`, with seconds only when the offset has them.
+fn utc_offset(offset: FixedOffset) -> String {
+    let seconds = offset.local_minus_utc();
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.abs();
+    let (hours, minutes, rest) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if rest == 0 {
+        format!("{}{:02}{:02}", sign, hours, minutes)
+    } else {
+        format!("{}{:02}{:02}{:02}", sign, hours, minutes, rest)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::models::{ModelBuilder, ProjectBuilder};
+    use super::*;
+
+    fn calendar(project: &Project) -> Result<String, Box<dyn std::error::Error>> {
+        let mut out = Vec::new();
+        write_calendar(project, &mut out)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    #[test]
+    fn test_icalendar_0() -> Result<(), Box<dyn std::error::Error>> {
+        let entry = ModelBuilder::new("CalendarEntry")
+            .id("0b9c3a52-8a0e-4d7b-8f53-6c1c0f0f7a10")
+            .field("Subject", "Meeting, Baker Street")
+            .field("Details", "Bring the violin")
+            .typed_field("StartDate", "TimeStamp", "2020-07-01T07:45:00.000+00:00")
+            .typed_field("EndDate", "TimeStamp", "2020-07-01T08:45:00.000+00:00")
+            .typed_field("TimeCreated", "TimeStamp", "2020-06-01T12:00:00.000+02:00")
+            .field("Location", "221B")
+            .field("RepeatRule", "Weekly")
+            .field("RepeatInterval", "2")
+            .typed_field("RepeatUntil", "TimeStamp", "2020-12-31T00:00:00.000+00:00")
+            .models("Attendees", "Party", [
+                ModelBuilder::new("Party").field("Identifier", "irene@example.com").field("Name", "Irene"),
+                ModelBuilder::new("Party").field("Identifier", "+43 123"),
+                ModelBuilder::new("Party").field("Identifier", "sherlock"),
+            ]);
+        let project = ProjectBuilder::new("Test")
+            .extraction("Logical", "Logical", "iPhone")
+            .metadata_item("Device Info", "DeviceInfoTimeZone", "Europe/Vienna", Some(0))
+            .model(entry)
+            .build();
+        let ics = calendar(&project)?;
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.contains("\
+BEGIN:VTIMEZONE\r
+TZID:Europe/Vienna\r
+BEGIN:STANDARD\r
+DTSTART:20200101T010000\r
+TZOFFSETFROM:+0100\r
+TZOFFSETTO:+0100\r
+TZNAME:CET\r
+END:STANDARD\r
+BEGIN:DAYLIGHT\r
+DTSTART:20200329T020000\r
+TZOFFSETFROM:+0100\r
+TZOFFSETTO:+0200\r
+TZNAME:CEST\r
+END:DAYLIGHT\r
+BEGIN:STANDARD\r
+DTSTART:20201025T030000\r
+TZOFFSETFROM:+0200\r
+TZOFFSETTO:+0100\r
+TZNAME:CET\r
+END:STANDARD\r
+END:VTIMEZONE\r
+"));
+        assert!(ics.contains("\
+BEGIN:VEVENT\r
+UID:0b9c3a52-8a0e-4d7b-8f53-6c1c0f0f7a10\r
+DTSTAMP:20200601T100000Z\r
+DTSTART;TZID=Europe/Vienna:20200701T094500\r
+DTEND;TZID=Europe/Vienna:20200701T104500\r
+RRULE:FREQ=WEEKLY;INTERVAL=2;UNTIL=20201231T000000Z\r
+SUMMARY:Meeting\\, Baker Street\r
+DESCRIPTION:Bring the violin\r
+LOCATION:221B\r
+ATTENDEE;CN=\"Irene\":mailto:irene@example.com\r
+ATTENDEE:tel:+43123\r
+END:VEVENT\r
+END:VCALENDAR\r
+"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_icalendar_utc_0() -> Result<(), Box<dyn std::error::Error>> {
+        let entry = ModelBuilder::new("CalendarEntry")
+            .id("entry-0")
+            .typed_field("StartDate", "TimeStamp", "2020-07-01T07:45:00.000+02:00")
+            .field("AllDay", "False")
+            .deleted_state("Deleted");
+        let project = ProjectBuilder::new("Test").extraction("Logical", "Logical", "iPhone").model(entry).build();
+        let ics = calendar(&project)?;
+        assert!(!ics.contains("VTIMEZONE"));
+        assert!(ics.contains("UID:entry-0\r\nDTSTAMP:20200701T054500Z\r\nDTSTART:20200701T054500Z\r\nX-UFDR-DELETED-STATE:Deleted\r\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_icalendar_dtstamp_fallback() -> Result<(), Box<dyn std::error::Error>> {
+        let entry = ModelBuilder::new("CalendarEntry").id("entry-0").field("Subject", "Undated");
+        let project = ProjectBuilder::new("Test").extraction("Logical", "Logical", "iPhone").model(entry).build();
+        let ics = calendar(&project)?;
+        let stamp = ics.split("\r\n").find_map(|line| line.strip_prefix("DTSTAMP:")).ok_or("missing DTSTAMP")?;
+        assert!(chrono::NaiveDateTime::parse_from_str(stamp, "%Y%m%dT%H%M%SZ").is_ok(), "{}", stamp);
+        Ok(())
+    }
+}
@@ -0,0 +1,196 @@
+use std::io::{BufRead, Write};
+use serde_json::{json, Map, Value as JsonValue};
+use crate::models::{ExtractionInfo, Model, Project, ReportItem, ReportStream, SourceExtractions, TypedValue};
+
+
+
+pub fn typed_value_to_json(value: &TypedValue) -> JsonValue {
+    match value {
+        TypedValue::String(s) => JsonValue::String(s.clone()),
+        TypedValue::Boolean(b) => JsonValue::Bool(*b),
+        TypedValue::Integer(i) => JsonValue::from(*i),
+        TypedValue::Float(x) => serde_json::Number::from_f64(*x).map(JsonValue::Number).unwrap_or(JsonValue::Null),
+        TypedValue::TimeStamp(t) => JsonValue::String(t.to_rfc3339()),
+    }
+}
+
+/// Flattens the fields of `model` into a `{name: value}` map. Nested models
+/// are inlined as objects, multi-valued fields as arrays.
+pub fn fields_to_json(model: &Model) -> Map<String, JsonValue> {
+    let mut fields = Map::new();
+    for field in &model.fields {
+        let value = field.typed_value().map(|value| typed_value_to_json(&value)).unwrap_or(JsonValue::Null);
+        fields.insert(field.name.clone(), value);
+    }
+    for multi_field in &model.multi_fields {
+        let values = multi_field.values.iter().map(|value| typed_value_to_json(&value.typed())).collect();
+        fields.insert(multi_field.name.clone(), JsonValue::Array(values));
+    }
+    for model_field in &model.model_fields {
+        let value = match model_field.models.as_slice() {
+            [] => JsonValue::Null,
+            [nested] => model_to_json(nested),
+            models => JsonValue::Array(models.iter().map(model_to_json).collect()),
+        };
+        fields.insert(model_field.name.clone(), value);
+    }
+    for multi_model_field in &model.multi_model_fields {
+        let models = multi_model_field.models.iter().map(model_to_json).collect();
+        fields.insert(multi_model_field.name.clone(), JsonValue::Array(models));
+    }
+    fields
+}
+
+pub fn model_to_json(model: &Model) -> JsonValue {
+    json!({
+        "type": model.dtype,
+        "id": model.id,
+        "deleted_state": model.deleted_state,
+        "decoding_confidence": model.decoding_confidence,
+        "fields": fields_to_json(model),
+    })
+}
+
+/// A top-level model as written on one line, tagged with its extraction.
+pub fn model_to_json_line(model: &Model, extraction: Option<&ExtractionInfo>) -> JsonValue {
+    json!({
+        "type": model.dtype,
+        "id": model.id,
+        "deleted_state": model.deleted_state,
+        "decoding_confidence": model.decoding_confidence,
+        "extraction_id": model.extraction_id,
+        "extraction": extraction.map(|info| info.name.as_str()),
+        "fields": fields_to_json(model),
+    })
+}
+
+pub fn write_model<W: Write>(writer: &mut W, model: &Model, extraction: Option<&ExtractionInfo>) -> Result<(), Box<dyn std::error::Error>> {
+    serde_json::to_writer(&mut *writer, &model_to_json_line(model, extraction))?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Writes every decoded model of an already parsed project, returns the
+/// number of lines written.
+pub fn export_project<W: Write>(project: &Project, writer: &mut W) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut count = 0;
+    for model_type in project.decoded_data.iter().flat_map(|decoded_data| decoded_data.model_types.iter()) {
+        for model in &model_type.models {
+            write_model(writer, model, model.extraction(project))?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Writes every decoded model while reading the report, holding a single
+/// top-level model in memory at a time.
+pub fn export_stream<B: BufRead, W: Write>(stream: ReportStream<B>, writer: &mut W) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut source_extractions = SourceExtractions { infos: vec![] };
+    let mut count = 0;
+    for item in stream {
+        match item? {
+            ReportItem::SourceExtractions(extractions) => source_extractions = extractions,
+            ReportItem::Model(model) => {
+                write_model(writer, &model, source_extractions.get(model.extraction_id))?;
+                count += 1;
+            }
+            _ => {}
+        }
+    }
+    Ok(count)
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use quick_xml::Reader;
+    use super::*;
+
+    const XML_STR: &str = r#"
+    <project id="00000000-0000-0000-0000-000000000000" name="Test" reportVersion="7.0.0.0" licenseID="0" containsGarbage="False" extractionType="Logical" NodeCount="3" ModelCount="3" xmlns="http://pa.cellebrite.com/report/2.0">
+        <sourceExtractions>
+            <extractionInfo id="0" name="Logical" isCustomName="False" type="Logical" deviceName="Report" fullName="Cellebrite UFED Reports" index="0" IsPartialData="False" />
+        </sourceExtractions>
+        <caseInformation>
+        </caseInformation>
+        <decodedData>
+            <modelType type="InstantMessage">
+                <model type="InstantMessage" id="message-0" deleted_state="Deleted" decoding_confidence="High" isrelated="False" extractionId="0">
+                    <field name="Body" type="String"><value type="String"><![CDATA[hi]]></value></field>
+                    <field name="TimeStamp" type="TimeStamp"><value type="TimeStamp">2020-07-01T07:45:53.000+00:00</value></field>
+                    <field name="UserMapping" type="Boolean"><value type="Boolean"><![CDATA[False]]></value></field>
+                    <field name="Id" type="String"><empty /></field>
+                    <modelField name="From" type="Party">
+                        <model type="Party" id="party-0" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                            <field name="Identifier" type="String"><value type="String"><![CDATA[+43123]]></value></field>
+                        </model>
+                    </modelField>
+                    <multiModelField name="To" type="Party" />
+                    <multiField name="Tags" type="String">
+                        <value type="String"><![CDATA[work]]></value>
+                    </multiField>
+                </model>
+                <model type="InstantMessage" id="message-1" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="4">
+                </model>
+            </modelType>
+        </decodedData>
+    </project>
+    "#;
+
+    #[test]
+    fn test_export_stream_0() -> Result<(), Box<dyn std::error::Error>> {
+        let stream = ReportStream::new(Reader::from_reader(Cursor::new(XML_STR)));
+        let mut out = Vec::new();
+        assert_eq!(export_stream(stream, &mut out)?, 2);
+        let out = String::from_utf8(out)?;
+        let lines: Vec<JsonValue> = out.lines().map(serde_json::from_str).collect::<Result<_, _>>()?;
+        assert_eq!(lines[0], json!({
+            "type": "InstantMessage",
+            "id": "message-0",
+            "deleted_state": "Deleted",
+            "decoding_confidence": "High",
+            "extraction_id": 0,
+            "extraction": "Logical",
+            "fields": {
+                "Body": "hi",
+                "TimeStamp": "2020-07-01T07:45:53+00:00",
+                "UserMapping": false,
+                "Id": null,
+                "Tags": ["work"],
+                "From": {
+                    "type": "Party",
+                    "id": "party-0",
+                    "deleted_state": "Intact",
+                    "decoding_confidence": "High",
+                    "fields": {"Identifier": "+43123"},
+                },
+                "To": [],
+            },
+        }));
+        assert_eq!(lines[1]["extraction"], JsonValue::Null);
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_project_0() -> Result<(), Box<dyn std::error::Error>> {
+        let mut stream_out = Vec::new();
+        export_stream(ReportStream::new(Reader::from_reader(Cursor::new(XML_STR))), &mut stream_out)?;
+        let mut reader = Reader::from_reader(Cursor::new(XML_STR));
+        let mut buf = Vec::new();
+        let project = loop {
+            match reader.read_event_into(&mut buf)? {
+                quick_xml::events::Event::Start(e) if e.name().as_ref() == b"project" => {
+                    break Project::parse_one(&e, &mut reader)?;
+                }
+                _ => (),
+            }
+            buf.clear();
+        };
+        let mut project_out = Vec::new();
+        assert_eq!(export_project(&project, &mut project_out)?, 2);
+        assert_eq!(project_out, stream_out);
+        Ok(())
+    }
+}
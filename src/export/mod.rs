@@ -0,0 +1,128 @@
+pub mod jsonl;
+pub mod csv;
+pub mod html;
+pub mod transcript;
+pub mod vcard;
+pub mod icalendar;
+pub mod geo;
+pub mod timeline;
+pub mod case_uco;
+pub mod dfxml;
+pub mod email;
+pub mod opensearch;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+
+use std::{fmt::Write as _, io::Write};
+use quick_xml::{events::{BytesEnd, BytesStart, BytesText, Event}, Writer};
+
+
+/// Columns every model row starts with in the tabular exports.
+pub(crate) const BASE_COLUMNS: [&str; 4] = ["id", "deleted_state", "decoding_confidence", "extraction_id"];
+
+/// Column of the field `name` below `prefix`. Top-level fields named like a
+/// base column are written as `field:<name>` so that neither is lost.
+pub(crate) fn field_column(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() && BASE_COLUMNS.contains(&name) {
+        format!("field:{}", name)
+    } else {
+        format!("{}{}", prefix, name)
+    }
+}
+
+/// Turns a model type or other report name into a safe file name stem.
+pub(crate) fn file_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// Percent-encodes everything but unreserved URI characters, e.g. for a
+/// URL path segment or an IRI that must stay distinct for distinct names.
+pub(crate) fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            byte => {
+                let _ = write!(encoded, "%{:02X}", byte);
+            }
+        }
+    }
+    encoded
+}
+
+/// Whether `path` ends with the path `name` at a path separator, either
+/// kind of slash accepted in both.
+pub(crate) fn path_ends_with(path: &str, name: &str) -> bool {
+    let path = path.replace('\\', "/");
+    let name = name.replace('\\', "/");
+    let name = name.trim_start_matches('/');
+    match path.strip_suffix(name) {
+        Some(rest) => !name.is_empty() && (rest.is_empty() || rest.ends_with('/')),
+        None => false,
+    }
+}
+
+/// Media type guessed from the extension of a file name or path.
+pub(crate) fn content_type(name: &str) -> &'static str {
+    let extension = name.rsplit(['.', '/', '\\']).next().unwrap_or_default().to_ascii_lowercase();
+    match extension.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "webp" => "image/webp",
+        "heic" => "image/heic",
+        "mp4" => "video/mp4",
+        "mov" => "video/quicktime",
+        "ogg" | "opus" => "audio/ogg",
+        "mp3" => "audio/mpeg",
+        "m4a" => "audio/mp4",
+        "pdf" => "application/pdf",
+        "txt" => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Escapes a text value in a vCard or iCalendar content line.
+pub(crate) fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ',' => escaped.push_str("\\,"),
+            ';' => escaped.push_str("\\;"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => (),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Splits a vCard or iCalendar content line into lines of at most 75
+/// octets, continuation lines starting with a space.
+pub(crate) fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
+
+/// `<name>text</name>`, escaped.
+pub(crate) fn write_text_element<W: Write>(writer: &mut Writer<W>, name: &str, text: &str) -> Result<(), Box<dyn std::error::Error>> {
+    writer.write_event(Event::Start(BytesStart::new(name)))?;
+    writer.write_event(Event::Text(BytesText::new(text)))?;
+    writer.write_event(Event::End(BytesEnd::new(name)))?;
+    Ok(())
+}
@@ -0,0 +1,375 @@
+use std::{collections::BTreeMap, fs, io::Write, path::{Path, PathBuf}};
+use serde_json::{json, Map, Value as JsonValue};
+use crate::models::{Model, Project, ValueKind};
+use super::{file_stem, jsonl::model_to_json_line};
+
+
+
+/// Mapping of one document property, merged over every document of an
+/// index.
+#[derive(Debug, Clone, PartialEq)]
+enum Mapping {
+    Leaf(&'static str),
+    Object(BTreeMap<String, Mapping>),
+    /// A property that is an object in some documents and a value in
+    /// others; stored but not indexed.
+    Disabled,
+}
+
+impl Mapping {
+    fn kind(kind: ValueKind) -> Self {
+        Mapping::Leaf(match kind {
+            ValueKind::String => "text",
+            ValueKind::Boolean => "boolean",
+            ValueKind::Integer => "long",
+            ValueKind::Float => "double",
+            ValueKind::TimeStamp => "date",
+        })
+    }
+
+    fn merge(&mut self, other: Mapping) {
+        match (&mut *self, other) {
+            (Mapping::Object(properties), Mapping::Object(other)) => {
+                for (name, mapping) in other {
+                    insert(properties, name, mapping);
+                }
+            }
+            (Mapping::Leaf(leaf), Mapping::Leaf(other)) if *leaf != other => *leaf = "text",
+            (Mapping::Leaf(_), Mapping::Leaf(_)) | (Mapping::Disabled, _) => {}
+            _ => *self = Mapping::Disabled,
+        }
+    }
+
+    fn to_json(&self) -> JsonValue {
+        match self {
+            Mapping::Leaf("text") => json!({"type": "text", "fields": {"keyword": {"type": "keyword", "ignore_above": 256}}}),
+            Mapping::Leaf(leaf) => json!({"type": leaf}),
+            Mapping::Object(properties) => {
+                let properties: Map<String, JsonValue> = properties.iter().map(|(name, mapping)| (name.clone(), mapping.to_json())).collect();
+                json!({"properties": properties})
+            }
+            Mapping::Disabled => json!({"type": "object", "enabled": false}),
+        }
+    }
+}
+
+fn insert(properties: &mut BTreeMap<String, Mapping>, name: String, mapping: Mapping) {
+    match properties.get_mut(&name) {
+        Some(existing) => existing.merge(mapping),
+        None => {
+            properties.insert(name, mapping);
+        }
+    }
+}
+
+
+/// Writes decoded models as OpenSearch/Elasticsearch `_bulk` requests, one
+/// index per model type named `<prefix>-<type>`. Documents are the lines of
+/// the JSONL export, with `_id` set to the model id so that re-indexing a
+/// report overwrites instead of duplicating.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenSearchExporter {
+    pub index_prefix: String,
+    /// Documents per bulk request.
+    pub batch_size: usize,
+}
+
+impl Default for OpenSearchExporter {
+    fn default() -> Self {
+        OpenSearchExporter { index_prefix: "ufdr".to_string(), batch_size: 1000 }
+    }
+}
+
+impl OpenSearchExporter {
+    pub fn new() -> Self {
+        OpenSearchExporter::default()
+    }
+
+    pub fn index_prefix(mut self, index_prefix: &str) -> Self {
+        self.index_prefix = index_prefix.to_lowercase();
+        self
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Index names must be lowercase, free of most punctuation and must not
+    /// start with `_`, `-` or `+`; the prefix is made safe like the type.
+    pub fn index_name(&self, dtype: &str) -> String {
+        let name = format!("{}-{}", file_stem(&self.index_prefix), file_stem(dtype)).to_lowercase();
+        name.trim_start_matches(['_', '-', '+']).to_string()
+    }
+
+    fn indices<'a>(&self, project: &'a Project) -> BTreeMap<String, Vec<&'a Model>> {
+        let mut indices: BTreeMap<String, Vec<&Model>> = BTreeMap::new();
+        for model_type in project.decoded_data.iter().flat_map(|decoded_data| decoded_data.model_types.iter()) {
+            for model in &model_type.models {
+                indices.entry(self.index_name(&model.dtype)).or_default().push(model);
+            }
+        }
+        indices
+    }
+
+    /// The body of the create index request of every index, keyed by index
+    /// name. Field types follow the declared `type` of their values; fields
+    /// whose values do not all parse as one type are mapped as text.
+    pub fn mappings(&self, project: &Project) -> BTreeMap<String, JsonValue> {
+        self.indices(project)
+            .into_iter()
+            .map(|(index, models)| {
+                let mut properties = BTreeMap::new();
+                for model in models {
+                    if let Mapping::Object(model_properties) = model_mapping(model) {
+                        for (name, mapping) in model_properties {
+                            insert(&mut properties, name, mapping);
+                        }
+                    }
+                    insert(&mut properties, "extraction_id".to_string(), Mapping::Leaf("long"));
+                    insert(&mut properties, "extraction".to_string(), Mapping::Leaf("keyword"));
+                }
+                (index, json!({"mappings": Mapping::Object(properties).to_json()}))
+            })
+            .collect()
+    }
+
+    /// `_bulk` request bodies of at most `batch_size` documents each.
+    pub fn bulk_requests(&self, project: &Project) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut requests = Vec::new();
+        let mut request = String::new();
+        let mut count = 0;
+        for (index, models) in self.indices(project) {
+            for model in models {
+                let action = json!({"index": {"_index": index, "_id": model.id}});
+                request.push_str(&serde_json::to_string(&action)?);
+                request.push('\n');
+                request.push_str(&serde_json::to_string(&model_to_json_line(model, model.extraction(project)))?);
+                request.push('\n');
+                count += 1;
+                if count == self.batch_size {
+                    requests.push(std::mem::take(&mut request));
+                    count = 0;
+                }
+            }
+        }
+        if !request.is_empty() {
+            requests.push(request);
+        }
+        Ok(requests)
+    }
+
+    /// Writes `<index>.mapping.json` per index and `bulk-<n>.ndjson` per
+    /// bulk request into `dir`, returns the paths written. Errors if a file
+    /// exists.
+    pub fn export_project<P: AsRef<Path>>(&self, project: &Project, dir: P) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let mut paths = Vec::new();
+        for (index, mapping) in self.mappings(project) {
+            let path = dir.join(format!("{}.mapping.json", index));
+            let mut file = fs::File::create_new(&path)?;
+            serde_json::to_writer_pretty(&mut file, &mapping)?;
+            file.write_all(b"\n")?;
+            paths.push(path);
+        }
+        for (n, request) in self.bulk_requests(project)?.into_iter().enumerate() {
+            let path = dir.join(format!("bulk-{:04}.ndjson", n));
+            fs::File::create_new(&path)?.write_all(request.as_bytes())?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    /// Creates the indices at `endpoint`, e.g. `http://localhost:9200`, and
+    /// sends the bulk requests. Existing indices are kept. Returns the
+    /// number of documents indexed; fails on the first rejected document.
+    #[cfg(feature = "opensearch")]
+    pub fn post(&self, project: &Project, endpoint: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        let endpoint = endpoint.trim_end_matches('/');
+        let agent: ureq::Agent = ureq::Agent::config_builder().http_status_as_error(false).build().into();
+        for (index, mapping) in self.mappings(project) {
+            let mut response = agent
+                .put(format!("{}/{}", endpoint, index))
+                .header("Content-Type", "application/json")
+                .send(serde_json::to_string(&mapping)?)?;
+            let status = response.status();
+            let body = response.body_mut().read_to_string()?;
+            if !status.is_success() && !body.contains("resource_already_exists_exception") {
+                return Err(format!("creating index {} failed with {}: {}", index, status, body).into());
+            }
+        }
+        let mut count = 0;
+        for request in self.bulk_requests(project)? {
+            let mut response = agent
+                .post(format!("{}/_bulk", endpoint))
+                .header("Content-Type", "application/x-ndjson")
+                .send(request)?;
+            let status = response.status();
+            let body = response.body_mut().read_to_string()?;
+            if !status.is_success() {
+                return Err(format!("bulk request failed with {}: {}", status, body).into());
+            }
+            let body: JsonValue = serde_json::from_str(&body)?;
+            let items = body["items"].as_array().map(Vec::as_slice).unwrap_or_default();
+            if body["errors"].as_bool().unwrap_or_default() {
+                let error = items
+                    .iter()
+                    .filter_map(|item| item.as_object()?.values().next()?.get("error"))
+                    .next()
+                    .map(|error| error.to_string())
+                    .unwrap_or_default();
+                return Err(format!("bulk request rejected documents: {}", error).into());
+            }
+            count += items.len();
+        }
+        Ok(count)
+    }
+}
+
+/// Mapping of a model as written by [`model_to_json`](super::jsonl::model_to_json).
+fn model_mapping(model: &Model) -> Mapping {
+    let mut fields = BTreeMap::new();
+    for field in &model.fields {
+        let dtype = field.value.as_ref().map(|value| value.dtype.as_str()).or(field.dtype.as_deref()).unwrap_or_default();
+        let mut mapping = Mapping::kind(ValueKind::from_dtype(dtype));
+        if let Some(value) = field.typed_value() {
+            mapping.merge(Mapping::kind(value.kind()));
+        }
+        insert(&mut fields, field.name.clone(), mapping);
+    }
+    for multi_field in &model.multi_fields {
+        let mut mapping = Mapping::kind(ValueKind::from_dtype(&multi_field.dtype));
+        for value in &multi_field.values {
+            mapping.merge(Mapping::kind(value.typed().kind()));
+        }
+        insert(&mut fields, multi_field.name.clone(), mapping);
+    }
+    let nested = model
+        .model_fields
+        .iter()
+        .map(|model_field| (&model_field.name, &model_field.models))
+        .chain(model.multi_model_fields.iter().map(|multi_model_field| (&multi_model_field.name, &multi_model_field.models)));
+    for (name, models) in nested {
+        for nested in models {
+            insert(&mut fields, name.clone(), model_mapping(nested));
+        }
+    }
+
+    let mut properties = BTreeMap::new();
+    for name in ["type", "id", "deleted_state", "decoding_confidence"] {
+        properties.insert(name.to_string(), Mapping::Leaf("keyword"));
+    }
+    properties.insert("fields".to_string(), Mapping::Object(fields));
+    Mapping::Object(properties)
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::models::{ModelBuilder, ProjectBuilder};
+    use super::*;
+
+    fn project() -> Project {
+        ProjectBuilder::new("Test")
+            .model(
+                ModelBuilder::new("InstantMessage")
+                    .id("message-0")
+                    .field("Body", "hi")
+                    .typed_field("TimeStamp", "TimeStamp", "2020-07-01T09:30:00.000+00:00")
+                    .typed_field("Count", "Int32", "3")
+                    .model("From", ModelBuilder::new("Party").id("party-0").field("Identifier", "+15551234")),
+            )
+            .model(ModelBuilder::new("InstantMessage").id("message-1").typed_field("Count", "Int32", "n/a"))
+            .model(ModelBuilder::new("Call Log").id("call-0").typed_field("Duration", "TimeSpan", "00:01:00"))
+            .build()
+    }
+
+    #[test]
+    fn test_opensearch_mappings_0() {
+        let mappings = OpenSearchExporter::new().mappings(&project());
+        assert_eq!(mappings.keys().collect::<Vec<_>>(), ["ufdr-call_log", "ufdr-instantmessage"]);
+        let properties = &mappings["ufdr-instantmessage"]["mappings"]["properties"];
+        assert_eq!(properties["id"], json!({"type": "keyword"}));
+        assert_eq!(properties["extraction_id"], json!({"type": "long"}));
+        let fields = &properties["fields"]["properties"];
+        assert_eq!(fields["TimeStamp"], json!({"type": "date"}));
+        assert_eq!(fields["Count"]["type"], "text");
+        assert_eq!(fields["From"]["properties"]["fields"]["properties"]["Identifier"]["fields"]["keyword"]["type"], "keyword");
+    }
+
+    #[test]
+    fn test_opensearch_bulk_0() -> Result<(), Box<dyn std::error::Error>> {
+        let requests = OpenSearchExporter::new().index_prefix("Case-1").batch_size(2).bulk_requests(&project())?;
+        assert_eq!(requests.len(), 2);
+        let lines: Vec<JsonValue> = requests.iter().flat_map(|request| request.lines()).map(serde_json::from_str).collect::<Result<_, _>>()?;
+        assert_eq!(lines[0], json!({"index": {"_index": "case-1-call_log", "_id": "call-0"}}));
+        assert_eq!(lines[1]["fields"]["Duration"], "00:01:00");
+        assert_eq!(lines[4], json!({"index": {"_index": "case-1-instantmessage", "_id": "message-1"}}));
+        assert_eq!(lines[5]["fields"]["Count"], "n/a");
+        Ok(())
+    }
+
+    #[test]
+    fn test_opensearch_index_name() {
+        assert_eq!(OpenSearchExporter::new().index_prefix("My Case #1").index_name("Call Log"), "my_case__1-call_log");
+        assert_eq!(OpenSearchExporter::new().index_prefix("_-+case").index_name("Chat"), "case-chat");
+        assert_eq!(OpenSearchExporter::new().index_prefix("").index_name("Chat"), "chat");
+    }
+
+    #[cfg(feature = "opensearch")]
+    #[test]
+    fn test_opensearch_post_0() -> Result<(), Box<dyn std::error::Error>> {
+        use std::{io::{BufRead, BufReader, Read}, net::TcpListener, sync::mpsc};
+
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let endpoint = format!("http://{}/", listener.local_addr()?);
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { break };
+                let mut reader = BufReader::new(stream);
+                let mut request_line = String::new();
+                let mut content_length = 0;
+                let mut line = String::new();
+                reader.read_line(&mut request_line).ok();
+                while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_length = value.trim().parse().unwrap_or_default();
+                        }
+                    }
+                    line.clear();
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).ok();
+                let response = if request_line.starts_with("PUT /ufdr-call_log ") {
+                    r#"{"error":{"type":"resource_already_exists_exception"},"status":400}"#.to_string()
+                } else if request_line.starts_with("PUT ") {
+                    r#"{"acknowledged":true}"#.to_string()
+                } else {
+                    let items = String::from_utf8_lossy(&body).lines().step_by(2).map(|_| r#"{"index":{"status":201}}"#).collect::<Vec<_>>().join(",");
+                    format!(r#"{{"errors":false,"items":[{}]}}"#, items)
+                };
+                let status = if response.contains("exception") { "400 Bad Request" } else { "200 OK" };
+                sender.send((request_line.trim_end().to_string(), String::from_utf8_lossy(&body).to_string())).ok();
+                let mut stream = reader.into_inner();
+                write!(stream, "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, response.len(), response).ok();
+            }
+        });
+
+        let count = OpenSearchExporter::new().batch_size(2).post(&project(), &endpoint)?;
+        assert_eq!(count, 3);
+        let requests: Vec<(String, String)> = receiver.try_iter().collect();
+        let request_lines: Vec<&str> = requests.iter().map(|(request_line, _)| request_line.as_str()).collect();
+        assert_eq!(request_lines, [
+            "PUT /ufdr-call_log HTTP/1.1",
+            "PUT /ufdr-instantmessage HTTP/1.1",
+            "POST /_bulk HTTP/1.1",
+            "POST /_bulk HTTP/1.1",
+        ]);
+        assert!(requests[1].1.starts_with(r#"{"mappings":{"properties":{"decoding_confidence""#));
+        assert_eq!(requests[3].1.lines().count(), 2);
+        Ok(())
+    }
+}
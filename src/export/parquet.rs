@@ -0,0 +1,471 @@
+use std::{collections::{HashMap, HashSet}, fs, io::BufRead, path::{Path, PathBuf}, sync::Arc};
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, Int64Array, ListArray, RecordBatch, StringArray,
+    TimestampMicrosecondArray, UInt64Array,
+};
+use arrow_buffer::OffsetBuffer;
+use arrow_schema::{DataType, Field as ArrowField, Schema, TimeUnit};
+use parquet::arrow::ArrowWriter;
+use crate::models::{parse_datetime, File, Model, ReportItem, ReportStream, TypedValue, ValueKind};
+use super::{field_column, file_stem};
+
+
+
+const TAGGED_FILES: &str = "tagged_files";
+
+/// A column of a model type table. Columns below a `multiModelField`, and
+/// `multiField`s, are lists.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnSpec {
+    pub name: String,
+    pub kind: ValueKind,
+    pub list: bool,
+}
+
+impl ColumnSpec {
+    fn data_type(&self) -> DataType {
+        let item = match self.kind {
+            ValueKind::String => DataType::Utf8,
+            ValueKind::Boolean => DataType::Boolean,
+            ValueKind::Integer => DataType::Int64,
+            ValueKind::Float => DataType::Float64,
+            ValueKind::TimeStamp => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        };
+        if self.list {
+            DataType::List(Arc::new(ArrowField::new("item", item, true)))
+        } else {
+            item
+        }
+    }
+}
+
+
+/// Writes one Parquet file per model type plus `tagged_files.parquet`.
+///
+/// The report is read twice: a first pass discovers the columns and their
+/// types, the second one writes record batches of `batch_size` rows, so
+/// only one batch per model type is held in memory. Nested models are
+/// flattened into dotted columns as in the CSV export, fields named like a
+/// base column becoming `field:<name>`. Values that do not parse as their
+/// column type are written as nulls.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParquetExporter {
+    pub batch_size: usize,
+}
+
+impl Default for ParquetExporter {
+    fn default() -> Self {
+        ParquetExporter { batch_size: 1024 }
+    }
+}
+
+impl ParquetExporter {
+    pub fn new() -> Self {
+        ParquetExporter::default()
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Exports the report at `report` into `dir`, returns the paths written.
+    pub fn export_path<P: AsRef<Path>, Q: AsRef<Path>>(&self, report: P, dir: Q) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let report = report.as_ref();
+        self.export(|| ReportStream::from_path(report), dir)
+    }
+
+    /// Same as [`ParquetExporter::export_path`], with `open` called once per
+    /// pass to get a fresh stream over the report.
+    pub fn export<B, F, Q>(&self, mut open: F, dir: Q) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>>
+    where
+        B: BufRead,
+        F: FnMut() -> Result<ReportStream<B>, Box<dyn std::error::Error>>,
+        Q: AsRef<Path>,
+    {
+        let dir = dir.as_ref();
+        // Columns per model type in order of first appearance, each with an
+        // index from column name to position.
+        let mut model_columns: Vec<(String, Vec<ColumnSpec>, HashMap<String, usize>)> = Vec::new();
+        let mut type_positions: HashMap<String, usize> = HashMap::new();
+        let mut timestamp_names: Vec<String> = Vec::new();
+        let mut seen_timestamps: HashSet<String> = HashSet::new();
+        let mut has_files = false;
+        for item in open()? {
+            match item? {
+                ReportItem::Model(model) => {
+                    let position = *type_positions.entry(model.dtype.clone()).or_insert_with(|| {
+                        model_columns.push((model.dtype.clone(), Vec::new(), HashMap::new()));
+                        model_columns.len() - 1
+                    });
+                    let (_, columns, positions) = &mut model_columns[position];
+                    for (name, kind, list, _) in cells(&model) {
+                        match positions.get(&name) {
+                            Some(&position) => {
+                                let column = &mut columns[position];
+                                if column.kind != kind {
+                                    column.kind = ValueKind::String;
+                                }
+                                column.list |= list;
+                            }
+                            None => {
+                                positions.insert(name.clone(), columns.len());
+                                columns.push(ColumnSpec { name, kind, list });
+                            }
+                        }
+                    }
+                }
+                ReportItem::File(file) => {
+                    has_files = true;
+                    for timestamp in file.access_info.iter().flat_map(|access_info| access_info.timestamps.iter()) {
+                        if seen_timestamps.insert(timestamp.name.clone()) {
+                            timestamp_names.push(timestamp.name.clone());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        fs::create_dir_all(dir)?;
+        let mut paths: Vec<PathBuf> = Vec::new();
+        let mut tables: HashMap<String, Table<Model>> = HashMap::new();
+        for (dtype, columns, _) in model_columns {
+            let path = dir.join(format!("{}.parquet", file_stem(&dtype)));
+            if paths.contains(&path) || file_stem(&dtype) == TAGGED_FILES {
+                return Err(format!("duplicate model type {}", dtype).into());
+            }
+            tables.insert(dtype, Table::create(&path, model_schema(&columns), columns)?);
+            paths.push(path);
+        }
+        let mut files_table = if has_files {
+            let path = dir.join(format!("{}.parquet", TAGGED_FILES));
+            let columns: Vec<ColumnSpec> = timestamp_names
+                .into_iter()
+                .map(|name| ColumnSpec { name, kind: ValueKind::TimeStamp, list: false })
+                .collect();
+            let table = Table::create(&path, files_schema(&columns), columns)?;
+            paths.push(path);
+            Some(table)
+        } else {
+            None
+        };
+
+        for item in open()? {
+            match item? {
+                ReportItem::Model(model) => {
+                    let table = tables.get_mut(&model.dtype).ok_or("model type missing from the first pass")?;
+                    table.rows.push(model);
+                    if table.rows.len() >= self.batch_size {
+                        table.flush(model_batch)?;
+                    }
+                }
+                ReportItem::File(file) => {
+                    let table = files_table.as_mut().ok_or("tagged files missing from the first pass")?;
+                    table.rows.push(file);
+                    if table.rows.len() >= self.batch_size {
+                        table.flush(files_batch)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        for (_, table) in tables {
+            table.close(model_batch)?;
+        }
+        if let Some(table) = files_table {
+            table.close(files_batch)?;
+        }
+        Ok(paths)
+    }
+}
+
+
+struct Table<T> {
+    writer: ArrowWriter<fs::File>,
+    schema: Arc<Schema>,
+    columns: Vec<ColumnSpec>,
+    rows: Vec<T>,
+}
+
+type BatchFn<T> = fn(&Arc<Schema>, &[ColumnSpec], &[T]) -> Result<RecordBatch, Box<dyn std::error::Error>>;
+
+impl<T> Table<T> {
+    fn create(path: &Path, schema: Arc<Schema>, columns: Vec<ColumnSpec>) -> Result<Self, Box<dyn std::error::Error>> {
+        let writer = ArrowWriter::try_new(fs::File::create(path)?, schema.clone(), None)?;
+        Ok(Table { writer, schema, columns, rows: Vec::new() })
+    }
+
+    fn flush(&mut self, batch: BatchFn<T>) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.rows.is_empty() {
+            self.writer.write(&batch(&self.schema, &self.columns, &self.rows)?)?;
+            self.rows.clear();
+        }
+        Ok(())
+    }
+
+    fn close(mut self, batch: BatchFn<T>) -> Result<(), Box<dyn std::error::Error>> {
+        self.flush(batch)?;
+        self.writer.close()?;
+        Ok(())
+    }
+}
+
+/// Flattened `(column, kind, list, values)` cells of `model`, in order of
+/// appearance.
+fn cells(model: &Model) -> Vec<(String, ValueKind, bool, Vec<TypedValue>)> {
+    type Cells = Vec<(String, ValueKind, bool, Vec<TypedValue>)>;
+    fn push(cells: &mut Cells, positions: &mut HashMap<String, usize>, name: String, kind: ValueKind, list: bool, value: Option<TypedValue>) {
+        let position = *positions.entry(name).or_insert_with_key(|name| {
+            cells.push((name.clone(), kind, list, Vec::new()));
+            cells.len() - 1
+        });
+        let cell = &mut cells[position];
+        if let Some(value) = value {
+            cell.3.push(value);
+        }
+        cell.2 |= list || cell.3.len() > 1;
+    }
+    fn collect(model: &Model, prefix: &str, list: bool, cells: &mut Cells, positions: &mut HashMap<String, usize>) {
+        for field in &model.fields {
+            let dtype = field.value.as_ref().map(|value| value.dtype.as_str()).or(field.dtype.as_deref()).unwrap_or_default();
+            push(cells, positions, field_column(prefix, &field.name), ValueKind::from_dtype(dtype), list, field.typed_value());
+        }
+        for multi_field in &model.multi_fields {
+            let name = field_column(prefix, &multi_field.name);
+            let kind = ValueKind::from_dtype(&multi_field.dtype);
+            push(cells, positions, name.clone(), kind, true, None);
+            for value in &multi_field.values {
+                push(cells, positions, name.clone(), kind, true, Some(value.typed()));
+            }
+        }
+        for model_field in &model.model_fields {
+            for nested in &model_field.models {
+                collect(nested, &format!("{}{}.", prefix, model_field.name), list, cells, positions);
+            }
+        }
+        for multi_model_field in &model.multi_model_fields {
+            for nested in &multi_model_field.models {
+                collect(nested, &format!("{}{}.", prefix, multi_model_field.name), true, cells, positions);
+            }
+        }
+    }
+    let mut cells = Vec::new();
+    collect(model, "", false, &mut cells, &mut HashMap::new());
+    cells
+}
+
+fn model_schema(columns: &[ColumnSpec]) -> Arc<Schema> {
+    let mut fields = vec![
+        ArrowField::new("id", DataType::Utf8, false),
+        ArrowField::new("deleted_state", DataType::Utf8, false),
+        ArrowField::new("decoding_confidence", DataType::Utf8, false),
+        ArrowField::new("extraction_id", DataType::UInt64, false),
+    ];
+    fields.extend(columns.iter().map(|column| ArrowField::new(&column.name, column.data_type(), true)));
+    Arc::new(Schema::new(fields))
+}
+
+fn files_schema(timestamp_columns: &[ColumnSpec]) -> Arc<Schema> {
+    let mut fields = vec![
+        ArrowField::new("id", DataType::Utf8, false),
+        ArrowField::new("fs", DataType::Utf8, false),
+        ArrowField::new("fsid", DataType::Utf8, false),
+        ArrowField::new("path", DataType::Utf8, false),
+        ArrowField::new("size", DataType::UInt64, false),
+        ArrowField::new("extraction_id", DataType::UInt64, false),
+        ArrowField::new("deleted", DataType::Utf8, false),
+        ArrowField::new("embedded", DataType::Utf8, false),
+        ArrowField::new("is_related", DataType::Utf8, false),
+        ArrowField::new("md5", DataType::Utf8, true),
+        ArrowField::new("sha1", DataType::Utf8, true),
+        ArrowField::new("sha256", DataType::Utf8, true),
+    ];
+    fields.extend(timestamp_columns.iter().map(|column| ArrowField::new(&column.name, column.data_type(), true)));
+    Arc::new(Schema::new(fields))
+}
+
+/// Builds a non-list array of `kind`; values of another kind become nulls,
+/// except for string columns which take any value as text.
+fn array(kind: ValueKind, values: Vec<Option<TypedValue>>) -> ArrayRef {
+    match kind {
+        ValueKind::String => Arc::new(StringArray::from_iter(values.into_iter().map(|value| value.map(|value| match value {
+            TypedValue::String(s) => s,
+            other => other.to_string(),
+        })))),
+        ValueKind::Boolean => Arc::new(BooleanArray::from_iter(values.into_iter().map(|value| match value {
+            Some(TypedValue::Boolean(b)) => Some(b),
+            _ => None,
+        }))),
+        ValueKind::Integer => Arc::new(Int64Array::from_iter(values.into_iter().map(|value| match value {
+            Some(TypedValue::Integer(i)) => Some(i),
+            _ => None,
+        }))),
+        ValueKind::Float => Arc::new(Float64Array::from_iter(values.into_iter().map(|value| match value {
+            Some(TypedValue::Float(x)) => Some(x),
+            Some(TypedValue::Integer(i)) => Some(i as f64),
+            _ => None,
+        }))),
+        ValueKind::TimeStamp => Arc::new(TimestampMicrosecondArray::from_iter(values.into_iter().map(|value| match value {
+            Some(TypedValue::TimeStamp(t)) => Some(t.timestamp_micros()),
+            _ => None,
+        })).with_timezone("UTC")),
+    }
+}
+
+fn column_array(column: &ColumnSpec, mut values: Vec<Vec<TypedValue>>) -> Result<ArrayRef, Box<dyn std::error::Error>> {
+    if !column.list {
+        return Ok(array(column.kind, values.into_iter().map(|mut cell| cell.pop()).collect()));
+    }
+    let offsets = OffsetBuffer::from_lengths(values.iter().map(Vec::len));
+    let items = array(column.kind, values.iter_mut().flat_map(|cell| cell.drain(..)).map(Some).collect());
+    let DataType::List(item_field) = column.data_type() else {
+        unreachable!()
+    };
+    Ok(Arc::new(ListArray::try_new(item_field, offsets, items, None)?))
+}
+
+fn model_batch(schema: &Arc<Schema>, columns: &[ColumnSpec], models: &[Model]) -> Result<RecordBatch, Box<dyn std::error::Error>> {
+    let mut arrays: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(models.iter().map(|model| model.id.as_str()))),
+        Arc::new(StringArray::from_iter_values(models.iter().map(|model| model.deleted_state.as_str()))),
+        Arc::new(StringArray::from_iter_values(models.iter().map(|model| model.decoding_confidence.as_str()))),
+        Arc::new(UInt64Array::from_iter_values(models.iter().map(|model| model.extraction_id))),
+    ];
+    let positions: HashMap<&str, usize> = columns.iter().enumerate().map(|(position, column)| (column.name.as_str(), position)).collect();
+    let mut table: Vec<Vec<Vec<TypedValue>>> = vec![vec![Vec::new(); models.len()]; columns.len()];
+    for (row, model) in models.iter().enumerate() {
+        for (name, _, _, values) in cells(model) {
+            if let Some(&position) = positions.get(name.as_str()) {
+                table[position][row] = values;
+            }
+        }
+    }
+    for (column, values) in columns.iter().zip(table) {
+        arrays.push(column_array(column, values)?);
+    }
+    Ok(RecordBatch::try_new(schema.clone(), arrays)?)
+}
+
+fn files_batch(schema: &Arc<Schema>, columns: &[ColumnSpec], files: &[File]) -> Result<RecordBatch, Box<dyn std::error::Error>> {
+    let text = |f: fn(&File) -> &str| -> ArrayRef { Arc::new(StringArray::from_iter_values(files.iter().map(f))) };
+    let hash = |algorithm: &str| -> ArrayRef { Arc::new(StringArray::from_iter(files.iter().map(|file| file.hash(algorithm)))) };
+    let mut arrays: Vec<ArrayRef> = vec![
+        text(|file| &file.id),
+        text(|file| &file.fs),
+        text(|file| &file.fsid),
+        text(|file| &file.path),
+        Arc::new(UInt64Array::from_iter_values(files.iter().map(|file| file.size))),
+        Arc::new(UInt64Array::from_iter_values(files.iter().map(|file| file.extraction_id))),
+        text(|file| &file.deleted),
+        text(|file| &file.embedded),
+        text(|file| &file.is_related),
+        hash("MD5"),
+        hash("SHA1"),
+        hash("SHA256"),
+    ];
+    for column in columns {
+        let values = files.iter().map(|file| {
+            file.access_info
+                .iter()
+                .flat_map(|access_info| access_info.timestamps.iter())
+                .find(|timestamp| timestamp.name == column.name)
+                .and_then(|timestamp| parse_datetime(&timestamp.text))
+                .map(TypedValue::TimeStamp)
+        });
+        arrays.push(array(ValueKind::TimeStamp, values.collect()));
+    }
+    Ok(RecordBatch::try_new(schema.clone(), arrays)?)
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use arrow_array::{cast::AsArray, types::{Int64Type, TimestampMicrosecondType}, Array};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use quick_xml::Reader;
+    use super::*;
+
+    const XML_STR: &str = r#"
+    <project id="00000000-0000-0000-0000-000000000000" name="Test" reportVersion="7.0.0.0" licenseID="0" containsGarbage="False" extractionType="Logical" NodeCount="5" ModelCount="3" xmlns="http://pa.cellebrite.com/report/2.0">
+        <taggedFiles>
+            <file fs="iPhone" fsid="fsid" path="/a.jpg" size="1" id="file-0" extractionId="0" deleted="Intact" embedded="false" isrelated="False">
+                <accessInfo>
+                    <timestamp name="CreationTime"><![CDATA[2020-07-01T07:45:53.000+02:00]]></timestamp>
+                </accessInfo>
+                <metadata section="MetaData">
+                    <item name="SHA256"><![CDATA[abc]]></item>
+                </metadata>
+            </file>
+        </taggedFiles>
+        <decodedData>
+            <modelType type="InstantMessage">
+                <model type="InstantMessage" id="message-0" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                    <field name="Body" type="String"><value type="String"><![CDATA[hi]]></value></field>
+                    <field name="TimeStamp" type="TimeStamp"><value type="TimeStamp">2020-07-01T07:45:53.000+00:00</value></field>
+                    <field name="Status" type="Int32"><value type="Int32"><![CDATA[3]]></value></field>
+                    <multiModelField name="To" type="Party">
+                        <model type="Party" id="party-1" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                            <field name="Identifier" type="String"><value type="String"><![CDATA[+43456]]></value></field>
+                        </model>
+                        <model type="Party" id="party-2" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                            <field name="Identifier" type="String"><value type="String"><![CDATA[+43789]]></value></field>
+                        </model>
+                    </multiModelField>
+                </model>
+                <model type="InstantMessage" id="message-1" deleted_state="Deleted" decoding_confidence="High" isrelated="False" extractionId="0">
+                    <field name="Status" type="Int32"><value type="Int32"><![CDATA[n/a]]></value></field>
+                </model>
+                <model type="InstantMessage" id="message-2" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                    <field name="UserMapping" type="Boolean"><value type="Boolean"><![CDATA[True]]></value></field>
+                    <field name="id" type="String"><value type="String"><![CDATA[42]]></value></field>
+                </model>
+            </modelType>
+        </decodedData>
+    </project>
+    "#;
+
+    fn read_batches(path: &Path) -> Result<Vec<RecordBatch>, Box<dyn std::error::Error>> {
+        let reader = ParquetRecordBatchReaderBuilder::try_new(fs::File::open(path)?)?.build()?;
+        Ok(reader.collect::<Result<_, _>>()?)
+    }
+
+    #[test]
+    fn test_parquet_exporter_0() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = std::env::temp_dir().join(format!("ufdr-parquet-{}", std::process::id()));
+        let paths = ParquetExporter::new()
+            .batch_size(2)
+            .export(|| Ok(ReportStream::new(Reader::from_reader(Cursor::new(XML_STR)))), &dir)?;
+        assert_eq!(paths, vec![dir.join("InstantMessage.parquet"), dir.join("tagged_files.parquet")]);
+
+        let batches = read_batches(&paths[0])?;
+        assert_eq!(batches.iter().map(RecordBatch::num_rows).sum::<usize>(), 3);
+        let schema = batches[0].schema();
+        let types: Vec<(&str, &DataType)> = schema.fields().iter().map(|field| (field.name().as_str(), field.data_type())).collect();
+        assert_eq!(types[4..], [
+            ("Body", &DataType::Utf8),
+            ("TimeStamp", &DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))),
+            ("Status", &DataType::Int64),
+            ("To.Identifier", &DataType::List(Arc::new(ArrowField::new("item", DataType::Utf8, true)))),
+            ("UserMapping", &DataType::Boolean),
+            ("field:id", &DataType::Utf8),
+        ]);
+        let status = batches[0].column_by_name("Status").ok_or("Status")?.as_primitive::<Int64Type>();
+        assert_eq!((status.value(0), status.is_null(1)), (3, true));
+        let to = batches[0].column_by_name("To.Identifier").ok_or("To.Identifier")?.as_list::<i32>();
+        assert_eq!(to.value(0).as_string::<i32>().iter().collect::<Vec<_>>(), vec![Some("+43456"), Some("+43789")]);
+        assert_eq!(to.value(1).len(), 0);
+        assert!(batches[0].column_by_name("UserMapping").ok_or("UserMapping")?.as_boolean().value(2));
+        assert_eq!(batches[0].column_by_name("field:id").ok_or("field:id")?.as_string::<i32>().value(2), "42");
+        assert_eq!(batches[0].column_by_name("id").ok_or("id")?.as_string::<i32>().value(2), "message-2");
+
+        let batches = read_batches(&paths[1])?;
+        let sha256 = batches[0].column_by_name("sha256").ok_or("sha256")?.as_string::<i32>();
+        assert_eq!(sha256.value(0), "abc");
+        let creation = batches[0].column_by_name("CreationTime").ok_or("CreationTime")?.as_primitive::<TimestampMicrosecondType>();
+        assert_eq!(creation.value(0), 1_593_582_353_000_000);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
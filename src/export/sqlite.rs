@@ -0,0 +1,486 @@
+use std::{collections::{HashMap, HashSet}, path::Path};
+use rusqlite::{params, types::Value as SqlValue, Connection, Transaction};
+use crate::models::{CaseField, Model, ModelGraph, Project, TypedValue, ValueKind};
+
+
+
+const SCHEMA: &str = "
+CREATE TABLE extractions (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    is_custom_name TEXT NOT NULL,
+    type TEXT NOT NULL,
+    device_name TEXT NOT NULL,
+    full_name TEXT NOT NULL,
+    idx INTEGER NOT NULL,
+    is_partial_data TEXT NOT NULL
+);
+CREATE TABLE case_fields (
+    position INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    field_type TEXT,
+    is_system TEXT,
+    case_field TEXT,
+    value TEXT NOT NULL
+);
+CREATE TABLE metadata_items (
+    section TEXT NOT NULL,
+    item_id TEXT,
+    name TEXT NOT NULL,
+    grp TEXT,
+    source_extraction TEXT,
+    value TEXT NOT NULL
+);
+CREATE TABLE images (
+    key TEXT NOT NULL,
+    path TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    type TEXT NOT NULL,
+    verify TEXT NOT NULL,
+    extraction_id INTEGER NOT NULL
+);
+CREATE TABLE tagged_files (
+    id TEXT PRIMARY KEY,
+    fs TEXT NOT NULL,
+    fsid TEXT NOT NULL,
+    path TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    extraction_id INTEGER NOT NULL,
+    deleted TEXT NOT NULL,
+    embedded TEXT NOT NULL,
+    is_related TEXT NOT NULL,
+    md5 TEXT,
+    sha1 TEXT,
+    sha256 TEXT
+);
+CREATE TABLE file_timestamps (
+    file_id TEXT NOT NULL REFERENCES tagged_files(id),
+    name TEXT NOT NULL,
+    value TEXT NOT NULL
+);
+CREATE TABLE file_metadata (
+    file_id TEXT NOT NULL REFERENCES tagged_files(id),
+    section TEXT NOT NULL,
+    name TEXT NOT NULL,
+    value TEXT NOT NULL
+);
+CREATE TABLE models (
+    row INTEGER PRIMARY KEY,
+    id TEXT NOT NULL,
+    type TEXT NOT NULL,
+    parent_row INTEGER REFERENCES models(row),
+    field_name TEXT,
+    depth INTEGER NOT NULL,
+    extraction_id INTEGER NOT NULL,
+    deleted_state TEXT NOT NULL,
+    decoding_confidence TEXT NOT NULL,
+    is_related TEXT NOT NULL
+);
+CREATE TABLE model_fields (
+    model_row INTEGER NOT NULL REFERENCES models(row),
+    name TEXT NOT NULL,
+    type TEXT,
+    position INTEGER NOT NULL,
+    value
+);
+CREATE TABLE model_relations (
+    parent_row INTEGER NOT NULL REFERENCES models(row),
+    child_row INTEGER NOT NULL REFERENCES models(row),
+    field_name TEXT NOT NULL,
+    position INTEGER NOT NULL
+);
+CREATE INDEX models_id ON models(id);
+CREATE INDEX model_fields_model_row ON model_fields(model_row);
+CREATE INDEX model_relations_parent_row ON model_relations(parent_row);
+CREATE INDEX model_relations_child_row ON model_relations(child_row);
+";
+
+
+/// Writes `project` into a new SQLite database at `path`, refusing to
+/// overwrite an existing file.
+pub fn export_project<P: AsRef<Path>>(project: &Project, path: P) -> Result<(), Box<dyn std::error::Error>> {
+    let path = path.as_ref();
+    if path.exists() {
+        return Err(format!("{} already exists", path.display()).into());
+    }
+    let mut connection = Connection::open(path)?;
+    write_project(project, &mut connection)
+}
+
+/// Creates the schema in `connection` and fills it from `project` in a
+/// single transaction.
+///
+/// Besides the generic `models`, `model_fields` and `model_relations`
+/// tables, every model type gets a `model_<type>` table with one column
+/// per field, keyed by `_row` into `models`. Multi-valued fields are
+/// stored there as JSON arrays.
+pub fn write_project(project: &Project, connection: &mut Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(SCHEMA)?;
+    write_extractions(project, &transaction)?;
+    write_case_fields(project, &transaction)?;
+    write_metadata(project, &transaction)?;
+    write_images(project, &transaction)?;
+    write_tagged_files(project, &transaction)?;
+    write_models(&project.model_graph(), &transaction)?;
+    transaction.commit()?;
+    Ok(())
+}
+
+fn write_extractions(project: &Project, transaction: &Transaction) -> Result<(), Box<dyn std::error::Error>> {
+    let mut statement = transaction.prepare("INSERT INTO extractions VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)")?;
+    for info in &project.source_extractions.infos {
+        statement.execute(params![
+            info.id as i64,
+            info.name,
+            info.is_custom_name,
+            info.dtype,
+            info.device_name,
+            info.full_name,
+            info.index,
+            info.is_partial_data,
+        ])?;
+    }
+    Ok(())
+}
+
+fn write_case_fields(project: &Project, transaction: &Transaction) -> Result<(), Box<dyn std::error::Error>> {
+    let mut statement = transaction.prepare("INSERT INTO case_fields VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?;
+    for (position, field) in project.case_information.fields.iter().enumerate() {
+        statement.execute(params![
+            position as i64,
+            field.name,
+            field.field_type,
+            field.is_system,
+            CaseField::from_field(field).map(|case_field| format!("{:?}", case_field)),
+            field.value_text(),
+        ])?;
+    }
+    Ok(())
+}
+
+fn write_metadata(project: &Project, transaction: &Transaction) -> Result<(), Box<dyn std::error::Error>> {
+    let mut statement = transaction.prepare("INSERT INTO metadata_items VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?;
+    for metadata in &project.metadata {
+        for item in &metadata.items {
+            statement.execute(params![
+                metadata.section,
+                item.id,
+                item.name,
+                item.group,
+                item.source_extraction,
+                item.text,
+            ])?;
+        }
+    }
+    Ok(())
+}
+
+fn write_images(project: &Project, transaction: &Transaction) -> Result<(), Box<dyn std::error::Error>> {
+    let mut statement = transaction.prepare("INSERT INTO images VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?;
+    for image in project.images.iter().flat_map(|images| images.images.iter()) {
+        statement.execute(params![
+            image.key,
+            image.path,
+            image.size as i64,
+            image.dtype,
+            image.verify,
+            image.extraction_id as i64,
+        ])?;
+    }
+    Ok(())
+}
+
+fn write_tagged_files(project: &Project, transaction: &Transaction) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file_statement = transaction.prepare("INSERT INTO tagged_files VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)")?;
+    let mut timestamp_statement = transaction.prepare("INSERT INTO file_timestamps VALUES (?1, ?2, ?3)")?;
+    let mut metadata_statement = transaction.prepare("INSERT INTO file_metadata VALUES (?1, ?2, ?3, ?4)")?;
+    for file in project.tagged_files.iter().flat_map(|tagged_files| tagged_files.files.iter()) {
+        file_statement.execute(params![
+            file.id,
+            file.fs,
+            file.fsid,
+            file.path,
+            file.size as i64,
+            file.extraction_id as i64,
+            file.deleted,
+            file.embedded,
+            file.is_related,
+            file.hash("MD5"),
+            file.hash("SHA1"),
+            file.hash("SHA256"),
+        ])?;
+        for timestamp in file.access_info.iter().flat_map(|access_info| access_info.timestamps.iter()) {
+            timestamp_statement.execute(params![file.id, timestamp.name, timestamp.text])?;
+        }
+        for metadata in &file.metadata {
+            for item in &metadata.items {
+                metadata_statement.execute(params![file.id, metadata.section, item.name, item.text])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn sql_value(value: TypedValue) -> SqlValue {
+    match value {
+        TypedValue::String(s) => SqlValue::Text(s),
+        TypedValue::Boolean(b) => SqlValue::Integer(b as i64),
+        TypedValue::Integer(i) => SqlValue::Integer(i),
+        TypedValue::Float(x) => SqlValue::Real(x),
+        TypedValue::TimeStamp(t) => SqlValue::Text(t.to_rfc3339()),
+    }
+}
+
+fn affinity(kind: ValueKind) -> &'static str {
+    match kind {
+        ValueKind::Boolean | ValueKind::Integer => "INTEGER",
+        ValueKind::Float => "REAL",
+        ValueKind::String | ValueKind::TimeStamp => "TEXT",
+    }
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+/// Leading columns of every `model_<type>` table.
+const RESERVED_COLUMNS: [(&str, &str); 4] = [
+    ("_row", "INTEGER PRIMARY KEY REFERENCES models(row)"),
+    ("_id", "TEXT NOT NULL"),
+    ("_extraction_id", "INTEGER NOT NULL"),
+    ("_deleted_state", "TEXT NOT NULL"),
+];
+
+/// Column of a `model_<type>` table. SQLite identifiers are case
+/// insensitive, so field names differing only in case, or clashing with a
+/// reserved column, get a suffix.
+struct Column {
+    field_name: String,
+    column_name: String,
+    kind: ValueKind,
+    multi: bool,
+}
+
+fn columns_of<'a>(models: impl Iterator<Item = &'a Model>) -> Vec<Column> {
+    let mut columns: Vec<Column> = Vec::new();
+    let mut field_names: HashSet<String> = HashSet::new();
+    let mut taken: HashSet<String> = RESERVED_COLUMNS.iter().map(|(name, _)| name.to_string()).collect();
+    let mut push = |columns: &mut Vec<Column>, name: &str, dtype: Option<&str>, multi: bool| {
+        if !field_names.insert(name.to_string()) {
+            return;
+        }
+        let mut column_name = name.to_string();
+        let mut suffix = 2;
+        while !taken.insert(column_name.to_lowercase()) {
+            column_name = format!("{}_{}", name, suffix);
+            suffix += 1;
+        }
+        columns.push(Column {
+            field_name: name.to_string(),
+            column_name,
+            kind: if multi { ValueKind::String } else { dtype.map(ValueKind::from_dtype).unwrap_or(ValueKind::String) },
+            multi,
+        });
+    };
+    for model in models {
+        for field in &model.fields {
+            let dtype = field.value.as_ref().map(|value| value.dtype.as_str()).or(field.dtype.as_deref());
+            push(&mut columns, &field.name, dtype, false);
+        }
+        for multi_field in &model.multi_fields {
+            push(&mut columns, &multi_field.name, None, true);
+        }
+    }
+    columns
+}
+
+fn write_models(graph: &ModelGraph, transaction: &Transaction) -> Result<(), Box<dyn std::error::Error>> {
+    let mut model_statement = transaction.prepare("INSERT INTO models VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)")?;
+    let mut field_statement = transaction.prepare("INSERT INTO model_fields VALUES (?1, ?2, ?3, ?4, ?5)")?;
+    let mut relation_statement = transaction.prepare("INSERT INTO model_relations VALUES (?1, ?2, ?3, ?4)")?;
+    let mut positions: HashMap<(usize, &str), i64> = HashMap::new();
+    for (row, node) in graph.nodes().enumerate() {
+        let model = node.model;
+        model_statement.execute(params![
+            row as i64,
+            model.id,
+            model.dtype,
+            node.parent.map(|parent| parent as i64),
+            node.field_name,
+            node.depth as i64,
+            model.extraction_id as i64,
+            model.deleted_state,
+            model.decoding_confidence,
+            model.is_related,
+        ])?;
+        for field in &model.fields {
+            let value = field.typed_value().map(sql_value).unwrap_or(SqlValue::Null);
+            let dtype = field.value.as_ref().map(|value| value.dtype.as_str()).or(field.dtype.as_deref());
+            field_statement.execute(params![row as i64, field.name, dtype, 0, value])?;
+        }
+        for multi_field in &model.multi_fields {
+            for (position, value) in multi_field.values.iter().enumerate() {
+                field_statement.execute(params![row as i64, multi_field.name, value.dtype, position as i64, sql_value(value.typed())])?;
+            }
+        }
+        if let (Some(parent), Some(field_name)) = (node.parent, node.field_name) {
+            let position = *positions.entry((parent, field_name)).and_modify(|position| *position += 1).or_insert(0);
+            relation_statement.execute(params![parent as i64, row as i64, field_name, position])?;
+        }
+    }
+
+    let mut tables: Vec<(&str, Vec<(usize, &Model)>)> = Vec::new();
+    let mut table_positions: HashMap<&str, usize> = HashMap::new();
+    for (row, node) in graph.nodes().enumerate() {
+        let dtype = node.model.dtype.as_str();
+        let position = *table_positions.entry(dtype).or_insert_with(|| {
+            tables.push((dtype, Vec::new()));
+            tables.len() - 1
+        });
+        tables[position].1.push((row, node.model));
+    }
+    for (dtype, rows) in tables {
+        let columns = columns_of(rows.iter().map(|(_, model)| *model));
+        let table = quote(&format!("model_{}", dtype));
+        let mut definitions: Vec<String> = RESERVED_COLUMNS.iter().map(|(name, definition)| format!("{} {}", quote(name), definition)).collect();
+        definitions.extend(columns.iter().map(|column| format!("{} {}", quote(&column.column_name), affinity(column.kind))));
+        transaction.execute(&format!("CREATE TABLE {} ({})", table, definitions.join(", ")), [])?;
+        let placeholders: Vec<String> = (1..=definitions.len()).map(|i| format!("?{}", i)).collect();
+        let mut statement = transaction.prepare(&format!("INSERT INTO {} VALUES ({})", table, placeholders.join(", ")))?;
+        for (row, model) in rows {
+            let mut values = vec![
+                SqlValue::Integer(row as i64),
+                SqlValue::Text(model.id.clone()),
+                SqlValue::Integer(model.extraction_id as i64),
+                SqlValue::Text(model.deleted_state.clone()),
+            ];
+            for column in &columns {
+                let value = if column.multi {
+                    model.multi_fields.iter().find(|multi_field| multi_field.name == column.field_name).map(|multi_field| {
+                        let texts: Vec<&str> = multi_field.values.iter().map(|value| value.text.as_str()).collect();
+                        SqlValue::Text(serde_json::to_string(&texts).unwrap_or_default())
+                    })
+                } else {
+                    model.field(&column.field_name).and_then(|field| field.typed_value()).map(sql_value)
+                };
+                values.push(value.unwrap_or(SqlValue::Null));
+            }
+            statement.execute(rusqlite::params_from_iter(values))?;
+        }
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use quick_xml::{events::Event, Reader};
+    use super::*;
+
+    const XML_STR: &str = r#"
+    <project id="00000000-0000-0000-0000-000000000000" name="Test" reportVersion="7.0.0.0" licenseID="0" containsGarbage="False" extractionType="Logical" NodeCount="4" ModelCount="2" xmlns="http://pa.cellebrite.com/report/2.0">
+        <sourceExtractions>
+            <extractionInfo id="0" name="Logical" isCustomName="False" type="Logical" deviceName="Report" fullName="Cellebrite UFED Reports" index="0" IsPartialData="False" />
+        </sourceExtractions>
+        <caseInformation>
+            <field name="Case Number" isSystem="True" isRequired="False" fieldType="CaseNumber" multipleLines="False"><![CDATA[2024-001]]></field>
+        </caseInformation>
+        <metadata section="Additional Fields">
+            <item name="UFED_PA_Version"><![CDATA[1.2.3.4]]></item>
+        </metadata>
+        <images>
+            <image key="FileDump" path="iPhoneBackup.tar" size="12345678" type="File" verify="NoSourceValues" extractionId="0" />
+        </images>
+        <taggedFiles>
+            <file fs="iPhone" fsid="fsid" path="/a.jpg" size="1" id="file-0" extractionId="0" deleted="Intact" embedded="false" isrelated="False">
+                <accessInfo>
+                    <timestamp name="CreationTime"><![CDATA[2020-07-01T07:45:53.000+00:00]]></timestamp>
+                </accessInfo>
+                <metadata section="MetaData">
+                    <item name="SHA256"><![CDATA[abc]]></item>
+                    <item name="MD5"><![CDATA[def]]></item>
+                </metadata>
+            </file>
+        </taggedFiles>
+        <decodedData>
+            <modelType type="InstantMessage">
+                <model type="InstantMessage" id="message-0" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                    <field name="Body" type="String"><value type="String"><![CDATA[hi]]></value></field>
+                    <field name="body" type="Int32"><value type="Int32"><![CDATA[42]]></value></field>
+                    <field name="_ID" type="String"><value type="String"><![CDATA[field id]]></value></field>
+                    <modelField name="From" type="Party">
+                        <model type="Party" id="party-0" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                            <field name="Identifier" type="String"><value type="String"><![CDATA[+43123]]></value></field>
+                        </model>
+                    </modelField>
+                    <multiModelField name="To" type="Party">
+                        <model type="Party" id="party-1" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                        </model>
+                    </multiModelField>
+                    <multiField name="Tags" type="String">
+                        <value type="String"><![CDATA[work]]></value>
+                        <value type="String"><![CDATA[late]]></value>
+                    </multiField>
+                </model>
+            </modelType>
+        </decodedData>
+    </project>
+    "#;
+
+    fn parse_project(xml_str: &str) -> Result<Project, Box<dyn std::error::Error>> {
+        let mut reader = Reader::from_reader(Cursor::new(xml_str));
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf)? {
+                Event::Start(e) if e.name().as_ref() == b"project" => return Project::parse_one(&e, &mut reader),
+                Event::Eof => return Err("eof".into()),
+                _ => (),
+            }
+            buf.clear();
+        }
+    }
+
+    #[test]
+    fn test_write_project_0() -> Result<(), Box<dyn std::error::Error>> {
+        let project = parse_project(XML_STR)?;
+        let mut connection = Connection::open_in_memory()?;
+        write_project(&project, &mut connection)?;
+
+        let count = |sql: &str| -> rusqlite::Result<i64> { connection.query_row(sql, [], |row| row.get(0)) };
+        assert_eq!(count("SELECT COUNT(*) FROM extractions")?, 1);
+        assert_eq!(count("SELECT COUNT(*) FROM metadata_items")?, 1);
+        assert_eq!(count("SELECT COUNT(*) FROM images")?, 1);
+        assert_eq!(count("SELECT COUNT(*) FROM file_timestamps")?, 1);
+        assert_eq!(count("SELECT COUNT(*) FROM models")?, 3);
+        assert_eq!(count("SELECT COUNT(*) FROM model_fields")?, 6);
+        assert_eq!(count("SELECT COUNT(*) FROM model_Party")?, 2);
+
+        let case_field: String = connection.query_row("SELECT case_field FROM case_fields", [], |row| row.get(0))?;
+        assert_eq!(case_field, "CaseNumber");
+        let hashes: (String, Option<String>, String) = connection.query_row(
+            "SELECT md5, sha1, sha256 FROM tagged_files WHERE id = 'file-0'", [], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        assert_eq!(hashes, ("def".to_string(), None, "abc".to_string()));
+
+        let message: (String, i64, String) = connection.query_row(
+            "SELECT Body, body_2, Tags FROM model_InstantMessage WHERE _id = 'message-0'", [], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        assert_eq!(message, ("hi".to_string(), 42, r#"["work","late"]"#.to_string()));
+        let ids: (String, String) = connection.query_row(
+            "SELECT _id, _ID_2 FROM model_InstantMessage", [], |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        assert_eq!(ids, ("message-0".to_string(), "field id".to_string()));
+
+        let mut statement = connection.prepare(
+            "SELECT r.field_name, r.position, c.id FROM model_relations r JOIN models c ON c.row = r.child_row ORDER BY r.child_row",
+        )?;
+        let relations: Vec<(String, i64, String)> = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?.collect::<Result<_, _>>()?;
+        assert_eq!(relations, vec![
+            ("From".to_string(), 0, "party-0".to_string()),
+            ("To".to_string(), 0, "party-1".to_string()),
+        ]);
+        Ok(())
+    }
+}
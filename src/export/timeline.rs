@@ -0,0 +1,176 @@
+use std::{io::Write, str::FromStr};
+use serde_json::json;
+use crate::models::{TimelineEvent, TimelineReference};
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineFormat {
+    Csv,
+    Jsonl,
+    Bodyfile,
+}
+
+impl TimelineFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TimelineFormat::Csv => "csv",
+            TimelineFormat::Jsonl => "jsonl",
+            TimelineFormat::Bodyfile => "body",
+        }
+    }
+
+    pub fn write<W: Write>(&self, events: &[TimelineEvent], writer: W) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            TimelineFormat::Csv => write_csv(events, writer),
+            TimelineFormat::Jsonl => write_jsonl(events, writer),
+            TimelineFormat::Bodyfile => write_bodyfile(events, writer),
+        }
+    }
+}
+
+impl FromStr for TimelineFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(TimelineFormat::Csv),
+            "jsonl" => Ok(TimelineFormat::Jsonl),
+            "bodyfile" | "body" | "mactime" => Ok(TimelineFormat::Bodyfile),
+            _ => Err(format!("unknown timeline format {}", s)),
+        }
+    }
+}
+
+
+/// Columns `time,type,source,description,reference`, times in RFC 3339.
+pub fn write_csv<W: Write>(events: &[TimelineEvent], writer: W) -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer = csv::Writer::from_writer(writer);
+    writer.write_record(["time", "type", "source", "description", "reference"])?;
+    for event in events {
+        writer.write_record([
+            event.time.to_rfc3339().as_str(),
+            &event.event_type,
+            &event.source,
+            &event.description,
+            &event.reference.id(),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn write_jsonl<W: Write>(events: &[TimelineEvent], mut writer: W) -> Result<(), Box<dyn std::error::Error>> {
+    for event in events {
+        let line = json!({
+            "time": event.time.to_rfc3339(),
+            "type": event.event_type,
+            "source": event.source,
+            "description": event.description,
+            "reference": event.reference.id(),
+        });
+        serde_json::to_writer(&mut writer, &line)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// The mactime 3.x body file, `MD5|name|inode|mode|UID|GID|size|atime|mtime|ctime|crtime`,
+/// one line per event. `AccessTime`, `ModifyTime`, `ChangeTime` and
+/// `CreationTime` go into their own column, any other event into mtime.
+/// Hash and size are filled for tagged files.
+pub fn write_bodyfile<W: Write>(events: &[TimelineEvent], mut writer: W) -> Result<(), Box<dyn std::error::Error>> {
+    for event in events {
+        let (md5, size, name) = match event.reference {
+            TimelineReference::File(file) => (file.hash("MD5").unwrap_or("0"), file.size, file.path.clone()),
+            _ => ("0", 0, format!("[{}] {}: {}", event.source, event.event_type, event.description)),
+        };
+        let mut times = [0; 4];
+        let column = match event.event_type.as_str() {
+            "AccessTime" => 0,
+            "ChangeTime" | "MetadataChangeTime" => 2,
+            "CreationTime" => 3,
+            _ => 1,
+        };
+        times[column] = event.time.timestamp();
+        writeln!(
+            writer,
+            "{}|{}|0|0|0|0|{}|{}|{}|{}|{}",
+            md5,
+            name.replace(['|', '\n', '\r'], " "),
+            size,
+            times[0],
+            times[1],
+            times[2],
+            times[3],
+        )?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::models::{FileBuilder, ModelBuilder, ProjectBuilder, Project};
+    use super::*;
+
+    fn project() -> Project {
+        let file = FileBuilder::new("/DCIM/IMG|0001.JPG")
+            .id("file-0")
+            .size(4)
+            .timestamp("CreationTime", "2020-07-01T08:00:00.000+00:00")
+            .metadata_item("MetaData", "MD5", "d41d8cd9");
+        ProjectBuilder::new("Test")
+            .file(file)
+            .model(
+                ModelBuilder::new("InstantMessage")
+                    .id("message-0")
+                    .field("Body", "hi, \"you\"\nthere")
+                    .typed_field("TimeStamp", "TimeStamp", "2020-07-01T09:30:00.000+00:00"),
+            )
+            .build()
+    }
+
+    fn render(format: TimelineFormat) -> Result<String, Box<dyn std::error::Error>> {
+        let project = project();
+        let mut out = Vec::new();
+        format.write(&project.timeline(), &mut out)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    #[test]
+    fn test_timeline_csv_0() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(render("csv".parse()?)?, "\
+time,type,source,description,reference
+2020-07-01T08:00:00+00:00,CreationTime,File,/DCIM/IMG|0001.JPG,file-0
+2020-07-01T09:30:00+00:00,TimeStamp,InstantMessage,\"hi, \"\"you\"\"
+there\",message-0
+");
+        Ok(())
+    }
+
+    #[test]
+    fn test_timeline_jsonl_0() -> Result<(), Box<dyn std::error::Error>> {
+        let jsonl = render(TimelineFormat::Jsonl)?;
+        let lines: Vec<serde_json::Value> = jsonl.lines().map(serde_json::from_str).collect::<Result<_, _>>()?;
+        assert_eq!(lines[1], json!({
+            "time": "2020-07-01T09:30:00+00:00",
+            "type": "TimeStamp",
+            "source": "InstantMessage",
+            "description": "hi, \"you\"\nthere",
+            "reference": "message-0",
+        }));
+        Ok(())
+    }
+
+    #[test]
+    fn test_timeline_bodyfile_0() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(render(TimelineFormat::Bodyfile)?, "\
+d41d8cd9|/DCIM/IMG 0001.JPG|0|0|0|0|4|0|0|0|1593590400
+0|[InstantMessage] TimeStamp: hi, \"you\" there|0|0|0|0|0|0|1593595800|0|0
+");
+        Ok(())
+    }
+}
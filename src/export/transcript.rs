@@ -0,0 +1,259 @@
+use std::{fmt::Write as _, fs, path::{Path, PathBuf}, str::FromStr};
+use chrono::{DateTime, FixedOffset};
+use quick_xml::escape::escape;
+use crate::models::{Project, Transcript, TranscriptMessage};
+use super::html::page;
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptFormat {
+    Text,
+    Markdown,
+    Html,
+}
+
+impl TranscriptFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TranscriptFormat::Text => "txt",
+            TranscriptFormat::Markdown => "md",
+            TranscriptFormat::Html => "html",
+        }
+    }
+
+    /// A complete document; HTML transcripts are standalone pages.
+    pub fn render(&self, transcript: &Transcript) -> String {
+        match self {
+            TranscriptFormat::Text => to_text(transcript),
+            TranscriptFormat::Markdown => to_markdown(transcript),
+            TranscriptFormat::Html => page(&transcript.title, &format!("<h1>{}</h1>{}", escape(&transcript.title), to_html(transcript))),
+        }
+    }
+}
+
+impl FromStr for TranscriptFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" | "txt" => Ok(TranscriptFormat::Text),
+            "markdown" | "md" => Ok(TranscriptFormat::Markdown),
+            "html" => Ok(TranscriptFormat::Html),
+            _ => Err(format!("unknown transcript format {}", s)),
+        }
+    }
+}
+
+
+/// Writes one `chat-<n>.<ext>` per chat in `dir`, in the order of
+/// [`Project::transcripts`].
+pub fn export_project<P: AsRef<Path>>(project: &Project, dir: P, format: TranscriptFormat) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    let mut paths = Vec::new();
+    for (index, transcript) in project.transcripts().iter().enumerate() {
+        let path = dir.join(format!("chat-{}.{}", index + 1, format.extension()));
+        fs::write(&path, format.render(transcript))?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+pub fn to_text(transcript: &Transcript) -> String {
+    let mut text = String::new();
+    let _ = writeln!(text, "Chat: {}", transcript.title);
+    if let Some(source) = transcript.source {
+        let _ = writeln!(text, "Source: {}", source);
+    }
+    if !transcript.participants.is_empty() {
+        let _ = writeln!(text, "Participants: {}", participants(transcript));
+    }
+    for message in &transcript.messages {
+        let _ = write!(text, "\n[{}] {}", timestamp(message.timestamp), heading(message));
+        if message.deleted {
+            text.push_str(" [deleted]");
+        }
+        text.push('\n');
+        if let Some(body) = message.body {
+            let _ = writeln!(text, "{}", body);
+        }
+        for attachment in &message.attachments {
+            let _ = writeln!(text, "  Attachment: {}", attachment.label());
+        }
+    }
+    text
+}
+
+pub fn to_markdown(transcript: &Transcript) -> String {
+    let mut markdown = String::new();
+    let _ = writeln!(markdown, "# {}\n", markdown_escape(&transcript.title));
+    if let Some(source) = transcript.source {
+        let _ = writeln!(markdown, "- **Source:** {}", markdown_escape(source));
+    }
+    if !transcript.participants.is_empty() {
+        let _ = writeln!(markdown, "- **Participants:** {}", markdown_escape(&participants(transcript)));
+    }
+    for message in &transcript.messages {
+        let _ = write!(
+            markdown,
+            "\n**{}** · {}",
+            markdown_escape(&heading(message)),
+            timestamp(message.timestamp),
+        );
+        if message.deleted {
+            markdown.push_str(" · *deleted*");
+        }
+        markdown.push('\n');
+        if let Some(body) = message.body {
+            markdown.push('\n');
+            for line in body.lines() {
+                let _ = writeln!(markdown, "> {}", markdown_escape(line));
+            }
+        }
+        if !message.attachments.is_empty() {
+            markdown.push('\n');
+        }
+        for attachment in &message.attachments {
+            let _ = writeln!(markdown, "- Attachment: {}", markdown_escape(&attachment.label()));
+        }
+    }
+    markdown
+}
+
+/// The conversation as an HTML fragment, used by the HTML report pages.
+pub fn to_html(transcript: &Transcript) -> String {
+    let mut html = String::new();
+    if let Some(source) = transcript.source {
+        let _ = write!(html, "<p>Source: {}</p>", escape(source));
+    }
+    if !transcript.participants.is_empty() {
+        let _ = write!(html, "<p>Participants: {}</p>", escape(participants(transcript)));
+    }
+    html.push_str("<div class=\"conversation\">");
+    for message in &transcript.messages {
+        let _ = write!(
+            html,
+            "<div class=\"message{}\"><div class=\"meta\">{} &middot; {}{}</div>",
+            if message.deleted { " deleted" } else { "" },
+            escape(heading(message)),
+            timestamp(message.timestamp),
+            if message.deleted { " &middot; deleted" } else { "" },
+        );
+        if let Some(body) = message.body {
+            let _ = write!(html, "<div>{}</div>", escape(body).replace('\n', "<br>"));
+        }
+        for attachment in &message.attachments {
+            let _ = write!(html, "<div class=\"attachment\">Attachment: {}</div>", escape(attachment.label()));
+        }
+        html.push_str("</div>");
+    }
+    html.push_str("</div>");
+    html
+}
+
+fn participants(transcript: &Transcript) -> String {
+    let labels: Vec<String> = transcript.participants.iter().map(|participant| participant.label()).collect();
+    labels.join(", ")
+}
+
+/// `sender -> recipients`, or just the sender when there are none.
+fn heading(message: &TranscriptMessage) -> String {
+    let from = message.from.as_ref().map(|from| from.label()).unwrap_or_else(|| "Unknown".to_string());
+    if message.to.is_empty() {
+        return from;
+    }
+    let to: Vec<String> = message.to.iter().map(|to| to.label()).collect();
+    format!("{} -> {}", from, to.join(", "))
+}
+
+fn timestamp(timestamp: Option<DateTime<FixedOffset>>) -> String {
+    match timestamp {
+        Some(timestamp) => timestamp.format("%Y-%m-%d %H:%M:%S %:z").to_string(),
+        None => "no timestamp".to_string(),
+    }
+}
+
+fn markdown_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\`*_[]<>#|".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::models::{ModelBuilder, ProjectBuilder};
+    use super::*;
+
+    fn project() -> Project {
+        let party = |identifier: &str| ModelBuilder::new("Party").field("Identifier", identifier);
+        let chat = ModelBuilder::new("Chat")
+            .field("Source", "WhatsApp")
+            .models("Participants", "Party", [party("+43123").field("Name", "Irene"), party("+43456").field("Name", "Sherlock")])
+            .models("Messages", "InstantMessage", [
+                ModelBuilder::new("InstantMessage")
+                    .typed_field("TimeStamp", "TimeStamp", "2020-07-01T09:00:00.000+02:00")
+                    .field("Body", "see *this*\n<b>now</b>")
+                    .model("From", party("+43456"))
+                    .models("To", "Party", [party("+43123")])
+                    .models("Attachments", "Attachment", [ModelBuilder::new("Attachment").field("Filename", "a.jpg")]),
+                ModelBuilder::new("InstantMessage")
+                    .typed_field("TimeStamp", "TimeStamp", "2020-07-01T06:00:00.000+00:00")
+                    .field("Body", "hi")
+                    .model("From", party("+43123"))
+                    .deleted_state("Deleted"),
+            ]);
+        ProjectBuilder::new("Test").model(chat).build()
+    }
+
+    #[test]
+    fn test_transcript_text_0() {
+        let project = project();
+        let transcripts = project.transcripts();
+        assert_eq!(to_text(&transcripts[0]), "\
+Chat: Irene (+43123), Sherlock (+43456)
+Source: WhatsApp
+Participants: Irene (+43123), Sherlock (+43456)
+
+[2020-07-01 06:00:00 +00:00] Irene (+43123) [deleted]
+hi
+
+[2020-07-01 09:00:00 +02:00] Sherlock (+43456) -> Irene (+43123)
+see *this*
+<b>now</b>
+  Attachment: a.jpg
+");
+    }
+
+    #[test]
+    fn test_transcript_markdown_html_0() -> Result<(), Box<dyn std::error::Error>> {
+        let project = project();
+        let transcripts = project.transcripts();
+        let markdown = to_markdown(&transcripts[0]);
+        assert!(markdown.contains("**Irene (+43123)** · 2020-07-01 06:00:00 +00:00 · *deleted*\n\n> hi\n"));
+        assert!(markdown.contains("> see \\*this\\*\n> \\<b\\>now\\</b\\>\n\n- Attachment: a.jpg\n"));
+        let html = to_html(&transcripts[0]);
+        assert!(html.contains("<div class=\"message deleted\">"));
+        assert!(html.contains("<div>see *this*<br>&lt;b&gt;now&lt;/b&gt;</div><div class=\"attachment\">Attachment: a.jpg</div>"));
+        let hi = html.find("<div>hi</div>").ok_or("missing hi")?;
+        let see = html.find("<div>see *this*").ok_or("missing see")?;
+        assert!(hi < see);
+        Ok(())
+    }
+
+    #[test]
+    fn test_transcript_export_0() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = std::env::temp_dir().join(format!("ufdr-transcript-{}", std::process::id()));
+        let paths = export_project(&project(), &dir, "md".parse()?)?;
+        assert_eq!(paths, vec![dir.join("chat-1.md")]);
+        assert!(fs::read_to_string(&paths[0])?.starts_with("# Irene (+43123), Sherlock (+43456)\n"));
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
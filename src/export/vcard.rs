@@ -0,0 +1,271 @@
+use std::{collections::{BTreeMap, HashMap}, fs, path::{Path, PathBuf}};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use uuid::Uuid;
+use crate::{models::{File, Model, Project}, package::Package};
+use super::{content_type, escape_text, file_stem, fold_line, path_ends_with};
+
+
+
+/// Writes `Contact` models as vCard 4.0, either into one `contacts.vcf` or
+/// into one `<Source>.vcf` per source app. Deleted contacts are skipped
+/// unless `include_deleted` is set, in which case they carry an
+/// `X-UFDR-DELETED-STATE` property.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VcardExporter {
+    pub include_deleted: bool,
+    pub split_by_source: bool,
+}
+
+impl VcardExporter {
+    pub fn new() -> Self {
+        VcardExporter::default()
+    }
+
+    pub fn include_deleted(mut self, include_deleted: bool) -> Self {
+        self.include_deleted = include_deleted;
+        self
+    }
+
+    pub fn split_by_source(mut self, split_by_source: bool) -> Self {
+        self.split_by_source = split_by_source;
+        self
+    }
+
+    /// Writes the vCard files into `dir` and returns their paths. Photos are
+    /// embedded when `package` holds the tagged files they refer to.
+    pub fn export_project<P: AsRef<Path>>(
+        &self,
+        project: &Project,
+        mut package: Option<&mut Package>,
+        dir: P,
+    ) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let files: Vec<&File> = project.tagged_files.iter().flat_map(|tagged_files| tagged_files.files.iter()).collect();
+        let files_by_id: HashMap<&str, &File> = files.iter().map(|file| (file.id.as_str(), *file)).collect();
+
+        let mut outputs: BTreeMap<String, String> = BTreeMap::new();
+        for contact in project.model_graph().models().filter(|model| model.dtype == "Contact") {
+            if contact.deleted_state == "Deleted" && !self.include_deleted {
+                continue;
+            }
+            let mut photos = Vec::new();
+            if let Some(package) = package.as_deref_mut() {
+                for file in photo_files(contact, &files, &files_by_id) {
+                    if let Some(local_path) = file.local_path().filter(|local_path| package.contains(local_path)) {
+                        photos.push((content_type(local_path), package.read(local_path)?));
+                    }
+                }
+            }
+            let name = if self.split_by_source {
+                format!("{}.vcf", file_stem(contact.field_text("Source").unwrap_or("Unknown")))
+            } else {
+                "contacts.vcf".to_string()
+            };
+            outputs.entry(name).or_default().push_str(&self.contact_to_vcard(contact, &photos));
+        }
+
+        let mut paths = Vec::with_capacity(outputs.len());
+        for (name, vcards) in outputs {
+            let path = dir.join(name);
+            fs::write(&path, vcards)?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    /// One `BEGIN:VCARD` ... `END:VCARD` block with CRLF line endings and
+    /// lines folded at 75 octets. `photos` are `(media type, bytes)` pairs.
+    pub fn contact_to_vcard(&self, contact: &Model, photos: &[(&str, Vec<u8>)]) -> String {
+        let mut lines = vec!["BEGIN:VCARD".to_string(), "VERSION:4.0".to_string()];
+        match Uuid::parse_str(&contact.id) {
+            Ok(uuid) => lines.push(format!("UID:urn:uuid:{}", uuid)),
+            Err(_) => lines.push(format!("UID:{}", escape_text(&contact.id))),
+        }
+
+        let entries = contact.nested_models("Entries");
+        let display_name = contact
+            .field_text("Name")
+            .or_else(|| entries.iter().find_map(|entry| entry.field_text("Value")))
+            .unwrap_or("Unknown");
+        lines.push(format!("FN:{}", escape_text(display_name)));
+
+        for entry in entries {
+            let Some(value) = entry.field_text("Value") else { continue };
+            let property = match entry.dtype.as_str() {
+                "PhoneNumber" => "TEL;VALUE=text",
+                "EmailAddress" => "EMAIL",
+                "WebAddress" => "URL",
+                _ => continue,
+            };
+            lines.push(format!("{}{}:{}", property, type_param(entry.field_text("Category")), escape_text(value)));
+        }
+
+        for organization in contact.nested_models("Organizations") {
+            if let Some(name) = organization.field_text("Name") {
+                lines.push(format!("ORG:{}", escape_text(name)));
+            }
+            if let Some(position) = organization.field_text("Position") {
+                lines.push(format!("TITLE:{}", escape_text(position)));
+            }
+        }
+
+        for address in contact.nested_models("Addresses") {
+            let street: Vec<String> = ["Street1", "Street2"]
+                .iter()
+                .filter_map(|name| address.field_text(name))
+                .map(escape_text)
+                .collect();
+            let components = [
+                String::new(),
+                String::new(),
+                street.join(","),
+                escape_text(address.field_text("City").unwrap_or_default()),
+                escape_text(address.field_text("State").unwrap_or_default()),
+                escape_text(address.field_text("ZipCode").or(address.field_text("PostalCode")).unwrap_or_default()),
+                escape_text(address.field_text("Country").unwrap_or_default()),
+            ];
+            if components.iter().any(|component| !component.is_empty()) {
+                lines.push(format!("ADR{}:{}", type_param(address.field_text("Category")), components.join(";")));
+            }
+        }
+
+        for (media_type, bytes) in photos {
+            lines.push(format!("PHOTO:data:{};base64,{}", media_type, STANDARD.encode(bytes)));
+        }
+
+        let notes = contact.multi_field("Notes").map(|notes| notes.values.as_slice()).unwrap_or_default();
+        for note in notes.iter().map(|value| value.text.as_str()).chain(contact.field_text("Notes")) {
+            if !note.is_empty() {
+                lines.push(format!("NOTE:{}", escape_text(note)));
+            }
+        }
+
+        if let Some(source) = contact.field_text("Source") {
+            lines.push(format!("X-UFDR-SOURCE:{}", escape_text(source)));
+        }
+        if contact.deleted_state != "Intact" {
+            lines.push(format!("X-UFDR-DELETED-STATE:{}", escape_text(&contact.deleted_state)));
+        }
+        lines.push("END:VCARD".to_string());
+
+        let mut vcard = String::new();
+        for line in lines {
+            vcard.push_str(&fold_line(&line));
+            vcard.push_str("\r\n");
+        }
+        vcard
+    }
+}
+
+/// Tagged files referenced by the contact's `Photos`, either by file id in
+/// `PhotoNodeId` or by a `Name` matching the trailing components of the
+/// file path.
+fn photo_files<'a>(contact: &Model, files: &[&'a File], files_by_id: &HashMap<&str, &'a File>) -> Vec<&'a File> {
+    contact
+        .nested_models("Photos")
+        .iter()
+        .filter_map(|photo| {
+            photo
+                .field_text("PhotoNodeId")
+                .and_then(|id| files_by_id.get(id).copied())
+                .or_else(|| {
+                    let name = photo.field_text("Name")?;
+                    files.iter().copied().find(|file| path_ends_with(&file.path, name))
+                })
+        })
+        .collect()
+}
+
+fn type_param(category: Option<&str>) -> &'static str {
+    match category.map(str::to_ascii_lowercase).as_deref() {
+        Some("mobile" | "cell") => ";TYPE=cell",
+        Some("home") => ";TYPE=home",
+        Some("work" | "business") => ";TYPE=work",
+        Some("fax" | "home fax" | "work fax") => ";TYPE=fax",
+        Some("pager") => ";TYPE=pager",
+        _ => "",
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::models::{FileBuilder, ModelBuilder, ProjectBuilder};
+    use super::*;
+
+    fn file(id: &str, path: &str, local_path: &str) -> FileBuilder {
+        FileBuilder::new(path).id(id).fs("Photos").size(4).local_path(local_path)
+    }
+
+    #[test]
+    fn test_vcard_0() {
+        let contact = ModelBuilder::new("Contact")
+            .id("6f1bd1c5-2c4a-4d8e-9a8e-0f8d2d0c3c11")
+            .field("Name", "Adler, Irene")
+            .field("Source", "WhatsApp")
+            .models("Entries", "ContactEntry", [
+                ModelBuilder::new("PhoneNumber").field("Category", "Mobile").field("Value", "+43 123"),
+                ModelBuilder::new("EmailAddress").field("Category", "Work").field("Value", "irene@example.com"),
+            ])
+            .models("Organizations", "Organization", [ModelBuilder::new("Organization").field("Name", "Opera; Warsaw").field("Position", "Contralto")])
+            .models("Addresses", "StreetAddress", [
+                ModelBuilder::new("StreetAddress").field("Category", "Home").field("Street1", "Briony Lodge").field("City", "London"),
+            ])
+            .multi_field("Notes", "String", ["line 1\nline 2"])
+            .deleted_state("Deleted")
+            .build();
+        let vcard = VcardExporter::new().contact_to_vcard(&contact, &[("image/png", vec![0; 60])]);
+        let lines: Vec<&str> = vcard.split("\r\n").collect();
+        assert_eq!(&lines[..9], &[
+            "BEGIN:VCARD",
+            "VERSION:4.0",
+            "UID:urn:uuid:6f1bd1c5-2c4a-4d8e-9a8e-0f8d2d0c3c11",
+            "FN:Adler\\, Irene",
+            "TEL;VALUE=text;TYPE=cell:+43 123",
+            "EMAIL;TYPE=work:irene@example.com",
+            "ORG:Opera\\; Warsaw",
+            "TITLE:Contralto",
+            "ADR;TYPE=home:;;Briony Lodge;London;;;",
+        ]);
+        assert!(lines[9].starts_with("PHOTO:data:image/png;base64,AAAA") && lines[9].len() == 75);
+        assert!(lines[10].starts_with(' '));
+        assert!(vcard.ends_with("NOTE:line 1\\nline 2\r\nX-UFDR-SOURCE:WhatsApp\r\nX-UFDR-DELETED-STATE:Deleted\r\nEND:VCARD\r\n"));
+    }
+
+    #[test]
+    fn test_vcard_export_0() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = std::env::temp_dir().join(format!("ufdr-vcard-{}", std::process::id()));
+        fs::create_dir_all(dir.join("package/files/Image"))?;
+        fs::write(dir.join("package/files/Image/irene.jpg"), b"jpeg")?;
+        fs::write(dir.join("package/files/Image/myholmes.jpg"), b"mine")?;
+        fs::write(dir.join("package/files/Image/holmes.jpg"), b"holmes")?;
+        let contact = |name: &str, source: &str| ModelBuilder::new("Contact").field("Name", name).field("Source", source);
+        let project = ProjectBuilder::new("Test")
+            .file(file("file-0", "/private/var/irene.jpg", "files\\Image\\irene.jpg"))
+            .file(file("file-1", "/private/var/myholmes.jpg", "files\\Image\\myholmes.jpg"))
+            .file(file("file-2", "/private/var/holmes.jpg", "files\\Image\\holmes.jpg"))
+            .model(contact("Irene", "WhatsApp").models("Photos", "ContactPhoto", [ModelBuilder::new("ContactPhoto").field("PhotoNodeId", "file-0")]))
+            .model(contact("Sherlock", "Phone Book").models("Photos", "ContactPhoto", [ModelBuilder::new("ContactPhoto").field("Name", "holmes.jpg")]))
+            .model(contact("Moriarty", "WhatsApp").deleted_state("Deleted"))
+            .build();
+        let mut package = Package::open(dir.join("package"))?;
+
+        let paths = VcardExporter::new().split_by_source(true).export_project(&project, Some(&mut package), dir.join("split"))?;
+        assert_eq!(paths, vec![dir.join("split/Phone_Book.vcf"), dir.join("split/WhatsApp.vcf")]);
+        let whatsapp = fs::read_to_string(&paths[1])?;
+        assert!(whatsapp.contains("FN:Irene\r\nPHOTO:data:image/jpeg;base64,anBlZw==\r\n"));
+        assert!(!whatsapp.contains("Moriarty"));
+        let phone_book = fs::read_to_string(&paths[0])?;
+        assert!(phone_book.contains("FN:Sherlock\r\nPHOTO:data:image/jpeg;base64,aG9sbWVz\r\n"));
+
+        let paths = VcardExporter::new().include_deleted(true).export_project(&project, None, dir.join("combined"))?;
+        assert_eq!(paths, vec![dir.join("combined/contacts.vcf")]);
+        let combined = fs::read_to_string(&paths[0])?;
+        assert_eq!(combined.matches("BEGIN:VCARD").count(), 3);
+        assert!(combined.contains("FN:Moriarty\r\nX-UFDR-SOURCE:WhatsApp\r\nX-UFDR-DELETED-STATE:Deleted\r\n"));
+        assert!(!combined.contains("PHOTO"));
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
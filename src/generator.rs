@@ -0,0 +1,357 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rand::{rngs::StdRng, seq::IndexedRandom, RngExt, SeedableRng};
+use crate::models::{AccessInfo, File, Image, Item, Metadata, Model, ModelBuilder, Project, ProjectBuilder, Timestamp};
+
+
+
+const FIRST_NAMES: [&str; 12] = [
+    "James", "Mary", "Irene", "John", "Martha", "Greg", "Molly", "Mike", "Sally", "Philip", "Sherlock", "Mycroft",
+];
+const LAST_NAMES: [&str; 10] = [
+    "Moriarty", "Hudson", "Adler", "Watson", "Lestrade", "Hooper", "Stamford", "Donovan", "Anderson", "Holmes",
+];
+const WORDS: [&str; 24] = [
+    "the", "game", "is", "on", "meet", "me", "at", "baker", "street", "tonight", "bring", "the", "file",
+    "call", "when", "you", "arrive", "late", "again", "ok", "see", "you", "soon", "thanks",
+];
+const CHAT_SOURCES: [&str; 4] = ["WhatsApp", "Telegram", "Signal", "iMessage"];
+const DEVICES: [(&str, &str, &str); 3] = [
+    ("Apple", "iPhone 12", "iOS"),
+    ("Samsung", "Galaxy S21", "Android"),
+    ("Google", "Pixel 6", "Android"),
+];
+
+
+/// Size and seed of a generated report. The same configuration always
+/// yields the same report for a given crate version.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratorConfig {
+    pub seed: u64,
+    /// At least one extraction is generated, 0 is treated as 1.
+    pub extractions: usize,
+    pub chats: usize,
+    pub messages_per_chat: usize,
+    pub contacts: usize,
+    pub calls: usize,
+    pub locations: usize,
+    pub tagged_files: usize,
+    pub images: usize,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        GeneratorConfig {
+            seed: 0,
+            extractions: 1,
+            chats: 5,
+            messages_per_chat: 20,
+            contacts: 20,
+            calls: 20,
+            locations: 20,
+            tagged_files: 20,
+            images: 1,
+        }
+    }
+}
+
+
+struct Generator {
+    rng: StdRng,
+    extractions: u64,
+    start: DateTime<Utc>,
+}
+
+impl Generator {
+    fn uuid(&mut self) -> String {
+        uuid::Builder::from_random_bytes(self.rng.random()).into_uuid().to_string()
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items.choose(&mut self.rng).copied().unwrap_or_default()
+    }
+
+    fn name(&mut self) -> String {
+        format!("{} {}", self.pick(&FIRST_NAMES), self.pick(&LAST_NAMES))
+    }
+
+    fn phone_number(&mut self) -> String {
+        format!("+43 6{:02} {:07}", self.rng.random_range(50..100), self.rng.random_range(0..10_000_000))
+    }
+
+    fn sentence(&mut self) -> String {
+        let len = self.rng.random_range(1..12);
+        (0..len).map(|_| self.pick(&WORDS)).collect::<Vec<_>>().join(" ")
+    }
+
+    fn hex(&mut self, bytes: usize) -> String {
+        (0..bytes).map(|_| format!("{:02x}", self.rng.random::<u8>())).collect()
+    }
+
+    /// A point in time within a year after the start of the report.
+    fn time(&mut self) -> DateTime<Utc> {
+        self.start + Duration::seconds(self.rng.random_range(0..365 * 24 * 3600))
+    }
+
+    fn timestamp(time: DateTime<Utc>) -> String {
+        time.format("%Y-%m-%dT%H:%M:%S%.3f+00:00").to_string()
+    }
+
+    fn deleted_state(&mut self) -> &'static str {
+        if self.rng.random_bool(0.1) { "Deleted" } else { "Intact" }
+    }
+
+    fn model(&mut self, dtype: &str, extraction_id: u64) -> ModelBuilder {
+        let deleted_state = self.deleted_state();
+        ModelBuilder::new(dtype).id(&self.uuid()).extraction_id(extraction_id).deleted_state(deleted_state)
+    }
+
+    fn party(&mut self, extraction_id: u64, identifier: &str, name: &str, role: &str) -> ModelBuilder {
+        ModelBuilder::new("Party")
+            .id(&self.uuid())
+            .extraction_id(extraction_id)
+            .field("Identifier", identifier)
+            .field("Name", name)
+            .field("Role", role)
+    }
+
+    fn extraction_id(&mut self) -> u64 {
+        self.rng.random_range(0..self.extractions)
+    }
+
+    fn chat(&mut self, messages: usize) -> Model {
+        let extraction_id = self.extraction_id();
+        let source = self.pick(&CHAT_SOURCES);
+        let people: Vec<(String, String)> = (0..self.rng.random_range(2..5)).map(|_| (self.phone_number(), self.name())).collect();
+        let participants: Vec<ModelBuilder> = people
+            .iter()
+            .map(|(identifier, name)| self.party(extraction_id, identifier, name, "General"))
+            .collect();
+        let mut time = self.time();
+        let mut chat_messages = Vec::with_capacity(messages);
+        for _ in 0..messages {
+            time += Duration::seconds(self.rng.random_range(1..6 * 3600));
+            let from = self.rng.random_range(0..people.len());
+            let (identifier, name) = people[from].clone();
+            let from = self.party(extraction_id, &identifier, &name, "From");
+            let to: Vec<ModelBuilder> = people
+                .iter()
+                .filter(|(other, _)| *other != identifier)
+                .map(|(identifier, name)| self.party(extraction_id, identifier, name, "To"))
+                .collect();
+            let body = self.sentence();
+            let status = if self.rng.random_bool(0.5) { "Read" } else { "Sent" };
+            chat_messages.push(
+                self.model("InstantMessage", extraction_id)
+                    .field("Source", source)
+                    .field("Body", &body)
+                    .typed_field("TimeStamp", "TimeStamp", &Self::timestamp(time))
+                    .field("Status", status)
+                    .model("From", from)
+                    .models("To", "Party", to),
+            );
+        }
+        let id = self.hex(8);
+        self.model("Chat", extraction_id)
+            .field("Source", source)
+            .field("Id", &id)
+            .field("Name", &people[0].1)
+            .models("Participants", "Party", participants)
+            .models("Messages", "InstantMessage", chat_messages)
+            .build()
+    }
+
+    fn contact(&mut self) -> Model {
+        let extraction_id = self.extraction_id();
+        let name = self.name();
+        let mut entries = vec![
+            ModelBuilder::new("PhoneNumber")
+                .id(&self.uuid())
+                .extraction_id(extraction_id)
+                .field("Category", "Mobile")
+                .field("Value", &self.phone_number()),
+        ];
+        if self.rng.random_bool(0.5) {
+            let email = format!("{}@example.com", name.to_lowercase().replace(' ', "."));
+            entries.push(
+                ModelBuilder::new("EmailAddress")
+                    .id(&self.uuid())
+                    .extraction_id(extraction_id)
+                    .field("Category", "Home")
+                    .field("Value", &email),
+            );
+        }
+        let source = self.pick(&CHAT_SOURCES);
+        self.model("Contact", extraction_id)
+            .field("Source", source)
+            .field("Name", &name)
+            .models("Entries", "ContactEntry", entries)
+            .build()
+    }
+
+    fn call(&mut self) -> Model {
+        let extraction_id = self.extraction_id();
+        let direction = if self.rng.random_bool(0.5) { "Incoming" } else { "Outgoing" };
+        let duration = self.rng.random_range(0..3600);
+        let (identifier, name) = (self.phone_number(), self.name());
+        let role = if direction == "Incoming" { "From" } else { "To" };
+        let party = self.party(extraction_id, &identifier, &name, role);
+        let time = self.time();
+        self.model("Call", extraction_id)
+            .field("Source", "Phone")
+            .field("Direction", direction)
+            .field("Type", if duration == 0 { "Missed" } else { direction })
+            .typed_field("TimeStamp", "TimeStamp", &Self::timestamp(time))
+            .typed_field("Duration", "TimeSpan", &format!("{:02}:{:02}:{:02}", duration / 3600, duration / 60 % 60, duration % 60))
+            .models("Parties", "Party", [party])
+            .build()
+    }
+
+    fn location(&mut self) -> Model {
+        let extraction_id = self.extraction_id();
+        let latitude = 48.2 + self.rng.random_range(-0.5..0.5);
+        let longitude = 16.37 + self.rng.random_range(-0.5..0.5);
+        let position = ModelBuilder::new("Coordinate")
+            .id(&self.uuid())
+            .extraction_id(extraction_id)
+            .typed_field("Latitude", "Double", &format!("{:.6}", latitude))
+            .typed_field("Longitude", "Double", &format!("{:.6}", longitude));
+        let time = self.time();
+        let description = self.sentence();
+        self.model("Location", extraction_id)
+            .typed_field("TimeStamp", "TimeStamp", &Self::timestamp(time))
+            .field("Description", &description)
+            .field("Category", "Visits")
+            .model("Position", position)
+            .build()
+    }
+
+    fn file(&mut self, index: usize) -> File {
+        let extraction_id = self.extraction_id();
+        let name = format!("IMG_{:04}.JPG", index);
+        let created = self.time();
+        let modified = created + Duration::seconds(self.rng.random_range(0..3600));
+        let timestamps = [("CreationTime", created), ("ModifyTime", modified), ("AccessTime", modified)];
+        let hashes = [("MD5", self.hex(16)), ("SHA256", self.hex(32))];
+        File {
+            fs: "Logical".to_string(),
+            fsid: self.uuid(),
+            path: format!("/private/var/mobile/Media/DCIM/100APPLE/{}", name),
+            size: self.rng.random_range(10_000..5_000_000),
+            id: self.uuid(),
+            extraction_id,
+            deleted: self.deleted_state().to_string(),
+            embedded: "false".to_string(),
+            is_related: "False".to_string(),
+            access_info: Some(AccessInfo {
+                timestamps: timestamps
+                    .into_iter()
+                    .map(|(name, time)| Timestamp { name: name.to_string(), text: Self::timestamp(time), other_attributes: vec![] })
+                    .collect(),
+            }),
+            metadata: vec![
+                Metadata {
+                    section: "File".to_string(),
+                    items: vec![item("Local Path", &format!("files\\Image\\{}", name))],
+                    other_attributes: vec![],
+                },
+                Metadata {
+                    section: "MetaData".to_string(),
+                    items: hashes.iter().map(|(name, hash)| item(name, hash)).collect(),
+                    other_attributes: vec![],
+                },
+            ],
+            other_attributes: vec![],
+        }
+    }
+}
+
+fn item(name: &str, text: &str) -> Item {
+    Item { id: None, name: name.to_string(), group: None, source_extraction: None, text: text.to_string(), other_attributes: vec![] }
+}
+
+
+/// Generates a synthetic report with realistic looking chats, contacts,
+/// calls, locations, tagged files and extraction images.
+pub fn generate(config: &GeneratorConfig) -> Project {
+    let extractions = config.extractions.max(1);
+    let mut generator = Generator {
+        rng: StdRng::seed_from_u64(config.seed),
+        extractions: extractions as u64,
+        start: Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).single().unwrap_or_default(),
+    };
+    let id = generator.uuid();
+    let mut builder = ProjectBuilder::new(&format!("Synthetic report {}", config.seed))
+        .id(&id)
+        .case_field("Case Number", "CaseNumber", &format!("SYN-{:04}", config.seed % 10_000))
+        .case_field("Examiner Name", "ExaminerName", "Synthetic Examiner");
+    for index in 0..extractions {
+        let (manufacturer, model, os) = *DEVICES.choose(&mut generator.rng).unwrap_or(&DEVICES[0]);
+        let device_name = format!("{} {}", manufacturer, model);
+        let extraction_id = Some(index as u64);
+        builder = builder
+            .extraction(&format!("Logical {}", index + 1), "Logical", &device_name)
+            .metadata_item("Device Info", "DeviceInfoSelectedManufacturer", manufacturer, extraction_id)
+            .metadata_item("Device Info", "DeviceInfoDetectedPhoneModel", model, extraction_id)
+            .metadata_item("Device Info", "DeviceInfoOSType", os, extraction_id)
+            .metadata_item("Device Info", "DeviceInfoTimeZone", "Europe/Vienna", extraction_id)
+            .metadata_item("Device Info", "DeviceInfoStorageCapacity", "64 GB", extraction_id);
+    }
+    for index in 0..config.images {
+        let extraction_id = generator.extraction_id();
+        builder = builder.image(Image {
+            key: "FileDump".to_string(),
+            path: format!("Extraction_{}.zip", index + 1),
+            size: generator.rng.random_range(1_000_000_000..64_000_000_000),
+            dtype: "File".to_string(),
+            verify: "NoSourceValues".to_string(),
+            extraction_id,
+            other_attributes: vec![],
+        });
+    }
+    for index in 0..config.tagged_files {
+        builder = builder.file(generator.file(index + 1));
+    }
+    for _ in 0..config.chats {
+        builder = builder.model(generator.chat(config.messages_per_chat));
+    }
+    for _ in 0..config.contacts {
+        builder = builder.model(generator.contact());
+    }
+    for _ in 0..config.calls {
+        builder = builder.model(generator.call());
+    }
+    for _ in 0..config.locations {
+        builder = builder.model(generator.location());
+    }
+    builder.build()
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_generate_0() -> Result<(), Box<dyn std::error::Error>> {
+        let config = GeneratorConfig { extractions: 2, chats: 3, messages_per_chat: 4, ..GeneratorConfig::default() };
+        let project = generate(&config);
+        assert_eq!(project, generate(&config));
+        assert_ne!(project, generate(&GeneratorConfig { seed: 1, ..config.clone() }));
+        assert_eq!(project.model_count, (3 + 20 + 20 + 20).to_string());
+        assert_eq!(project.source_extractions.infos.len(), 2);
+        assert_eq!(project.tagged_files.as_ref().map(|tagged_files| tagged_files.files.len()), Some(20));
+        assert!(project.validate_extraction_refs().is_ok());
+        assert_eq!(project.device_infos().len(), 2);
+        let graph = project.model_graph();
+        assert_eq!(graph.len().to_string(), project.node_count);
+        assert_eq!(graph.models().filter(|model| model.dtype == "InstantMessage").count(), 12);
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_no_extractions() {
+        let project = generate(&GeneratorConfig { extractions: 0, ..GeneratorConfig::default() });
+        assert_eq!(project.source_extractions.infos.len(), 1);
+        assert!(project.validate_extraction_refs().is_ok());
+    }
+}
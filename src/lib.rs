@@ -1,3 +1,8 @@
+pub mod models;
+pub mod export;
+pub mod generator;
+pub mod package;
+
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
 }
@@ -1,28 +1,696 @@
-use models::Project;
+use ufdr::{
+    export::{
+        case_uco, dfxml, email::EmailExporter, geo::GeoExporter, html::{self, HtmlExporter}, icalendar, jsonl,
+        opensearch::OpenSearchExporter, csv::CsvExporter, timeline::TimelineFormat, transcript::{self, TranscriptFormat},
+        vcard::VcardExporter,
+    },
+    generator::{generate, GeneratorConfig},
+    models::{CaseField, Project, ReportStats, ReportStream},
+    package::Package,
+};
+use clap::{builder::RangedU64ValueParser, Parser, Subcommand, ValueEnum};
 use quick_xml::{events::Event, Reader};
-use std::{fs::File, io::BufReader};
+use serde_json::{json, Value as JsonValue};
+use std::{
+    fs::File, io::{self, BufReader, BufWriter, Write}, path::{Path, PathBuf}, process::ExitCode,
+};
 
-mod models;
+#[derive(Parser)]
+#[command(version, about = "Inspect and convert Cellebrite UFDR reports")]
+struct Cli {
+    /// Write machine-readable JSON to standard output
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the parsed report
+    Print {
+        /// A report.xml, a .ufdr file or an extracted package directory
+        report: PathBuf,
+    },
+    /// Show case, extraction and device information
+    Info {
+        /// A report.xml, a .ufdr file or an extracted package directory
+        report: PathBuf,
+    },
+    /// List model types with their number of models
+    Types {
+        /// A report.xml, a .ufdr file or an extracted package directory
+        report: PathBuf,
+    },
+    /// List decoded models
+    Models {
+        /// A report.xml, a .ufdr file or an extracted package directory
+        report: PathBuf,
+        /// Only models of this type, e.g. Chat
+        #[arg(short = 't', long = "type")]
+        model_type: Option<String>,
+        /// Only models of this extraction
+        #[arg(short, long)]
+        extraction: Option<u64>,
+    },
+    /// List tagged files
+    Files {
+        /// A report.xml, a .ufdr file or an extracted package directory
+        report: PathBuf,
+        /// Only files of this extraction
+        #[arg(short, long)]
+        extraction: Option<u64>,
+    },
+    /// Summarise model types, deletion, decoding confidence, extractions,
+    /// tagged files and the time range covered
+    Stats {
+        /// A report.xml, a .ufdr file or an extracted package directory
+        report: PathBuf,
+    },
+    /// Convert the report to another format
+    Export {
+        /// A report.xml, a .ufdr file or an extracted package directory
+        report: PathBuf,
+        #[arg(short, long, value_enum)]
+        format: ExportFormat,
+        /// Output file or directory; single-file formats write to standard
+        /// output if omitted
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// OpenSearch endpoint to send the documents to, e.g. http://localhost:9200
+        #[cfg(feature = "opensearch")]
+        #[arg(long)]
+        endpoint: Option<String>,
+    },
+    /// Write a synthetic report.xml
+    Generate {
+        /// Output file, standard output if omitted
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        #[arg(long, default_value_t = 1, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+        extractions: usize,
+        #[arg(long, default_value_t = 5)]
+        chats: usize,
+        #[arg(long, default_value_t = 20)]
+        messages_per_chat: usize,
+        #[arg(long, default_value_t = 20)]
+        contacts: usize,
+        #[arg(long, default_value_t = 20)]
+        calls: usize,
+        #[arg(long, default_value_t = 20)]
+        locations: usize,
+        #[arg(long, default_value_t = 20)]
+        tagged_files: usize,
+        #[arg(long, default_value_t = 1)]
+        images: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ExportFormat {
+    Jsonl,
+    Csv,
+    Html,
+    TranscriptText,
+    TranscriptMarkdown,
+    TranscriptHtml,
+    Vcard,
+    Icalendar,
+    Geojson,
+    Kml,
+    TimelineCsv,
+    TimelineJsonl,
+    Bodyfile,
+    CaseUco,
+    Dfxml,
+    Eml,
+    Mbox,
+    Opensearch,
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl ExportFormat {
+    /// Formats written from the parsed project as a single stream, to a file
+    /// or standard output.
+    fn is_stream(&self) -> bool {
+        matches!(
+            self,
+            ExportFormat::Icalendar
+                | ExportFormat::Geojson
+                | ExportFormat::Kml
+                | ExportFormat::TimelineCsv
+                | ExportFormat::TimelineJsonl
+                | ExportFormat::Bodyfile
+                | ExportFormat::CaseUco
+                | ExportFormat::Dfxml
+        )
+    }
+}
+
+/// A parsed report and, unless it was read from a bare report.xml, the
+/// package holding its files.
+struct Report {
+    project: Project,
+    package: Option<Package>,
+    path: PathBuf,
+}
+
+impl Report {
+    fn open(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if is_xml(path) {
+            let project = read_project(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+            return Ok(Report { project, package: None, path: path.to_path_buf() });
+        }
+        let mut package = Package::open(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+        let project = package.project().map_err(|error| format!("{}: {}", path.display(), error))?;
+        Ok(Report { project, package: Some(package), path: path.to_path_buf() })
+    }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let file = File::open("data/xml/report.xml")?;
+    /// Directory the tagged files' `Local Path`s are relative to, if the
+    /// files are on disk.
+    fn files_dir(&self) -> Option<PathBuf> {
+        match &self.package {
+            Some(Package::Directory(dir)) => Some(dir.clone()),
+            Some(Package::Zip(_)) => None,
+            None => self.path.parent().map(Path::to_path_buf),
+        }
+    }
+}
+
+fn is_xml(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("xml"))
+}
+
+fn read_project(path: &Path) -> Result<Project, Box<dyn std::error::Error>> {
+    let file = File::open(path)?;
     let mut reader = Reader::from_reader(BufReader::new(file));
     let mut buf = Vec::new();
     loop {
-        let event = reader.read_event_into(&mut buf)?;
-        match event {
-            Event::Start(ref e) if e.name().as_ref() == b"project" => {
-                let proj = Project::parse_one(e, &mut reader)?;
-                println!("{:#?}", proj);
-                break;
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(ref e) if e.name().as_ref() == b"project" => return Project::parse_one(e, &mut reader),
+            Event::Eof => return Err(format!("no project in {}", path.display()).into()),
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+fn info_json(project: &Project) -> JsonValue {
+    let case_info = project.case_information.case_info();
+    let mut case = serde_json::Map::new();
+    for (key, case_field) in [
+        ("case_number", CaseField::CaseNumber),
+        ("case_name", CaseField::CaseName),
+        ("suspect", CaseField::Suspect),
+        ("examiner", CaseField::Examiner),
+        ("evidence_number", CaseField::EvidenceNumber),
+        ("department", CaseField::Department),
+        ("location", CaseField::Location),
+    ] {
+        case.insert(key.to_string(), json!(case_info.get(case_field)));
+    }
+    let custom_fields: serde_json::Map<String, JsonValue> = case_info.custom_fields.into_iter().map(|(name, value)| (name, json!(value))).collect();
+    case.insert("custom_fields".to_string(), JsonValue::Object(custom_fields));
+    let extractions: Vec<JsonValue> = project
+        .source_extractions
+        .infos
+        .iter()
+        .map(|info| {
+            json!({
+                "id": info.id,
+                "name": info.name,
+                "type": info.dtype,
+                "device_name": info.device_name,
+                "full_name": info.full_name,
+                "partial": info.is_partial_data.eq_ignore_ascii_case("true"),
+            })
+        })
+        .collect();
+    let devices: Vec<JsonValue> = project
+        .device_infos()
+        .into_iter()
+        .map(|device_info| {
+            json!({
+                "extraction_id": device_info.extraction_id,
+                "name": device_info.name,
+                "manufacturer": device_info.manufacturer,
+                "model_number": device_info.model_number,
+                "os_type": device_info.os_type,
+                "os_version": device_info.os_version,
+                "time_zone": device_info.time_zone,
+                "storage_capacity": device_info.storage_capacity,
+                "extraction_start": device_info.extraction_start.map(|time| time.to_rfc3339()),
+                "extraction_end": device_info.extraction_end.map(|time| time.to_rfc3339()),
+            })
+        })
+        .collect();
+    json!({
+        "project": {
+            "id": project.id,
+            "name": project.name,
+            "report_version": project.report_version,
+            "extraction_type": project.extraction_type,
+        },
+        "case": case,
+        "extractions": extractions,
+        "devices": devices,
+    })
+}
+
+fn write_info<W: Write>(project: &Project, out: &mut W) -> io::Result<()> {
+    let case_info = project.case_information.case_info();
+    let mut rows: Vec<(&str, String)> = vec![("Project", project.name.clone()), ("Report version", project.report_version.clone())];
+    for (label, case_field) in [
+        ("Case number", CaseField::CaseNumber),
+        ("Case name", CaseField::CaseName),
+        ("Suspect", CaseField::Suspect),
+        ("Examiner", CaseField::Examiner),
+        ("Evidence number", CaseField::EvidenceNumber),
+        ("Department", CaseField::Department),
+        ("Location", CaseField::Location),
+    ] {
+        if let Some(value) = case_info.get(case_field) {
+            rows.push((label, value.to_string()));
+        }
+    }
+    rows.extend(case_info.custom_fields.iter().map(|(name, value)| (name.as_str(), value.clone())));
+    for (label, value) in rows {
+        writeln!(out, "{:<20}{}", label, value)?;
+    }
+
+    let device_infos = project.device_infos();
+    for info in &project.source_extractions.infos {
+        writeln!(out)?;
+        writeln!(out, "Extraction {}: {} ({})", info.id, info.name, info.dtype)?;
+        let mut rows = vec![("Device name", Some(info.device_name.clone()))];
+        if let Some(device_info) = device_infos.iter().find(|device_info| device_info.extraction_id == info.id) {
+            let os = [&device_info.os_type, &device_info.os_version].into_iter().flatten().cloned().collect::<Vec<_>>().join(" ");
+            rows.extend([
+                ("Manufacturer", device_info.manufacturer.clone()),
+                ("Model", device_info.model_number.clone().or(device_info.name.clone())),
+                ("OS", Some(os)),
+                ("Time zone", device_info.time_zone.clone()),
+                ("Storage", device_info.storage_capacity.map(|bytes| format!("{} bytes", bytes))),
+                ("Extraction start", device_info.extraction_start.map(|time| time.to_rfc3339())),
+                ("Extraction end", device_info.extraction_end.map(|time| time.to_rfc3339())),
+            ]);
+        }
+        for (label, value) in rows {
+            if let Some(value) = value.filter(|value| !value.is_empty()) {
+                writeln!(out, "  {:<18}{}", label, value)?;
             }
+        }
+    }
+    Ok(())
+}
 
-            Event::Eof => break,
+/// Top-level model types with their number of models, in report order.
+fn type_counts(project: &Project) -> Vec<(&str, usize)> {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for model_type in project.decoded_data.iter().flat_map(|decoded_data| decoded_data.model_types.iter()) {
+        match counts.iter_mut().find(|(dtype, _)| *dtype == model_type.dtype) {
+            Some((_, count)) => *count += model_type.models.len(),
+            None => counts.push((&model_type.dtype, model_type.models.len())),
+        }
+    }
+    counts
+}
 
-            _ => {}
+fn stats_json(stats: &ReportStats) -> JsonValue {
+    let model_types: Vec<JsonValue> = stats
+        .model_types
+        .iter()
+        .map(|model_type| {
+            json!({
+                "type": model_type.dtype,
+                "count": model_type.count,
+                "deleted_states": model_type.deleted_states,
+                "deleted_ratio": model_type.deleted_ratio(),
+            })
+        })
+        .collect();
+    let extractions: Vec<JsonValue> = stats
+        .extractions
+        .iter()
+        .map(|extraction| json!({"id": extraction.extraction_id, "name": extraction.name, "models": extraction.models, "files": extraction.files}))
+        .collect();
+    let file_extensions: serde_json::Map<String, JsonValue> = stats
+        .file_extensions
+        .iter()
+        .map(|(extension, extension_stats)| (extension.clone(), json!({"count": extension_stats.count, "bytes": extension_stats.bytes})))
+        .collect();
+    json!({
+        "models": stats.models(),
+        "model_types": model_types,
+        "deleted_states": stats.deleted_states,
+        "decoding_confidence": stats.decoding_confidence,
+        "extractions": extractions,
+        "files": stats.files,
+        "file_bytes": stats.file_bytes,
+        "file_extensions": file_extensions,
+        "first": stats.first.map(|time| time.to_rfc3339()),
+        "last": stats.last.map(|time| time.to_rfc3339()),
+    })
+}
+
+fn write_stats<W: Write>(stats: &ReportStats, out: &mut W) -> io::Result<()> {
+    let counts = |counts: &std::collections::BTreeMap<String, usize>| {
+        let counts: Vec<String> = counts
+            .iter()
+            .map(|(name, count)| format!("{} {}", if name.is_empty() { "(none)" } else { name }, count))
+            .collect();
+        counts.join(", ")
+    };
+    writeln!(out, "{:<22}{}", "Models", stats.models())?;
+    for model_type in &stats.model_types {
+        writeln!(out, "  {:<20}{:>8}  {:>5.1}% deleted", model_type.dtype, model_type.count, model_type.deleted_ratio() * 100.0)?;
+    }
+    writeln!(out, "{:<22}{}", "Deleted state", counts(&stats.deleted_states))?;
+    writeln!(out, "{:<22}{}", "Decoding confidence", counts(&stats.decoding_confidence))?;
+    writeln!(out, "{:<22}{} ({} bytes)", "Files", stats.files, stats.file_bytes)?;
+    for (extension, extension_stats) in &stats.file_extensions {
+        let extension = if extension.is_empty() { "(none)" } else { extension };
+        writeln!(out, "  {:<20}{:>8}  {} bytes", extension, extension_stats.count, extension_stats.bytes)?;
+    }
+    writeln!(out, "Extractions")?;
+    for extraction in &stats.extractions {
+        let label = format!("{}: {}", extraction.extraction_id, extraction.name);
+        writeln!(out, "  {:<20}{:>8} models, {} files", label, extraction.models, extraction.files)?;
+    }
+    if let (Some(first), Some(last)) = (stats.first, stats.last) {
+        writeln!(out, "{:<22}{} to {}", "Time range", first.to_rfc3339(), last.to_rfc3339())?;
+    }
+    Ok(())
+}
+
+/// Writes to the new file `output`, or to standard output if omitted.
+fn output_writer(output: Option<&Path>) -> io::Result<Box<dyn Write>> {
+    Ok(match output {
+        Some(path) => Box::new(BufWriter::new(File::create_new(path)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    })
+}
+
+fn required_output(format: ExportFormat, output: Option<&Path>) -> Result<&Path, Box<dyn std::error::Error>> {
+    output.ok_or_else(|| {
+        let name = format.to_possible_value().map(|value| value.get_name().to_string()).unwrap_or_default();
+        format!("--output is required for the {} format", name).into()
+    })
+}
+
+/// Streams the models to JSON lines without building the project.
+fn export_jsonl(report: &Path, output: Option<&Path>) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut writer = output_writer(output)?;
+    if is_xml(report) {
+        jsonl::export_stream(ReportStream::from_path(report)?, &mut writer)?;
+    } else {
+        let mut package = Package::open(report).map_err(|error| format!("{}: {}", report.display(), error))?;
+        jsonl::export_stream(package.report_stream()?, &mut writer)?;
+    }
+    writer.flush()?;
+    Ok(output.map(Path::to_path_buf).into_iter().collect())
+}
+
+fn export(
+    report: &mut Report,
+    format: ExportFormat,
+    output: Option<&Path>,
+    endpoint: Option<&str>,
+) -> Result<(Vec<PathBuf>, Option<usize>), Box<dyn std::error::Error>> {
+    let project = &report.project;
+    if format.is_stream() {
+        let mut writer = output_writer(output)?;
+        match format {
+            ExportFormat::Icalendar => icalendar::write_calendar(project, &mut writer)?,
+            ExportFormat::Geojson => GeoExporter::new().write_geojson(project, &mut writer)?,
+            ExportFormat::Kml => GeoExporter::new().write_kml(project, &mut writer)?,
+            ExportFormat::TimelineCsv => TimelineFormat::Csv.write(&project.timeline(), &mut writer)?,
+            ExportFormat::TimelineJsonl => TimelineFormat::Jsonl.write(&project.timeline(), &mut writer)?,
+            ExportFormat::Bodyfile => TimelineFormat::Bodyfile.write(&project.timeline(), &mut writer)?,
+            ExportFormat::CaseUco => case_uco::write_case(project, &mut writer)?,
+            ExportFormat::Dfxml => dfxml::write_dfxml(project, &mut writer)?,
+            _ => unreachable!(),
         }
+        writer.flush()?;
+        return Ok((output.map(Path::to_path_buf).into_iter().collect(), None));
+    }
 
-        buf.clear();
+    let output = required_output(format, output)?;
+    let paths = match format {
+        ExportFormat::Csv => CsvExporter::new().export_project(project, output)?,
+        ExportFormat::Html => match report.files_dir() {
+            Some(dir) => HtmlExporter::new().files_root(&html::file_url(&dir)?).export_project(project, None, output)?,
+            None => HtmlExporter::new().export_project(project, report.package.as_mut(), output)?,
+        },
+        ExportFormat::TranscriptText => transcript::export_project(project, output, TranscriptFormat::Text)?,
+        ExportFormat::TranscriptMarkdown => transcript::export_project(project, output, TranscriptFormat::Markdown)?,
+        ExportFormat::TranscriptHtml => transcript::export_project(project, output, TranscriptFormat::Html)?,
+        ExportFormat::Vcard => VcardExporter::new().export_project(project, report.package.as_mut(), output)?,
+        ExportFormat::Eml => EmailExporter::new().export_project(project, report.package.as_mut(), output)?,
+        ExportFormat::Mbox => EmailExporter::new().mbox(true).export_project(project, report.package.as_mut(), output)?,
+        ExportFormat::Opensearch => {
+            let exporter = OpenSearchExporter::new();
+            let paths = exporter.export_project(project, output)?;
+            let documents = match endpoint {
+                #[cfg(feature = "opensearch")]
+                Some(endpoint) => Some(exporter.post(project, endpoint)?),
+                #[cfg(not(feature = "opensearch"))]
+                Some(_) => unreachable!(),
+                None => None,
+            };
+            return Ok((paths, documents));
+        }
+        #[cfg(feature = "sqlite")]
+        ExportFormat::Sqlite => export_sqlite(project, output)?,
+        _ => unreachable!(),
+    };
+    Ok((paths, None))
+}
+
+#[cfg(feature = "sqlite")]
+fn export_sqlite(project: &Project, output: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    ufdr::export::sqlite::export_project(project, output)?;
+    Ok(vec![output.to_path_buf()])
+}
+
+/// Streams the report once per model type without building the project.
+#[cfg(feature = "parquet")]
+fn export_parquet(report: &Path, output: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    use ufdr::export::parquet::ParquetExporter;
+
+    if is_xml(report) {
+        return ParquetExporter::new().export_path(report, output);
+    }
+    match Package::open(report).map_err(|error| format!("{}: {}", report.display(), error))? {
+        Package::Directory(dir) => ParquetExporter::new().export_path(dir.join("report.xml"), output),
+        mut package => ParquetExporter::new().export(
+            || Ok(ReportStream::new(Reader::from_reader(io::Cursor::new(package.read("report.xml")?)))),
+            output,
+        ),
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let mut out = io::stdout().lock();
+    match cli.command {
+        Command::Print { report } => writeln!(out, "{:#?}", Report::open(&report)?.project)?,
+        Command::Info { report } => {
+            let report = Report::open(&report)?;
+            if cli.json {
+                writeln!(out, "{}", serde_json::to_string_pretty(&info_json(&report.project))?)?;
+            } else {
+                write_info(&report.project, &mut out)?;
+            }
+        }
+        Command::Types { report } => {
+            let report = Report::open(&report)?;
+            let counts = type_counts(&report.project);
+            if cli.json {
+                let counts: Vec<JsonValue> = counts.iter().map(|(dtype, count)| json!({"type": dtype, "count": count})).collect();
+                writeln!(out, "{}", serde_json::to_string_pretty(&counts)?)?;
+            } else {
+                for (dtype, count) in counts {
+                    writeln!(out, "{:>8}  {}", count, dtype)?;
+                }
+            }
+        }
+        Command::Models { report, model_type, extraction } => {
+            let report = Report::open(&report)?;
+            let project = &report.project;
+            let models = project
+                .decoded_data
+                .iter()
+                .flat_map(|decoded_data| decoded_data.model_types.iter())
+                .filter(|model_type_| model_type.as_ref().is_none_or(|dtype| model_type_.dtype == *dtype))
+                .flat_map(|model_type| model_type.models.iter())
+                .filter(|model| extraction.is_none_or(|extraction_id| model.extraction_id == extraction_id));
+            if cli.json {
+                let models: Vec<JsonValue> = models.map(|model| jsonl::model_to_json_line(model, model.extraction(project))).collect();
+                writeln!(out, "{}", serde_json::to_string_pretty(&models)?)?;
+            } else {
+                let csv = CsvExporter::new();
+                for model in models {
+                    let summary: Vec<String> = csv
+                        .flatten(model)
+                        .into_iter()
+                        .filter(|(_, value)| !value.is_empty())
+                        .map(|(name, value)| format!("{}: {}", name, value.replace(['\r', '\n', '\t'], " ")))
+                        .collect();
+                    writeln!(out, "{}\t{}\t{}\t{}", model.id, model.dtype, model.deleted_state, summary.join("; "))?;
+                }
+            }
+        }
+        Command::Files { report, extraction } => {
+            let report = Report::open(&report)?;
+            let files = report
+                .project
+                .tagged_files
+                .iter()
+                .flat_map(|tagged_files| tagged_files.files.iter())
+                .filter(|file| extraction.is_none_or(|extraction_id| file.extraction_id == extraction_id));
+            if cli.json {
+                let files: Vec<JsonValue> = files
+                    .map(|file| {
+                        json!({
+                            "id": file.id,
+                            "path": file.path,
+                            "fs": file.fs,
+                            "size": file.size,
+                            "deleted": file.deleted,
+                            "extraction_id": file.extraction_id,
+                            "local_path": file.local_path(),
+                            "md5": file.hash("MD5"),
+                            "sha256": file.hash("SHA256"),
+                        })
+                    })
+                    .collect();
+                writeln!(out, "{}", serde_json::to_string_pretty(&files)?)?;
+            } else {
+                for file in files {
+                    writeln!(out, "{}\t{}\t{}\t{}", file.id, file.size, file.deleted, file.path)?;
+                }
+            }
+        }
+        Command::Stats { report } => {
+            let report = Report::open(&report)?;
+            let stats = report.project.stats();
+            if cli.json {
+                writeln!(out, "{}", serde_json::to_string_pretty(&stats_json(&stats))?)?;
+            } else {
+                write_stats(&stats, &mut out)?;
+            }
+        }
+        #[cfg(feature = "opensearch")]
+        Command::Export { report, format, output, endpoint } => export_command(cli.json, &mut out, &report, format, output, endpoint)?,
+        #[cfg(not(feature = "opensearch"))]
+        Command::Export { report, format, output } => export_command(cli.json, &mut out, &report, format, output, None)?,
+        Command::Generate { output, seed, extractions, chats, messages_per_chat, contacts, calls, locations, tagged_files, images } => {
+            let config = GeneratorConfig { seed, extractions, chats, messages_per_chat, contacts, calls, locations, tagged_files, images };
+            let project = generate(&config);
+            let mut writer: Box<dyn Write> = match output {
+                Some(path) => Box::new(BufWriter::new(File::create_new(path)?)),
+                None => Box::new(BufWriter::new(out)),
+            };
+            project.write_report(&mut writer)?;
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn export_command<W: Write>(
+    json: bool,
+    out: &mut W,
+    report: &Path,
+    format: ExportFormat,
+    output: Option<PathBuf>,
+    endpoint: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (paths, documents) = match format {
+        ExportFormat::Jsonl => (export_jsonl(report, output.as_deref())?, None),
+        #[cfg(feature = "parquet")]
+        ExportFormat::Parquet => (export_parquet(report, required_output(format, output.as_deref())?)?, None),
+        _ => export(&mut Report::open(report)?, format, output.as_deref(), endpoint.as_deref())?,
+    };
+    if output.is_none() {
+        // The export itself went to standard output.
+        return Ok(());
+    }
+    if json {
+        let paths: Vec<String> = paths.iter().map(|path| path.display().to_string()).collect();
+        writeln!(out, "{}", serde_json::to_string_pretty(&json!({"paths": paths, "documents": documents}))?)?;
+    } else {
+        for path in paths {
+            writeln!(out, "{}", path.display())?;
+        }
+        if let Some(documents) = documents {
+            writeln!(out, "{} documents indexed", documents)?;
+        }
     }
     Ok(())
 }
+
+/// Exits with 0 on success, 1 if the command failed and 2 on usage errors.
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) if error.downcast_ref::<io::Error>().is_some_and(|error| error.kind() == io::ErrorKind::BrokenPipe) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use ufdr::models::{ModelBuilder, ProjectBuilder};
+    use clap::CommandFactory;
+    use super::*;
+
+    #[test]
+    fn test_cli_0() {
+        Cli::command().debug_assert();
+        let cli = Cli::try_parse_from(["ufdr-rs", "models", "report.ufdr", "--type", "Chat", "--json"]).unwrap();
+        assert!(cli.json);
+        assert!(matches!(cli.command, Command::Models { model_type: Some(ref dtype), extraction: None, .. } if dtype == "Chat"));
+        assert!(Cli::try_parse_from(["ufdr-rs", "export", "report.xml", "--format", "docx"]).is_err());
+        assert!(Cli::try_parse_from(["ufdr-rs", "print"]).is_err());
+        let error = required_output(ExportFormat::Csv, None).unwrap_err();
+        assert_eq!(error.to_string(), "--output is required for the csv format");
+        assert_eq!(required_output(ExportFormat::Csv, Some(Path::new("out"))).unwrap(), Path::new("out"));
+    }
+
+    #[test]
+    fn test_info_0() -> Result<(), Box<dyn std::error::Error>> {
+        let project = ProjectBuilder::new("Test")
+            .case_field("Case Number", "String", "2020-042")
+            .case_field("Seized at", "String", "Baker Street")
+            .extraction("Logical", "Logical", "iPhone")
+            .metadata_item("Device Info", "DeviceInfoDetectedManufacturer", "Apple", Some(0))
+            .model(ModelBuilder::new("Chat"))
+            .model(ModelBuilder::new("Contact"))
+            .model(ModelBuilder::new("Chat"))
+            .build();
+        let info = info_json(&project);
+        assert_eq!(info["case"]["case_number"], "2020-042");
+        assert_eq!(info["case"]["custom_fields"]["Seized at"], "Baker Street");
+        assert_eq!(info["devices"][0]["manufacturer"], "Apple");
+        let mut out = Vec::new();
+        write_info(&project, &mut out)?;
+        let text = String::from_utf8(out)?;
+        assert!(text.contains("Case number         2020-042\nSeized at           Baker Street\n"));
+        assert!(text.contains("Extraction 0: Logical (Logical)\n  Device name       iPhone\n  Manufacturer      Apple\n"));
+        assert_eq!(type_counts(&project), [("Chat", 2), ("Contact", 1)]);
+        let stats = stats_json(&project.stats());
+        assert_eq!(stats["model_types"][0], json!({"type": "Chat", "count": 2, "deleted_states": {"Intact": 2}, "deleted_ratio": 0.0}));
+        assert_eq!(stats["extractions"][0], json!({"id": 0, "name": "Logical", "models": 3, "files": 0}));
+        Ok(())
+    }
+}
@@ -1,10 +1,11 @@
-use std::io::BufRead;
-use quick_xml::{events::{Event}, Reader};
+use std::io::{BufRead, Write};
+use quick_xml::{events::{BytesEnd, BytesStart, Event}, Reader, Writer};
 use super::{ParseError, Timestamp};
 
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AccessInfo {
     pub timestamps: Vec<Timestamp>
 }
@@ -46,6 +47,15 @@ impl AccessInfo {
         }
         Ok(AccessInfo { timestamps })
     }
+
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        writer.write_event(Event::Start(BytesStart::new("accessInfo")))?;
+        for timestamp in &self.timestamps {
+            timestamp.write_one(writer)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("accessInfo")))?;
+        Ok(())
+    }
 }
 
 
@@ -98,10 +108,12 @@ mod test {
                 Timestamp {
                     name: "CreationTime".to_string(),
                     text: "2020-08-08T15:50:58.000+00:00".to_string(),
+                    other_attributes: vec![],
                 },
                 Timestamp {
                     name: "ModifyTime".to_string(),
                     text: "2020-08-08T15:50:58.000+00:00".to_string(),
+                    other_attributes: vec![],
                 }
             ]
         })
@@ -0,0 +1,487 @@
+use uuid::Uuid;
+use super::{
+    AccessInfo, CaseInformation, DataField, DecodedData, ExtractionInfo, Field, File, Image, Images, Item, Metadata,
+    Model, ModelChild, ModelField, ModelGraph, ModelType, MultiField, MultiModelField, Project, SourceExtractions,
+    TaggedFiles, Timestamp, Value,
+};
+
+
+
+/// Fluent construction of a [`Model`], e.g.
+/// `ModelBuilder::new("InstantMessage").field("Body", "hi").model("From", party)`.
+///
+/// Models get a random UUID as id, are `Intact`, `High` confidence and
+/// belong to extraction 0 unless told otherwise.
+#[derive(Debug, PartialEq)]
+pub struct ModelBuilder {
+    model: Model,
+}
+
+impl ModelBuilder {
+    pub fn new(dtype: &str) -> Self {
+        ModelBuilder {
+            model: Model {
+                dtype: dtype.to_string(),
+                id: Uuid::new_v4().to_string(),
+                deleted_state: "Intact".to_string(),
+                decoding_confidence: "High".to_string(),
+                is_related: "False".to_string(),
+                extraction_id: 0,
+                fields: vec![],
+                multi_model_fields: vec![],
+                model_fields: vec![],
+                data_fields: vec![],
+                multi_fields: vec![],
+                children: vec![],
+                other_attributes: vec![],
+            },
+        }
+    }
+
+    pub fn id(mut self, id: &str) -> Self {
+        self.model.id = id.to_string();
+        self
+    }
+
+    pub fn deleted_state(mut self, deleted_state: &str) -> Self {
+        self.model.deleted_state = deleted_state.to_string();
+        self
+    }
+
+    pub fn decoding_confidence(mut self, decoding_confidence: &str) -> Self {
+        self.model.decoding_confidence = decoding_confidence.to_string();
+        self
+    }
+
+    pub fn related(mut self, is_related: bool) -> Self {
+        self.model.is_related = if is_related { "True" } else { "False" }.to_string();
+        self
+    }
+
+    pub fn extraction_id(mut self, extraction_id: u64) -> Self {
+        self.model.extraction_id = extraction_id;
+        self
+    }
+
+    /// Adds a `String` field.
+    pub fn field(self, name: &str, value: &str) -> Self {
+        self.typed_field(name, "String", value)
+    }
+
+    /// Adds a field of type `dtype`, e.g. `TimeStamp` or `Boolean`.
+    pub fn typed_field(mut self, name: &str, dtype: &str, value: &str) -> Self {
+        self.model.children.push(ModelChild::Field(name.to_string()));
+        self.model.fields.push(Field {
+            name: name.to_string(),
+            is_system: None,
+            is_required: None,
+            field_type: None,
+            multiple_lines: None,
+            dtype: Some(dtype.to_string()),
+            text: String::new(),
+            value: Some(Value { dtype: dtype.to_string(), text: value.to_string(), other_attributes: vec![] }),
+            other_attributes: vec![],
+        });
+        self
+    }
+
+    /// Adds a field without a value, written as `<empty />`.
+    pub fn empty_field(mut self, name: &str, dtype: &str) -> Self {
+        self.model.children.push(ModelChild::Field(name.to_string()));
+        self.model.fields.push(Field {
+            name: name.to_string(),
+            is_system: None,
+            is_required: None,
+            field_type: None,
+            multiple_lines: None,
+            dtype: Some(dtype.to_string()),
+            text: String::new(),
+            value: None,
+            other_attributes: vec![],
+        });
+        self
+    }
+
+    pub fn multi_field<'a, I: IntoIterator<Item = &'a str>>(mut self, name: &str, dtype: &str, values: I) -> Self {
+        self.model.children.push(ModelChild::MultiField(name.to_string()));
+        self.model.multi_fields.push(MultiField {
+            name: name.to_string(),
+            dtype: dtype.to_string(),
+            values: values.into_iter().map(|text| Value { dtype: dtype.to_string(), text: text.to_string(), other_attributes: vec![] }).collect(),
+            other_attributes: vec![],
+        });
+        self
+    }
+
+    /// Nests `model` under the modelField `name`.
+    pub fn model<M: Into<Model>>(mut self, name: &str, model: M) -> Self {
+        let model = model.into();
+        self.model.children.push(ModelChild::ModelField(name.to_string()));
+        self.model.model_fields.push(ModelField {
+            name: name.to_string(),
+            dtype: model.dtype.clone(),
+            models: vec![model],
+            other_attributes: vec![],
+        });
+        self
+    }
+
+    /// Appends `models` to the multiModelField `name`, creating it with
+    /// type `dtype` if needed.
+    pub fn models<M, I>(mut self, name: &str, dtype: &str, models: I) -> Self
+    where
+        M: Into<Model>,
+        I: IntoIterator<Item = M>,
+    {
+        let position = match self.model.multi_model_fields.iter().position(|field| field.name == name) {
+            Some(position) => position,
+            None => {
+                self.model.children.push(ModelChild::MultiModelField(name.to_string()));
+                self.model.multi_model_fields.push(MultiModelField {
+                    name: name.to_string(),
+                    dtype: dtype.to_string(),
+                    models: vec![],
+                    other_attributes: vec![],
+                });
+                self.model.multi_model_fields.len() - 1
+            }
+        };
+        self.model.multi_model_fields[position].models.extend(models.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn data_field(mut self, data_field: DataField) -> Self {
+        self.model.children.push(ModelChild::DataField(data_field.name.clone()));
+        self.model.data_fields.push(data_field);
+        self
+    }
+
+    pub fn build(self) -> Model {
+        self.model
+    }
+}
+
+impl From<ModelBuilder> for Model {
+    fn from(builder: ModelBuilder) -> Self {
+        builder.build()
+    }
+}
+
+
+/// Fluent construction of a tagged [`File`], e.g.
+/// `FileBuilder::new("/DCIM/IMG_0001.JPG").size(4).timestamp("CreationTime", time)`.
+///
+/// Files get a random UUID as id, are `Intact`, not embedded and belong to
+/// extraction 0 unless told otherwise.
+#[derive(Debug, PartialEq)]
+pub struct FileBuilder {
+    file: File,
+}
+
+impl FileBuilder {
+    pub fn new(path: &str) -> Self {
+        FileBuilder {
+            file: File {
+                fs: "Logical".to_string(),
+                fsid: "fs".to_string(),
+                path: path.to_string(),
+                size: 0,
+                id: Uuid::new_v4().to_string(),
+                extraction_id: 0,
+                deleted: "Intact".to_string(),
+                embedded: "False".to_string(),
+                is_related: "False".to_string(),
+                access_info: None,
+                metadata: vec![],
+                other_attributes: vec![],
+            },
+        }
+    }
+
+    pub fn id(mut self, id: &str) -> Self {
+        self.file.id = id.to_string();
+        self
+    }
+
+    pub fn fs(mut self, fs: &str) -> Self {
+        self.file.fs = fs.to_string();
+        self
+    }
+
+    pub fn size(mut self, size: u64) -> Self {
+        self.file.size = size;
+        self
+    }
+
+    pub fn extraction_id(mut self, extraction_id: u64) -> Self {
+        self.file.extraction_id = extraction_id;
+        self
+    }
+
+    pub fn deleted(mut self, deleted: &str) -> Self {
+        self.file.deleted = deleted.to_string();
+        self
+    }
+
+    pub fn embedded(mut self, embedded: bool) -> Self {
+        self.file.embedded = if embedded { "True" } else { "False" }.to_string();
+        self
+    }
+
+    /// Adds an `accessInfo` timestamp such as `CreationTime`.
+    pub fn timestamp(mut self, name: &str, text: &str) -> Self {
+        let timestamp = Timestamp { name: name.to_string(), text: text.to_string(), other_attributes: vec![] };
+        self.file.access_info.get_or_insert_with(|| AccessInfo { timestamps: vec![] }).timestamps.push(timestamp);
+        self
+    }
+
+    /// Adds an item to the metadata section `section`, creating it if
+    /// needed.
+    pub fn metadata_item(mut self, section: &str, name: &str, value: &str) -> Self {
+        let item = Item { id: None, name: name.to_string(), group: None, source_extraction: None, text: value.to_string(), other_attributes: vec![] };
+        match self.file.metadata.iter_mut().find(|metadata| metadata.section == section) {
+            Some(metadata) => metadata.items.push(item),
+            None => self.file.metadata.push(Metadata { section: section.to_string(), items: vec![item], other_attributes: vec![] }),
+        }
+        self
+    }
+
+    /// Sets the `Local Path` of the file inside the package.
+    pub fn local_path(self, local_path: &str) -> Self {
+        self.metadata_item("File", "Local Path", local_path)
+    }
+
+    pub fn build(self) -> File {
+        self.file
+    }
+}
+
+impl From<FileBuilder> for File {
+    fn from(builder: FileBuilder) -> Self {
+        builder.build()
+    }
+}
+
+
+/// Fluent construction of a [`Project`]. Models are grouped into one
+/// `modelType` per type in order of first appearance, and `NodeCount` and
+/// `ModelCount` are computed on [`ProjectBuilder::build`].
+#[derive(Debug, PartialEq)]
+pub struct ProjectBuilder {
+    id: String,
+    name: String,
+    report_version: String,
+    extraction_type: String,
+    infos: Vec<ExtractionInfo>,
+    case_fields: Vec<Field>,
+    metadata: Vec<Metadata>,
+    images: Vec<Image>,
+    files: Vec<File>,
+    model_types: Vec<ModelType>,
+}
+
+impl ProjectBuilder {
+    pub fn new(name: &str) -> Self {
+        ProjectBuilder {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            report_version: "7.0.0.0".to_string(),
+            extraction_type: "Logical".to_string(),
+            infos: vec![],
+            case_fields: vec![],
+            metadata: vec![],
+            images: vec![],
+            files: vec![],
+            model_types: vec![],
+        }
+    }
+
+    pub fn id(mut self, id: &str) -> Self {
+        self.id = id.to_string();
+        self
+    }
+
+    pub fn report_version(mut self, report_version: &str) -> Self {
+        self.report_version = report_version.to_string();
+        self
+    }
+
+    pub fn extraction_type(mut self, extraction_type: &str) -> Self {
+        self.extraction_type = extraction_type.to_string();
+        self
+    }
+
+    /// Adds an `extractionInfo`; ids are assigned in order starting at 0.
+    pub fn extraction(mut self, name: &str, dtype: &str, device_name: &str) -> Self {
+        let id = self.infos.len() as u32;
+        self.infos.push(ExtractionInfo {
+            id: u64::from(id),
+            name: name.to_string(),
+            is_custom_name: "False".to_string(),
+            dtype: dtype.to_string(),
+            device_name: device_name.to_string(),
+            full_name: device_name.to_string(),
+            index: id,
+            is_partial_data: "False".to_string(),
+            other_attributes: vec![],
+        });
+        self
+    }
+
+    pub fn case_field(mut self, name: &str, field_type: &str, value: &str) -> Self {
+        self.case_fields.push(Field {
+            name: name.to_string(),
+            is_system: Some("True".to_string()),
+            is_required: Some("False".to_string()),
+            field_type: Some(field_type.to_string()),
+            multiple_lines: Some("False".to_string()),
+            dtype: None,
+            text: value.to_string(),
+            value: None,
+            other_attributes: vec![],
+        });
+        self
+    }
+
+    /// Adds an item to the metadata section `section`, creating it if
+    /// needed.
+    pub fn metadata_item(mut self, section: &str, name: &str, value: &str, source_extraction: Option<u64>) -> Self {
+        let item = Item {
+            id: None,
+            name: name.to_string(),
+            group: None,
+            source_extraction: source_extraction.map(|id| id.to_string()),
+            text: value.to_string(),
+            other_attributes: vec![],
+        };
+        match self.metadata.iter_mut().find(|metadata| metadata.section == section) {
+            Some(metadata) => metadata.items.push(item),
+            None => self.metadata.push(Metadata { section: section.to_string(), items: vec![item], other_attributes: vec![] }),
+        }
+        self
+    }
+
+    pub fn image(mut self, image: Image) -> Self {
+        self.images.push(image);
+        self
+    }
+
+    pub fn file<F: Into<File>>(mut self, file: F) -> Self {
+        self.files.push(file.into());
+        self
+    }
+
+    pub fn model<M: Into<Model>>(mut self, model: M) -> Self {
+        let model = model.into();
+        match self.model_types.iter_mut().find(|model_type| model_type.dtype == model.dtype) {
+            Some(model_type) => model_type.models.push(model),
+            None => self.model_types.push(ModelType { dtype: model.dtype.clone(), models: vec![model], other_attributes: vec![] }),
+        }
+        self
+    }
+
+    pub fn models<M: Into<Model>, I: IntoIterator<Item = M>>(self, models: I) -> Self {
+        models.into_iter().fold(self, |builder, model| builder.model(model))
+    }
+
+    /// `ModelCount` is the number of top-level models, `NodeCount` also
+    /// counts nested ones.
+    pub fn build(self) -> Project {
+        let decoded_data = DecodedData { model_types: self.model_types };
+        let model_count: usize = decoded_data.model_types.iter().map(|model_type| model_type.models.len()).sum();
+        let node_count = ModelGraph::new(&decoded_data).len();
+        Project {
+            id: self.id,
+            name: self.name,
+            report_version: self.report_version,
+            license_id: "0".to_string(),
+            contains_garbage: "False".to_string(),
+            extraction_type: self.extraction_type,
+            node_count: node_count.to_string(),
+            model_count: model_count.to_string(),
+            xmlns: "http://pa.cellebrite.com/report/2.0".to_string(),
+            source_extractions: SourceExtractions { infos: self.infos },
+            case_information: CaseInformation { fields: self.case_fields },
+            metadata: self.metadata,
+            images: if self.images.is_empty() { None } else { Some(Images { images: self.images }) },
+            tagged_files: if self.files.is_empty() { None } else { Some(TaggedFiles { files: self.files }) },
+            decoded_data: if decoded_data.model_types.is_empty() { None } else { Some(decoded_data) },
+            other_attributes: vec![],
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use quick_xml::{events::Event, Reader};
+    use super::*;
+
+    #[test]
+    fn test_model_builder_0() {
+        let party = ModelBuilder::new("Party").id("party-0").field("Identifier", "+43123");
+        let message = ModelBuilder::new("InstantMessage")
+            .field("Body", "hi")
+            .typed_field("TimeStamp", "TimeStamp", "2020-07-01T07:45:53.000+00:00")
+            .model("From", party)
+            .models("To", "Party", [ModelBuilder::new("Party"), ModelBuilder::new("Party")])
+            .build();
+        assert_eq!(message.field_text("Body"), Some("hi"));
+        assert_eq!(message.nested_models("From")[0].id, "party-0");
+        assert_eq!(message.nested_models("From")[0].field_text("Identifier"), Some("+43123"));
+        assert_eq!(message.nested_models("To").len(), 2);
+        assert_ne!(message.nested_models("To")[0].id, message.nested_models("To")[1].id);
+        assert!(Uuid::parse_str(&message.id).is_ok());
+    }
+
+    #[test]
+    fn test_project_builder_0() -> Result<(), Box<dyn std::error::Error>> {
+        let chat = ModelBuilder::new("Chat")
+            .field("Source", "WhatsApp")
+            .models("Messages", "InstantMessage", [
+                ModelBuilder::new("InstantMessage").field("Body", "hi"),
+                ModelBuilder::new("InstantMessage").field("Body", "ho"),
+            ]);
+        let project = ProjectBuilder::new("Test")
+            .extraction("Logical", "Logical", "Apple iPhone")
+            .case_field("Case Number", "CaseNumber", "2024-001")
+            .metadata_item("Device Info", "Device Name", "Phone", Some(0))
+            .model(chat)
+            .model(ModelBuilder::new("Contact").field("Name", "Moriarty"))
+            .model(ModelBuilder::new("Chat"))
+            .build();
+        assert_eq!((project.node_count.as_str(), project.model_count.as_str()), ("5", "3"));
+        let model_types = &project.decoded_data.as_ref().ok_or("decodedData")?.model_types;
+        assert_eq!(model_types.iter().map(|model_type| model_type.dtype.as_str()).collect::<Vec<_>>(), vec!["Chat", "Contact"]);
+        assert!(project.validate_extraction_refs().is_ok());
+
+        let mut out = Vec::new();
+        project.write_report(&mut out)?;
+        let mut reader = Reader::from_reader(out.as_slice());
+        let mut buf = Vec::new();
+        let parsed = loop {
+            match reader.read_event_into(&mut buf)? {
+                Event::Start(e) if e.name().as_ref() == b"project" => break Project::parse_one(&e, &mut reader)?,
+                Event::Eof => return Err("eof".into()),
+                _ => (),
+            }
+            buf.clear();
+        };
+        assert_eq!(parsed, project);
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_builder_0() {
+        let file = FileBuilder::new("/DCIM/IMG_0001.JPG")
+            .size(4)
+            .timestamp("CreationTime", "2020-07-01T07:45:53.000+00:00")
+            .local_path("files\\Image\\IMG_0001.JPG")
+            .metadata_item("MetaData", "MD5", "d41d8cd9")
+            .build();
+        assert_eq!(file.local_path(), Some("files\\Image\\IMG_0001.JPG"));
+        assert_eq!(file.hash("MD5"), Some("d41d8cd9"));
+        assert_eq!(file.access_info.map(|access_info| access_info.timestamps.len()), Some(1));
+        assert!(Uuid::parse_str(&file.id).is_ok());
+    }
+}
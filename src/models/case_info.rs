@@ -0,0 +1,264 @@
+use super::{CaseInformation, Field};
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CaseField {
+    CaseNumber,
+    CaseName,
+    Suspect,
+    Examiner,
+    EvidenceNumber,
+    Department,
+    Location,
+}
+
+const LABELS: [(CaseField, &[&str]); 7] = [
+    (CaseField::CaseNumber, &[
+        "case number", "case no", "case id",
+        "fall nummer", "fallnummer", "aktenzeichen",
+        "numero de dossier", "numero du dossier", "numero d affaire", "numero de l affaire",
+        "numero de caso", "numero del caso", "no de caso",
+    ]),
+    (CaseField::CaseName, &[
+        "case name", "case title",
+        "fallname", "fall name", "fallbezeichnung",
+        "nom du dossier", "nom de dossier", "nom de l affaire", "nom du cas",
+        "nombre del caso", "nombre de caso",
+    ]),
+    (CaseField::Suspect, &[
+        "suspect", "suspect name", "suspects",
+        "beschuldigter", "beschuldigte", "verdachtiger", "tatverdachtiger",
+        "nom du suspect",
+        "sospechoso", "nombre del sospechoso",
+    ]),
+    (CaseField::Examiner, &[
+        "examiner", "examiner name", "examiner s name", "name of examiner", "investigator",
+        "name d ermittlers", "name des ermittlers", "ermittler", "sachbearbeiter", "untersucher",
+        "examinateur", "nom de l examinateur", "enqueteur", "nom de l enqueteur",
+        "examinador", "nombre del examinador", "investigador", "nombre del investigador",
+    ]),
+    (CaseField::EvidenceNumber, &[
+        "evidence number", "evidence no", "evidence id", "exhibit number",
+        "beweisnummer", "beweismittelnummer", "asservatennummer",
+        "numero de preuve", "numero de la preuve", "numero de piece", "numero de scelle",
+        "numero de evidencia", "numero de prueba", "numero de la prueba",
+    ]),
+    (CaseField::Department, &[
+        "department", "unit", "agency", "organization",
+        "abteilung", "dienststelle", "behorde",
+        "service", "departement", "unite",
+        "departamento", "unidad", "agencia",
+    ]),
+    (CaseField::Location, &[
+        "location", "place",
+        "ort", "standort",
+        "lieu", "emplacement",
+        "ubicacion", "lugar", "localidad",
+    ]),
+];
+
+impl CaseField {
+    /// Maps the language-neutral `fieldType` attribute of system fields.
+    pub fn from_field_type(field_type: &str) -> Option<Self> {
+        match field_type {
+            "CaseNumber" => Some(CaseField::CaseNumber),
+            "CaseName" => Some(CaseField::CaseName),
+            "Suspect" | "SuspectName" => Some(CaseField::Suspect),
+            "Examiner" | "ExaminerName" => Some(CaseField::Examiner),
+            "EvidenceNumber" => Some(CaseField::EvidenceNumber),
+            "Department" => Some(CaseField::Department),
+            "Location" => Some(CaseField::Location),
+            _ => None,
+        }
+    }
+
+    /// Maps an English, German, French or Spanish field label.
+    pub fn from_label(label: &str) -> Option<Self> {
+        let label = normalize_label(label);
+        LABELS
+            .iter()
+            .find(|(_, labels)| labels.contains(&label.as_str()))
+            .map(|(case_field, _)| *case_field)
+    }
+
+    pub fn from_field(field: &Field) -> Option<Self> {
+        field
+            .field_type
+            .as_deref()
+            .and_then(CaseField::from_field_type)
+            .or_else(|| CaseField::from_label(&field.name))
+    }
+}
+
+fn normalize_label(label: &str) -> String {
+    let folded: String = label
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ä' => 'a',
+            'è' | 'é' | 'ê' | 'ë' => 'e',
+            'ì' | 'í' | 'î' | 'ï' => 'i',
+            'ò' | 'ó' | 'ô' | 'ö' | 'º' | '°' => 'o',
+            'ù' | 'ú' | 'û' | 'ü' => 'u',
+            'ñ' => 'n',
+            'ç' => 'c',
+            'ß' => 's',
+            c if c.is_alphanumeric() => c,
+            _ => ' ',
+        })
+        .collect();
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+
+/// Case information with the standard fields recognised regardless of the
+/// UI language the report was created in.
+#[derive(Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CaseInfo {
+    pub case_number: Option<String>,
+    pub case_name: Option<String>,
+    pub suspect: Option<String>,
+    pub examiner: Option<String>,
+    pub evidence_number: Option<String>,
+    pub department: Option<String>,
+    pub location: Option<String>,
+    pub custom_fields: Vec<(String, String)>,
+}
+
+impl CaseInfo {
+    pub fn get(&self, case_field: CaseField) -> Option<&str> {
+        match case_field {
+            CaseField::CaseNumber => self.case_number.as_deref(),
+            CaseField::CaseName => self.case_name.as_deref(),
+            CaseField::Suspect => self.suspect.as_deref(),
+            CaseField::Examiner => self.examiner.as_deref(),
+            CaseField::EvidenceNumber => self.evidence_number.as_deref(),
+            CaseField::Department => self.department.as_deref(),
+            CaseField::Location => self.location.as_deref(),
+        }
+    }
+
+    fn slot(&mut self, case_field: CaseField) -> &mut Option<String> {
+        match case_field {
+            CaseField::CaseNumber => &mut self.case_number,
+            CaseField::CaseName => &mut self.case_name,
+            CaseField::Suspect => &mut self.suspect,
+            CaseField::Examiner => &mut self.examiner,
+            CaseField::EvidenceNumber => &mut self.evidence_number,
+            CaseField::Department => &mut self.department,
+            CaseField::Location => &mut self.location,
+        }
+    }
+}
+
+impl From<&CaseInformation> for CaseInfo {
+    fn from(case_information: &CaseInformation) -> Self {
+        let mut case_info = CaseInfo::default();
+        for field in &case_information.fields {
+            let text = field.value_text().to_string();
+            match CaseField::from_field(field) {
+                Some(case_field) if case_info.slot(case_field).is_none() => {
+                    *case_info.slot(case_field) = Some(text);
+                }
+                _ => case_info.custom_fields.push((field.name.clone(), text)),
+            }
+        }
+        case_info
+    }
+}
+
+impl CaseInformation {
+    pub fn case_info(&self) -> CaseInfo {
+        CaseInfo::from(self)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use quick_xml::{events::Event, Reader};
+    use super::*;
+
+    fn test_case_info(xml_str: &str, expected: CaseInfo) -> Result<(), String> {
+        let mut reader = Reader::from_reader(Cursor::new(xml_str));
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(e)) if e.name().as_ref() == b"caseInformation" => {
+                    let uwu = CaseInformation::parse_one(&mut reader);
+                    if let Ok(case_information) = uwu {
+                        assert_eq!(case_information.case_info(), expected);
+                        return Ok(());
+                    } else {
+                        return Err(format!("CaseInformation::parse_one error {:#?}", uwu));
+                    }
+                },
+                Ok(Event::Eof) => {
+                    return Err("eof".to_string());
+                }
+                _ => (),
+            }
+            buf.clear();
+        }
+    }
+
+    #[test]
+    fn test_case_info_0() -> Result<(), String> {
+        let xml_str = r#"
+        <caseInformation>
+            <field name="Fall-Nummer" isSystem="True" isRequired="False" fieldType="CaseNumber" multipleLines="False">Case 001</field>
+            <field name="Fallname" isSystem="True" isRequired="False" fieldType="CaseName" multipleLines="False">Super important case</field>
+            <field name="Beweisnummer" isSystem="True" isRequired="False" fieldType="EvidenceNumber" multipleLines="False">001</field>
+            <field name="Name d. Ermittlers" isSystem="False" isRequired="False" fieldType="None" multipleLines="False">Sherlock Holmes</field>
+            <field name="Abteilung" isSystem="False" isRequired="False" fieldType="None" multipleLines="False">Department of Investigation</field>
+            <field name="Ort" isSystem="False" isRequired="False" fieldType="None" multipleLines="False">Vienna</field>
+            <field name="Beschuldigter" isSystem="False" isRequired="False" fieldType="None" multipleLines="False">Professor James Moriarty</field>
+            <field name="PIN" isSystem="False" isRequired="False" fieldType="None" multipleLines="False">1337</field>
+        </caseInformation>
+        "#;
+        test_case_info(xml_str, CaseInfo {
+            case_number: Some("Case 001".to_string()),
+            case_name: Some("Super important case".to_string()),
+            suspect: Some("Professor James Moriarty".to_string()),
+            examiner: Some("Sherlock Holmes".to_string()),
+            evidence_number: Some("001".to_string()),
+            department: Some("Department of Investigation".to_string()),
+            location: Some("Vienna".to_string()),
+            custom_fields: vec![("PIN".to_string(), "1337".to_string())],
+        })
+    }
+
+    #[test]
+    fn test_case_info_1() -> Result<(), String> {
+        let xml_str = r#"
+        <caseInformation>
+            <field name="Numéro de dossier" isSystem="True" isRequired="False" fieldType="CaseNumber" multipleLines="False">Affaire 42</field>
+            <field name="Nom de l'examinateur" isSystem="False" isRequired="False" fieldType="None" multipleLines="False">Jules Maigret</field>
+            <field name="Lieu" isSystem="False" isRequired="False" fieldType="None" multipleLines="False">Paris</field>
+            <field name="Nombre del sospechoso" isSystem="False" isRequired="False" fieldType="None" multipleLines="False">Arsène Lupin</field>
+            <field name="Ubicación" isSystem="False" isRequired="False" fieldType="None" multipleLines="False">Madrid</field>
+        </caseInformation>
+        "#;
+        test_case_info(xml_str, CaseInfo {
+            case_number: Some("Affaire 42".to_string()),
+            examiner: Some("Jules Maigret".to_string()),
+            suspect: Some("Arsène Lupin".to_string()),
+            location: Some("Paris".to_string()),
+            custom_fields: vec![("Ubicación".to_string(), "Madrid".to_string())],
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_case_field_0() {
+        assert_eq!(CaseField::from_label("Case Number:"), Some(CaseField::CaseNumber));
+        assert_eq!(CaseField::from_label("Examiner Name"), Some(CaseField::Examiner));
+        assert_eq!(CaseField::from_label("Número de caso"), Some(CaseField::CaseNumber));
+        assert_eq!(CaseField::from_label("Département"), Some(CaseField::Department));
+        assert_eq!(CaseField::from_label("PIN"), None);
+    }
+}
@@ -1,11 +1,12 @@
-use std::io::BufRead;
-use quick_xml::{events::Event, Reader};
+use std::io::{BufRead, Write};
+use quick_xml::{events::{BytesEnd, BytesStart, Event}, Reader, Writer};
 use super::ParseError;
 use crate::models::Field;
 
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CaseInformation {
     pub fields: Vec<Field>
 }
@@ -47,6 +48,15 @@ impl CaseInformation {
         }
         Ok(CaseInformation { fields })
     }
+
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        writer.write_event(Event::Start(BytesStart::new("caseInformation")))?;
+        for field in &self.fields {
+            field.write_one(writer)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("caseInformation")))?;
+        Ok(())
+    }
 }
 
 
@@ -88,6 +98,7 @@ mod test {
                                     dtype: None,
                                     text: "Case 001".to_string(),
                                     value: None,
+                                    other_attributes: vec![],
                                 },
                                 Field {
                                     name: "Fallname".to_string(),
@@ -98,6 +109,7 @@ mod test {
                                     dtype: None,
                                     text: "Super important case".to_string(),
                                     value: None,
+                                    other_attributes: vec![],
                                 },
                                 Field {
                                     name: "Beweisnummer".to_string(),
@@ -108,6 +120,7 @@ mod test {
                                     dtype: None,
                                     text: "001".to_string(),
                                     value: None,
+                                    other_attributes: vec![],
                                 },
                                 Field {
                                     name: "Name d. Ermittlers".to_string(),
@@ -118,6 +131,7 @@ mod test {
                                     dtype: None,
                                     text: "Sherlock Holmes".to_string(),
                                     value: None,
+                                    other_attributes: vec![],
                                 },
                                 Field {
                                     name: "Abteilung".to_string(),
@@ -128,6 +142,7 @@ mod test {
                                     dtype: None,
                                     text: "Department of Investigation".to_string(),
                                     value: None,
+                                    other_attributes: vec![],
                                 },
                                 Field {
                                     name: "Ort".to_string(),
@@ -138,6 +153,7 @@ mod test {
                                     dtype: None,
                                     text: "Vienna".to_string(),
                                     value: None,
+                                    other_attributes: vec![],
                                 },
                                 Field {
                                     name: "Beschuldigter".to_string(),
@@ -148,6 +164,7 @@ mod test {
                                     dtype: None,
                                     text: "Professor James Moriarty".to_string(),
                                     value: None,
+                                    other_attributes: vec![],
                                 },
                                 Field {
                                     name: "PIN".to_string(),
@@ -158,6 +175,7 @@ mod test {
                                     dtype: None,
                                     text: "1337".to_string(),
                                     value: None,
+                                    other_attributes: vec![],
                                 }
                             ]
                         });
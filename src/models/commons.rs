@@ -1,21 +1,28 @@
-use std::io::BufRead;
-use quick_xml::{events::{BytesStart, Event}, Reader};
-use super::{attributes_to_map, read_text, ParseError};
+use std::io::{BufRead, Write};
+use quick_xml::{events::{BytesEnd, BytesStart, Event}, Reader, Writer};
+use super::{attribute_pairs, attributes_to_map, other_attributes, read_text, write_cdata, ExtractionInfo, ParseError, Project};
 
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Field {
     pub name: String,
     pub is_system: Option<String>,
     pub is_required: Option<String>,
     pub field_type: Option<String>,
     pub multiple_lines: Option<String>,
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
     pub dtype: Option<String>,
     pub text: String,
     pub value: Option<Value>,
+    /// Attributes other than the ones above, in document order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub other_attributes: Vec<(String, String)>,
 }
 
+const FIELD_ATTRIBUTES: [&str; 6] = ["name", "isSystem", "isRequired", "fieldType", "multipleLines", "type"];
+
 impl Field {
     pub fn parse_one<B: BufRead>(e: &BytesStart, reader: &mut Reader<B>) -> Result<Self, Box<dyn std::error::Error>> {
         let map = attributes_to_map(e)?;
@@ -30,7 +37,7 @@ impl Field {
                 }
                 Event::End(e) if e.name().as_ref() == b"field" => break,
                 Event::Text(e) => {
-                    text.push_str(&e.unescape()?.to_string());
+                    text.push_str(&e.unescape()?);
                 }
                 Event::CData(e) => {
                     text.push_str(std::str::from_utf8(&e)?.trim());
@@ -57,20 +64,65 @@ impl Field {
             dtype: map.get("type").cloned(),
             text: text.trim().to_string(),
             value,
-        })
+            other_attributes: other_attributes(e, &FIELD_ATTRIBUTES)?,
+        })
+    }
+
+    /// Model fields without a value are written with an `<empty />` child,
+    /// case information fields with their text.
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        let mut start = BytesStart::new("field");
+        start.push_attribute(("name", self.name.as_str()));
+        let optional = [
+            ("isSystem", &self.is_system),
+            ("isRequired", &self.is_required),
+            ("fieldType", &self.field_type),
+            ("multipleLines", &self.multiple_lines),
+            ("type", &self.dtype),
+        ];
+        for (key, value) in optional {
+            if let Some(value) = value {
+                start.push_attribute((key, value.as_str()));
+            }
+        }
+        start.extend_attributes(attribute_pairs(&self.other_attributes));
+        writer.write_event(Event::Start(start))?;
+        match &self.value {
+            Some(value) => value.write_one(writer)?,
+            None if self.text.is_empty() && self.dtype.is_some() => {
+                writer.write_event(Event::Empty(BytesStart::new("empty")))?;
+            }
+            None => write_cdata(writer, &self.text)?,
+        }
+        writer.write_event(Event::End(BytesEnd::new("field")))?;
+        Ok(())
+    }
+
+    /// The `<value>` text for model fields, the element text otherwise.
+    pub fn value_text(&self) -> &str {
+        match &self.value {
+            Some(value) => &value.text,
+            None => &self.text,
+        }
     }
 }
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Item {
     pub id: Option<String>,
     pub name: String,
     pub group: Option<String>,
     pub source_extraction: Option<String>,
     pub text: String,
+    /// Attributes other than the ones above, in document order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub other_attributes: Vec<(String, String)>,
 }
 
+const ITEM_ATTRIBUTES: [&str; 4] = ["id", "name", "group", "sourceExtraction"];
+
 impl Item {
     pub fn parse_one<B: BufRead>(e: &BytesStart, reader: &mut Reader<B>) -> Result<Self, Box<dyn std::error::Error>> {
         let map = attributes_to_map(e)?;
@@ -81,15 +133,50 @@ impl Item {
             group: map.get("group").cloned(),
             source_extraction: map.get("sourceExtraction").cloned(),
             text,
+            other_attributes: other_attributes(e, &ITEM_ATTRIBUTES)?,
         })
     }
+
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        let mut start = BytesStart::new("item");
+        if let Some(id) = &self.id {
+            start.push_attribute(("id", id.as_str()));
+        }
+        start.push_attribute(("name", self.name.as_str()));
+        if let Some(group) = &self.group {
+            start.push_attribute(("group", group.as_str()));
+        }
+        if let Some(source_extraction) = &self.source_extraction {
+            start.push_attribute(("sourceExtraction", source_extraction.as_str()));
+        }
+        start.extend_attributes(attribute_pairs(&self.other_attributes));
+        writer.write_event(Event::Start(start))?;
+        write_cdata(writer, &self.text)?;
+        writer.write_event(Event::End(BytesEnd::new("item")))?;
+        Ok(())
+    }
+
+    /// `None` for items that are not keyed to an extraction, see
+    /// `source_extraction_id` to tell those apart from dangling keys.
+    pub fn extraction<'a>(&self, project: &'a Project) -> Option<&'a ExtractionInfo> {
+        project.source_extractions.get(self.source_extraction_id()?)
+    }
+
+    pub fn source_extraction_id(&self) -> Option<u64> {
+        self.source_extraction.as_deref()?.trim().parse().ok()
+    }
 }
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Value {
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
     pub dtype: String,
     pub text: String,
+    /// Attributes other than the ones above, in document order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub other_attributes: Vec<(String, String)>,
 }
 
 impl Value {
@@ -99,8 +186,16 @@ impl Value {
         Ok(Value {
             dtype: map.get("type").cloned().ok_or("missing type")?,
             text,
+            other_attributes: other_attributes(e, &["type"])?,
         })
     }
+
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        writer.write_event(Event::Start(BytesStart::new("value").with_attributes([("type", self.dtype.as_str())]).with_attributes(attribute_pairs(&self.other_attributes))))?;
+        write_cdata(writer, &self.text)?;
+        writer.write_event(Event::End(BytesEnd::new("value")))?;
+        Ok(())
+    }
 }
 
 
@@ -126,8 +221,8 @@ mod test {
                 Ok(Event::Eof) => {
                     return Err("eof".to_string());
                 }
-                a => {
-                    // println!("{:#?}", a);
+                _a => {
+                    // println!("{:#?}", _a);
                 },
             }
         }
@@ -147,6 +242,7 @@ mod test {
             dtype: None,
             text: "Case 001".to_string(),
             value: None,
+            other_attributes: vec![],
         })
     }
 
@@ -164,6 +260,7 @@ mod test {
             dtype: None,
             text: "Super important case".to_string(),
             value: None,
+            other_attributes: vec![],
         })
     }
 
@@ -181,6 +278,7 @@ mod test {
             dtype: None,
             text: "001".to_string(),
             value: None,
+            other_attributes: vec![],
         })
     }
 
@@ -198,6 +296,7 @@ mod test {
             dtype: None,
             text: "Sherlock Holmes".to_string(),
             value: None,
+            other_attributes: vec![],
         })
     }
 
@@ -215,6 +314,7 @@ mod test {
             dtype: None,
             text: "Department of Investigation".to_string(),
             value: None,
+            other_attributes: vec![],
         })
     }
 
@@ -232,6 +332,7 @@ mod test {
             dtype: None,
             text: "Vienna".to_string(),
             value: None,
+            other_attributes: vec![],
         })
     }
 
@@ -249,6 +350,7 @@ mod test {
             dtype: None,
             text: "Professor James Moriarty".to_string(),
             value: None,
+            other_attributes: vec![],
         })
     }
 
@@ -266,6 +368,7 @@ mod test {
             dtype: None,
             text: "1337".to_string(),
             value: None,
+            other_attributes: vec![],
         })
     }
 
@@ -284,7 +387,8 @@ mod test {
             multiple_lines: None,
             dtype: Some("Boolean".to_string()),
             text: "".to_string(),
-            value: Some(Value { dtype: "Boolean".to_string(), text: "False".to_string() }),
+            value: Some(Value { dtype: "Boolean".to_string(), text: "False".to_string(), other_attributes: vec![] }),
+            other_attributes: vec![],
         })
     }
 
@@ -304,6 +408,7 @@ mod test {
             dtype: Some("String".to_string()),
             text: "".to_string(),
             value: None,
+            other_attributes: vec![],
         })
     }
 
@@ -316,7 +421,7 @@ mod test {
                 Ok(Event::Start(e)) if e.name().as_ref() == b"item" => {
                     if let Ok(item) = Item::parse_one(&e, &mut reader) {
                         assert_eq!(item, expected);
-                        let known_keys = vec![
+                        let known_keys = [
                             "id",
                             "name",
                             "group",
@@ -333,8 +438,8 @@ mod test {
                 Ok(Event::Eof) => {
                     return Err("eof".to_string());
                 }
-                a => {
-                    // println!("{:#?}", a);
+                _a => {
+                    // println!("{:#?}", _a);
                 },
             }
         }
@@ -351,6 +456,7 @@ mod test {
             group: None,
             source_extraction: None,
             text: "10/10/2020 13:37:00".to_string(),
+            other_attributes: vec![],
         })
     }
 
@@ -365,6 +471,7 @@ mod test {
             group: None,
             source_extraction: None,
             text: "1.2.3.4".to_string(),
+            other_attributes: vec![],
         })
     }
 
@@ -379,6 +486,7 @@ mod test {
             group: None,
             source_extraction: Some("0".to_string()),
             text: "Apple".to_string(),
+            other_attributes: vec![],
         })
     }
 
@@ -393,6 +501,7 @@ mod test {
             group: None,
             source_extraction: Some("0".to_string()),
             text: "Cable No. 220".to_string(),
+            other_attributes: vec![],
         })
     }
 
@@ -407,6 +516,7 @@ mod test {
             group: None,
             source_extraction: Some("0".to_string()),
             text: "Logical".to_string(),
+            other_attributes: vec![],
         })
     }
 
@@ -421,6 +531,7 @@ mod test {
             group: None,
             source_extraction: Some("0".to_string()),
             text: "12345678-a931-45a1-b3a1-1234567890A".to_string(),
+            other_attributes: vec![],
         })
     }
 
@@ -435,6 +546,7 @@ mod test {
             group: None,
             source_extraction: Some("0".to_string()),
             text: "10/10/2020 13:37:00".to_string(),
+            other_attributes: vec![],
         })
     }
 
@@ -449,6 +561,7 @@ mod test {
             group: None,
             source_extraction: Some("0".to_string()),
             text: "10/10/2020 14:00:00".to_string(),
+            other_attributes: vec![],
         })
     }
 
@@ -463,6 +576,7 @@ mod test {
             group: Some("iPhone of Prof Moriarty".to_string()),
             source_extraction: Some("0".to_string()),
             text: "1.2.31".to_string(),
+            other_attributes: vec![],
         })
     }
 
@@ -477,6 +591,7 @@ mod test {
             group: Some("iPhone of Prof Moriarty".to_string()),
             source_extraction: Some("0".to_string()),
             text: "1.2.3".to_string(),
+            other_attributes: vec![],
         })
     }
 
@@ -491,6 +606,7 @@ mod test {
             group: Some("iPhone of Prof Moriarty".to_string()),
             source_extraction: Some("0".to_string()),
             text: "Activated".to_string(),
+            other_attributes: vec![],
         })
     }
 
@@ -505,6 +621,7 @@ mod test {
             group: Some("iPhone of Prof Moriarty".to_string()),
             source_extraction: Some("0".to_string()),
             text: "12:34:56:78:90:ab".to_string(),
+            other_attributes: vec![],
         })
     }
 
@@ -519,6 +636,7 @@ mod test {
             group: Some("iPhone of Prof Moriarty".to_string()),
             source_extraction: Some("".to_string()),
             text: "Ready".to_string(),
+            other_attributes: vec![],
         })
     }
 
@@ -533,6 +651,7 @@ mod test {
             group: None,
             source_extraction: None,
             text: r"files\Audio\En-Creative-Commons.ogg".to_string(),
+            other_attributes: vec![],
         })
     }
 
@@ -547,6 +666,7 @@ mod test {
             group: None,
             source_extraction: None,
             text: r"".to_string(),
+            other_attributes: vec![],
         })
     }
 
@@ -561,6 +681,7 @@ mod test {
             group: None,
             source_extraction: None,
             text: r"3d7f880de7e11d0940558da7dc7e709f".to_string(),
+            other_attributes: vec![],
         })
     }
 
@@ -575,6 +696,7 @@ mod test {
             group: None,
             source_extraction: None,
             text: r"Audio".to_string(),
+            other_attributes: vec![],
         })
     }
 
@@ -589,6 +711,7 @@ mod test {
             group: None,
             source_extraction: None,
             text: r"AppDomain-com.some-audio-app".to_string(),
+            other_attributes: vec![],
         })
     }
 
@@ -600,7 +723,7 @@ mod test {
                 Ok(Event::Start(e)) if e.name().as_ref() == b"value" => {
                     let uwu = Value::parse_one(&e, &mut reader);
                     if let Ok(image) = uwu {
-                        let known_keys = vec![
+                        let known_keys = [
                             "type",
                         ];
                         for key in attributes_to_map(&e).unwrap().keys() {
@@ -628,7 +751,8 @@ mod test {
         "#;
         test_value(xml_str, Value {
             dtype: "Boolean".to_string(),
-            text: "False".to_string()
+            text: "False".to_string(),
+            other_attributes: vec![],
         })
     }
 
@@ -640,7 +764,8 @@ mod test {
         "#;
         test_value(xml_str, Value {
             dtype: "TimeStamp".to_string(),
-            text: "2020-07-01T07:45:53.000+00:00".to_string()
+            text: "2020-07-01T07:45:53.000+00:00".to_string(),
+            other_attributes: vec![],
         })
     }
 }
\ No newline at end of file
@@ -1,14 +1,19 @@
-use std::io::BufRead;
-use quick_xml::{Reader, events::{BytesStart, Event}};
-use super::{attributes_to_map, ParseError, Source};
+use std::io::{BufRead, Write};
+use quick_xml::{Reader, Writer, events::{BytesEnd, BytesStart, Event}};
+use super::{attribute_pairs, attributes_to_map, other_attributes, ParseError, Source};
 
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DataField {
     pub name: String,
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
     pub dtype: String,
-    pub sources: Vec<Source>
+    pub sources: Vec<Source>,
+    /// Attributes other than the ones above, in document order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub other_attributes: Vec<(String, String)>,
 }
 
 impl DataField {
@@ -49,8 +54,21 @@ impl DataField {
             name: map.get("name").cloned().ok_or("missing name")?,
             dtype: map.get("type").cloned().ok_or("missing type")?,
             sources,
+            other_attributes: other_attributes(e, &["name", "type"])?,
         })
     }
+
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        writer.write_event(Event::Start(BytesStart::new("dataField").with_attributes([
+            ("name", self.name.as_str()),
+            ("type", self.dtype.as_str()),
+        ]).with_attributes(attribute_pairs(&self.other_attributes))))?;
+        for source in &self.sources {
+            source.write_one(writer)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("dataField")))?;
+        Ok(())
+    }
 }
 
 
@@ -99,7 +117,8 @@ mod test {
         test_data_field(xml_str, DataField {
             name: "Data".to_string(),
             dtype: "MemoryRange".to_string(),
-            sources: vec![Source { length: 11159817 }],
+            sources: vec![Source { length: 11159817, other_attributes: vec![] }],
+            other_attributes: vec![],
         })
     }
 }
\ No newline at end of file
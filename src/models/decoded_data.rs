@@ -1,9 +1,10 @@
-use std::{io::BufRead};
-use quick_xml::{events::{Event}, Reader};
+use std::io::{BufRead, Write};
+use quick_xml::{events::{BytesEnd, BytesStart, Event}, Reader, Writer};
 use super::{ModelType, ParseError};
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DecodedData {
     pub model_types: Vec<ModelType>
 }
@@ -47,13 +48,22 @@ impl DecodedData {
             model_types
         })
     }
+
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        writer.write_event(Event::Start(BytesStart::new("decodedData")))?;
+        for model_type in &self.model_types {
+            model_type.write_one(writer)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("decodedData")))?;
+        Ok(())
+    }
 }
 
 
 #[cfg(test)]
 mod test {
     use std::io::Cursor;
-    use crate::models::{attributes_to_map, Field, Model, Value};
+    use crate::models::{attributes_to_map, Field, Model, ModelChild, Value};
 
     use super::*;
 
@@ -108,6 +118,8 @@ mod test {
                 decoding_confidence: "High".to_string(),
                 is_related: "False".to_string(),
                 extraction_id: 1,
+                children: vec![ModelChild::Field("UserMapping".to_string())],
+                other_attributes: vec![],
                 multi_model_fields: vec![],
                 model_fields: vec![],
                 multi_fields: vec![],
@@ -123,9 +135,12 @@ mod test {
                     value: Some(Value {
                         dtype: "Boolean".to_string(),
                         text: "False".to_string(),
-                    })
+                        other_attributes: vec![],
+                    }),
+                    other_attributes: vec![],
                 }]
-            }]
+            }],
+            other_attributes: vec![],
         }],
         })
     }
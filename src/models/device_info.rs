@@ -0,0 +1,252 @@
+use std::{fmt, str::FromStr};
+use chrono::{DateTime, FixedOffset};
+use super::{parse_datetime, Item, ParseError};
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MacAddress(pub [u8; 6]);
+
+impl FromStr for MacAddress {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let digits: String = s.chars().filter(|c| !matches!(c, ':' | '-' | '.')).collect();
+        if digits.len() != 12 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ParseError::new(&format!("invalid MAC address: {}", s)));
+        }
+        let mut bytes = [0u8; 6];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&digits[2 * i..2 * i + 2], 16)
+                .map_err(|_| ParseError::new(&format!("invalid MAC address: {}", s)))?;
+        }
+        Ok(MacAddress(bytes))
+    }
+}
+
+impl fmt::Display for MacAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}", a, b, c, d, e, g)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for MacAddress {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for MacAddress {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+
+/// Parses sizes such as `42 GB` into bytes. Units are decimal, matching the
+/// capacities advertised by device vendors.
+pub fn parse_storage_size(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text.find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ',')).unwrap_or(text.len());
+    let number: f64 = text[..split].replace(',', ".").parse().ok()?;
+    let multiplier = match text[split..].trim().to_ascii_uppercase().as_str() {
+        "" | "B" | "BYTES" => 1u64,
+        "KB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        "TB" => 1_000_000_000_000,
+        _ => return None,
+    };
+    Some((number * multiplier as f64).round() as u64)
+}
+
+
+/// Typed view of the `DeviceInfo*` items of one source extraction.
+#[derive(Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceInfo {
+    pub extraction_id: u64,
+    pub name: Option<String>,
+    pub manufacturer: Option<String>,
+    pub model_number: Option<String>,
+    pub os_type: Option<String>,
+    pub os_version: Option<String>,
+    pub baseband_version: Option<String>,
+    pub time_zone: Option<String>,
+    pub locale_language: Option<String>,
+    pub storage_capacity: Option<u64>,
+    pub storage_available: Option<u64>,
+    pub wifi_address: Option<MacAddress>,
+    pub bluetooth_address: Option<MacAddress>,
+    pub cloud_backup_enabled: Option<bool>,
+    pub phone_date_time: Option<DateTime<FixedOffset>>,
+    pub extraction_start: Option<DateTime<FixedOffset>>,
+    pub extraction_end: Option<DateTime<FixedOffset>>,
+    pub other: Vec<(String, String)>,
+}
+
+impl DeviceInfo {
+    /// Builds the device info of `extraction_id` from metadata items. Items
+    /// keyed to that extraction take precedence over unkeyed ones, items
+    /// keyed to other extractions are ignored.
+    pub fn from_items<'a, I>(extraction_id: u64, items: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Item>,
+    {
+        let mut device_info = DeviceInfo { extraction_id, ..Default::default() };
+        let (keyed, unkeyed): (Vec<&Item>, Vec<&Item>) = items
+            .into_iter()
+            .filter(|item| match item.source_extraction.as_deref() {
+                None | Some("") => true,
+                Some(id) => id.parse() == Ok(extraction_id),
+            })
+            .partition(|item| !matches!(item.source_extraction.as_deref(), None | Some("")));
+        for item in keyed.into_iter().chain(unkeyed) {
+            device_info.set(&item.name, &item.text);
+        }
+        device_info
+    }
+
+    fn set(&mut self, name: &str, text: &str) {
+        fn fill<T>(slot: &mut Option<T>, value: Option<T>) {
+            if slot.is_none() {
+                *slot = value;
+            }
+        }
+        let string = || Some(text.to_string()).filter(|s| !s.is_empty());
+        match name {
+            "DeviceInfoDetectedPhoneModel" | "DeviceInfoSelectedDeviceName" => fill(&mut self.name, string()),
+            "DeviceInfoSelectedManufacturer" | "DeviceInfoDetectedManufacturer" => fill(&mut self.manufacturer, string()),
+            "DeviceInfoModelNumber" => fill(&mut self.model_number, string()),
+            "DeviceInfoOSType" => fill(&mut self.os_type, string()),
+            "DeviceInfoOSVersion" => fill(&mut self.os_version, string()),
+            "DeviceInfoBaseBandVersion" => fill(&mut self.baseband_version, string()),
+            "DeviceInfoTimeZone" => fill(&mut self.time_zone, string()),
+            "DeviceInfoLocaleLanguage" => fill(&mut self.locale_language, string()),
+            "DeviceInfoStorageCapacity" => fill(&mut self.storage_capacity, parse_storage_size(text)),
+            "DeviceInfoStorageAvailable" => fill(&mut self.storage_available, parse_storage_size(text)),
+            "DeviceInfoWiFiAddress" => fill(&mut self.wifi_address, text.parse().ok()),
+            "DeviceInfoBluetoothDeviceAddress" => fill(&mut self.bluetooth_address, text.parse().ok()),
+            "DeviceInfoCloudBackupEnabled" => fill(&mut self.cloud_backup_enabled, parse_bool(text)),
+            "DeviceInfoPhoneDateTime" => fill(&mut self.phone_date_time, parse_datetime(text)),
+            "DeviceInfoExtractionStartDateTime" => fill(&mut self.extraction_start, parse_datetime(text)),
+            "DeviceInfoExtractionEndDateTime" => fill(&mut self.extraction_end, parse_datetime(text)),
+            _ => {
+                if !self.other.iter().any(|(n, _)| n == name) {
+                    self.other.push((name.to_string(), text.to_string()));
+                }
+            }
+        }
+    }
+}
+
+fn parse_bool(text: &str) -> Option<bool> {
+    match text.trim().to_ascii_lowercase().as_str() {
+        "true" | "yes" | "1" => Some(true),
+        "false" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use quick_xml::{events::Event, Reader};
+    use crate::models::Metadata;
+    use super::*;
+
+    fn parse_metadata(xml_str: &str) -> Result<Metadata, String> {
+        let mut reader = Reader::from_reader(Cursor::new(xml_str));
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(e)) if e.name().as_ref() == b"metadata" => {
+                    return Metadata::parse_one(&e, &mut reader).map_err(|e| e.to_string());
+                },
+                Ok(Event::Eof) => {
+                    return Err("eof".to_string());
+                }
+                _ => (),
+            }
+            buf.clear();
+        }
+    }
+
+    #[test]
+    fn test_mac_address_0() {
+        let mac: MacAddress = "12:34:56:78:90:AB".parse().unwrap();
+        assert_eq!(mac, MacAddress([0x12, 0x34, 0x56, 0x78, 0x90, 0xab]));
+        assert_eq!(mac.to_string(), "12:34:56:78:90:ab");
+        assert_eq!("12-34-56-78-90-ab".parse::<MacAddress>().unwrap(), mac);
+        assert!("12:34:56".parse::<MacAddress>().is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_mac_address_serde_0() {
+        let mac = MacAddress([0x12, 0x34, 0x56, 0x78, 0x90, 0xab]);
+        assert_eq!(serde_json::to_string(&mac).unwrap(), "\"12:34:56:78:90:ab\"");
+        assert_eq!(serde_json::from_str::<MacAddress>("\"12:34:56:78:90:AB\"").unwrap(), mac);
+    }
+
+    #[test]
+    fn test_parse_storage_size_0() {
+        assert_eq!(parse_storage_size("42 GB"), Some(42_000_000_000));
+        assert_eq!(parse_storage_size("1,5 MB"), Some(1_500_000));
+        assert_eq!(parse_storage_size("1024"), Some(1024));
+        assert_eq!(parse_storage_size("lots"), None);
+    }
+
+    #[test]
+    fn test_device_info_0() -> Result<(), String> {
+        let xml_str = r#"
+        <metadata section="Device Info">
+            <item id="78885761-d2fd-4179-84eb-70b4a19b809c" name="DeviceInfoBaseBandVersion" group="iPhone of Prof Moriarty" sourceExtraction="0"><![CDATA[1.2.31]]></item>
+            <item id="dc8707a8-d227-4715-ba39-b59632543eb6" name="DeviceInfoOSVersion" group="iPhone of Prof Moriarty" sourceExtraction="0"><![CDATA[1.2.3]]></item>
+            <item id="0da47208-0b0d-46ad-9eb3-b3ebd9eb8f69" name="DeviceInfoActivationState" group="iPhone of Prof Moriarty" sourceExtraction="0"><![CDATA[Activated]]></item>
+            <item id="579d8e32-8255-4286-870a-93efecce33de" name="DeviceInfoBluetoothDeviceAddress" group="iPhone of Prof Moriarty" sourceExtraction="0"><![CDATA[12:34:56:78:90:ab]]></item>
+            <item id="e56c40e2-40af-4ffc-9197-58a4e0e64e58" name="DeviceInfoSimStatus" group="iPhone of Prof Moriarty" sourceExtraction=""><![CDATA[Ready]]></item>
+            <item id="dbb39d9d-1288-4546-9e71-e7ac5e03b441" name="DeviceInfoWiFiAddress" group="iPhone of Prof Moriarty" sourceExtraction="0"><![CDATA[12:34:56:78:90:ac]]></item>
+            <item id="4db3ebf8-f9b7-4f7a-9912-74e4d26c140b" name="DeviceInfoStorageCapacity" group="iPhone of Prof Moriarty" sourceExtraction="0"><![CDATA[42 GB]]></item>
+            <item id="49381e39-0e49-469e-b1bf-751b0495f652" name="DeviceInfoStorageAvailable" group="iPhone of Prof Moriarty" sourceExtraction="0"><![CDATA[21 GB]]></item>
+            <item id="e0e74416-208e-4aaf-b558-f2156432e65f" name="DeviceInfoTimeZone" group="iPhone of Prof Moriarty" sourceExtraction="0"><![CDATA[Europe/Vienna]]></item>
+            <item id="d0c17d7a-7bdb-4c2d-85a3-6154b649879d" name="DeviceInfoModelNumber" group="iPhone of Prof Moriarty" sourceExtraction="0"><![CDATA[ABCDEF]]></item>
+            <item id="354c50b2-7757-43fe-9ca7-f8cba8c01e4d" name="DeviceInfoOSType" group="Metadata" sourceExtraction="0"><![CDATA[iOS]]></item>
+            <item id="210ab633-3908-4c59-b0b9-da29ec4b1da6" name="DeviceInfoTimeZone" group="Phone Settings" sourceExtraction="1"><![CDATA[Europe/London]]></item>
+            <item id="806912da-1d09-476e-b3e6-5cdd5b5d6791" name="DeviceInfoPhoneDateTime" sourceExtraction="0"><![CDATA[09.09.2020 19:33:13(UTC+0)]]></item>
+            <item id="6bef83e8-bdd8-48ec-92f1-653122740fc1" name="DeviceInfoCloudBackupEnabled" group="Phone Settings" sourceExtraction="0"><![CDATA[True]]></item>
+        </metadata>
+        "#;
+        let metadata = parse_metadata(xml_str)?;
+        let device_info = DeviceInfo::from_items(0, &metadata.items);
+        assert_eq!(device_info, DeviceInfo {
+            extraction_id: 0,
+            model_number: Some("ABCDEF".to_string()),
+            os_type: Some("iOS".to_string()),
+            os_version: Some("1.2.3".to_string()),
+            baseband_version: Some("1.2.31".to_string()),
+            time_zone: Some("Europe/Vienna".to_string()),
+            storage_capacity: Some(42_000_000_000),
+            storage_available: Some(21_000_000_000),
+            wifi_address: Some(MacAddress([0x12, 0x34, 0x56, 0x78, 0x90, 0xac])),
+            bluetooth_address: Some(MacAddress([0x12, 0x34, 0x56, 0x78, 0x90, 0xab])),
+            cloud_backup_enabled: Some(true),
+            phone_date_time: parse_datetime("2020-09-09T19:33:13+00:00"),
+            other: vec![
+                ("DeviceInfoActivationState".to_string(), "Activated".to_string()),
+                ("DeviceInfoSimStatus".to_string(), "Ready".to_string()),
+            ],
+            ..Default::default()
+        });
+        let device_info = DeviceInfo::from_items(1, &metadata.items);
+        assert_eq!(device_info.time_zone.as_deref(), Some("Europe/London"));
+        assert_eq!(device_info.os_type, None);
+        Ok(())
+    }
+}
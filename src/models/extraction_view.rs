@@ -0,0 +1,203 @@
+use std::fmt;
+use super::{ExtractionInfo, File, Image, Item, Model, ModelGraph, Project};
+
+
+
+/// Decoded data, tagged files, images and metadata items of one extraction.
+#[derive(Debug, PartialEq)]
+pub struct ExtractionView<'a> {
+    pub info: &'a ExtractionInfo,
+    pub models: Vec<&'a Model>,
+    pub files: Vec<&'a File>,
+    pub images: Vec<&'a Image>,
+    pub items: Vec<&'a Item>,
+}
+
+impl<'a> ExtractionView<'a> {
+    pub fn new(project: &'a Project, info: &'a ExtractionInfo) -> Self {
+        let id = info.id;
+        let models = project
+            .decoded_data
+            .iter()
+            .flat_map(|decoded_data| decoded_data.model_types.iter())
+            .flat_map(|model_type| model_type.models.iter())
+            .filter(|model| model.extraction_id == id)
+            .collect();
+        let files = project
+            .tagged_files
+            .iter()
+            .flat_map(|tagged_files| tagged_files.files.iter())
+            .filter(|file| file.extraction_id == id)
+            .collect();
+        let images = project
+            .images
+            .iter()
+            .flat_map(|images| images.images.iter())
+            .filter(|image| image.extraction_id == id)
+            .collect();
+        let items = project
+            .metadata
+            .iter()
+            .flat_map(|metadata| metadata.items.iter())
+            .filter(|item| item.source_extraction_id() == Some(id))
+            .collect();
+        ExtractionView { info, models, files, images, items }
+    }
+
+    pub fn models_of_type<'b>(&'b self, dtype: &'b str) -> impl Iterator<Item = &'a Model> + 'b {
+        self.models.iter().copied().filter(move |model| model.dtype == dtype)
+    }
+}
+
+
+/// A reference to an extraction id missing from `sourceExtractions`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DanglingExtractionRef {
+    pub element: &'static str,
+    pub id: String,
+    pub extraction_id: String,
+}
+
+impl fmt::Display for DanglingExtractionRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} refers to unknown extraction {:?}", self.element, self.id, self.extraction_id)
+    }
+}
+
+impl DanglingExtractionRef {
+    pub fn collect(project: &Project) -> Vec<Self> {
+        let mut dangling = Vec::new();
+        let source_extractions = &project.source_extractions;
+        for model in ModelGraph::from_project(project).models() {
+            if source_extractions.get(model.extraction_id).is_none() {
+                dangling.push(DanglingExtractionRef::new("model", &model.id, model.extraction_id));
+            }
+        }
+        for file in project.tagged_files.iter().flat_map(|tagged_files| tagged_files.files.iter()) {
+            if source_extractions.get(file.extraction_id).is_none() {
+                dangling.push(DanglingExtractionRef::new("file", &file.id, file.extraction_id));
+            }
+        }
+        for image in project.images.iter().flat_map(|images| images.images.iter()) {
+            if source_extractions.get(image.extraction_id).is_none() {
+                dangling.push(DanglingExtractionRef::new("image", &image.key, image.extraction_id));
+            }
+        }
+        let items = project
+            .metadata
+            .iter()
+            .chain(project.tagged_files.iter().flat_map(|tagged_files| tagged_files.files.iter()).flat_map(|file| file.metadata.iter()))
+            .flat_map(|metadata| metadata.items.iter());
+        for item in items {
+            let Some(source_extraction) = item.source_extraction.as_deref().filter(|s| !s.trim().is_empty()) else {
+                continue;
+            };
+            if item.extraction(project).is_none() {
+                dangling.push(DanglingExtractionRef {
+                    element: "item",
+                    id: item.id.clone().unwrap_or_else(|| item.name.clone()),
+                    extraction_id: source_extraction.to_string(),
+                });
+            }
+        }
+        dangling
+    }
+
+    fn new(element: &'static str, id: &str, extraction_id: u64) -> Self {
+        DanglingExtractionRef { element, id: id.to_string(), extraction_id: extraction_id.to_string() }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use quick_xml::{events::Event, Reader};
+    use super::*;
+
+    fn parse_project(xml_str: &str) -> Result<Project, String> {
+        let mut reader = Reader::from_reader(Cursor::new(xml_str));
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(e)) if e.name().as_ref() == b"project" => {
+                    return Project::parse_one(&e, &mut reader).map_err(|e| e.to_string());
+                },
+                Ok(Event::Eof) => {
+                    return Err("eof".to_string());
+                }
+                _ => (),
+            }
+            buf.clear();
+        }
+    }
+
+    const XML_STR: &str = r#"
+    <project id="00000000-0000-0000-0000-000000000000" name="Test" reportVersion="7.0.0.0" licenseID="0" containsGarbage="False" extractionType="Logical" NodeCount="4" ModelCount="3" xmlns="http://pa.cellebrite.com/report/2.0">
+        <sourceExtractions>
+            <extractionInfo id="0" name="Logical" isCustomName="False" type="Logical" deviceName="Report" fullName="Cellebrite UFED Reports" index="0" IsPartialData="False" />
+            <extractionInfo id="1" name="File System" isCustomName="False" type="FileSystem" deviceName="Report" fullName="Cellebrite UFED Reports" index="1" IsPartialData="False" />
+        </sourceExtractions>
+        <caseInformation>
+        </caseInformation>
+        <metadata section="Extraction Data">
+            <item name="ExtractionType" sourceExtraction="0"><![CDATA[Logical]]></item>
+            <item name="ExtractionType" sourceExtraction="7"><![CDATA[Cloud]]></item>
+        </metadata>
+        <images>
+            <image key="FileDump" path="iPhoneBackup.tar" size="12345678" type="File" verify="NoSourceValues" extractionId="1" />
+        </images>
+        <taggedFiles>
+            <file fs="iPhone" fsid="fsid" path="/a.jpg" size="1" id="file-0" extractionId="1" deleted="Intact" embedded="false" isrelated="False">
+            </file>
+        </taggedFiles>
+        <decodedData>
+            <modelType type="Chat">
+                <model type="Chat" id="chat-0" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                    <modelField name="Account" type="UserAccount">
+                        <model type="UserAccount" id="account-0" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="3">
+                        </model>
+                    </modelField>
+                </model>
+                <model type="Chat" id="chat-1" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="1">
+                </model>
+            </modelType>
+        </decodedData>
+    </project>
+    "#;
+
+    #[test]
+    fn test_extraction_view_0() -> Result<(), String> {
+        let project = parse_project(XML_STR)?;
+        let model = &project.decoded_data.as_ref().unwrap().model_types[0].models[1];
+        assert_eq!(model.extraction(&project).map(|info| info.name.as_str()), Some("File System"));
+        let view = project.extraction_view(1).ok_or("missing extraction")?;
+        assert_eq!(view.models.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(), vec!["chat-1"]);
+        assert_eq!(view.files.iter().map(|f| f.id.as_str()).collect::<Vec<_>>(), vec!["file-0"]);
+        assert_eq!(view.images.len(), 1);
+        assert_eq!(view.models_of_type("Chat").count(), 1);
+        let view = project.extraction_view(0).ok_or("missing extraction")?;
+        assert_eq!(view.items.iter().map(|i| i.text.as_str()).collect::<Vec<_>>(), vec!["Logical"]);
+        assert!(project.extraction_view(2).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_dangling_extraction_refs_0() -> Result<(), String> {
+        let project = parse_project(XML_STR)?;
+        assert_eq!(project.dangling_extraction_refs(), vec![
+            DanglingExtractionRef {
+                element: "model",
+                id: "account-0".to_string(),
+                extraction_id: "3".to_string(),
+            },
+            DanglingExtractionRef {
+                element: "item",
+                id: "ExtractionType".to_string(),
+                extraction_id: "7".to_string(),
+            },
+        ]);
+        assert!(project.validate_extraction_refs().is_err());
+        Ok(())
+    }
+}
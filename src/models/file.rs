@@ -1,9 +1,10 @@
-use std::io::BufRead;
-use quick_xml::{events::{BytesStart, Event}, Reader};
-use super::{attributes_to_map, AccessInfo, Metadata, ParseError};
+use std::io::{BufRead, Write};
+use quick_xml::{events::{BytesEnd, BytesStart, Event}, Reader, Writer};
+use super::{attribute_pairs, attributes_to_map, other_attributes, AccessInfo, ExtractionInfo, Metadata, ParseError, Project};
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct File {
     pub fs: String,
     pub fsid: String,
@@ -15,9 +16,14 @@ pub struct File {
     pub embedded: String,
     pub is_related: String,
     pub access_info: Option<AccessInfo>,
-    pub metadata: Vec<Metadata>
+    pub metadata: Vec<Metadata>,
+    /// Attributes other than the ones above, in document order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub other_attributes: Vec<(String, String)>,
 }
 
+const FILE_ATTRIBUTES: [&str; 9] = ["fs", "fsid", "path", "size", "id", "extractionId", "deleted", "embedded", "isrelated"];
+
 impl File {
     pub fn parse_one<B: BufRead>(
         e: &BytesStart,
@@ -69,9 +75,63 @@ impl File {
             embedded: map.get("embedded").cloned().ok_or("missing embedded")?,
             is_related: map.get("isrelated").cloned().ok_or("missing isrelated")?,
             access_info,
-            metadata
+            metadata,
+            other_attributes: other_attributes(e, &FILE_ATTRIBUTES)?,
         })
     }
+
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        let size = self.size.to_string();
+        let extraction_id = self.extraction_id.to_string();
+        writer.write_event(Event::Start(BytesStart::new("file").with_attributes([
+            ("fs", self.fs.as_str()),
+            ("fsid", self.fsid.as_str()),
+            ("path", self.path.as_str()),
+            ("size", size.as_str()),
+            ("id", self.id.as_str()),
+            ("extractionId", extraction_id.as_str()),
+            ("deleted", self.deleted.as_str()),
+            ("embedded", self.embedded.as_str()),
+            ("isrelated", self.is_related.as_str()),
+        ]).with_attributes(attribute_pairs(&self.other_attributes))))?;
+        if let Some(access_info) = &self.access_info {
+            access_info.write_one(writer)?;
+        }
+        for metadata in &self.metadata {
+            metadata.write_one(writer)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("file")))?;
+        Ok(())
+    }
+
+    pub fn extraction<'a>(&self, project: &'a Project) -> Option<&'a ExtractionInfo> {
+        project.source_extractions.get(self.extraction_id)
+    }
+
+    /// Looks up a hash such as `MD5` or `SHA256` in the file metadata,
+    /// tolerating names such as `SHA-256` or `sha256`.
+    pub fn hash(&self, algorithm: &str) -> Option<&str> {
+        self.metadata
+            .iter()
+            .flat_map(|metadata| metadata.items.iter())
+            .find(|item| {
+                let name: String = item.name.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+                name.eq_ignore_ascii_case(algorithm)
+            })
+            .map(|item| item.text.as_str())
+            .filter(|text| !text.is_empty())
+    }
+
+    /// The `Local Path` of the file inside the package, e.g.
+    /// `files\Image\a.jpg`.
+    pub fn local_path(&self) -> Option<&str> {
+        self.metadata
+            .iter()
+            .flat_map(|metadata| metadata.items.iter())
+            .find(|item| item.name == "Local Path")
+            .map(|item| item.text.as_str())
+            .filter(|text| !text.is_empty())
+    }
 }
 
 
@@ -145,10 +205,12 @@ mod test {
                 Timestamp {
                     name: "CreationTime".to_string(),
                     text: "2020-08-08T15:50:58.000+00:00".to_string(),
+                    other_attributes: vec![],
                 },
                 Timestamp {
                     name: "ModifyTime".to_string(),
                     text: "2020-08-08T15:50:58.000+00:00".to_string(),
+                    other_attributes: vec![],
                 }
             ] }),
             metadata: vec![
@@ -159,10 +221,13 @@ mod test {
                         group: None,
                         id: None,
                         source_extraction: None,
-                        text: r"files\Image\4454825783_dbcb233af5_b.jpg".to_string()
-                    }]
+                        text: r"files\Image\4454825783_dbcb233af5_b.jpg".to_string(),
+                        other_attributes: vec![],
+                    }],
+                    other_attributes: vec![],
                 }
             ],
+            other_attributes: vec![],
         })
     }
 }
\ No newline at end of file
@@ -1,10 +1,11 @@
-use std::io::BufRead;
-use quick_xml::{events::{BytesStart, Event}, Reader};
-use super::{attributes_to_map, ParseError};
+use std::io::{BufRead, Write};
+use quick_xml::{events::{BytesEnd, BytesStart, Event}, Reader, Writer};
+use super::{attribute_pairs, attributes_to_map, other_attributes, ExtractionInfo, ParseError, Project};
 
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Images {
     pub images: Vec<Image>
 }
@@ -46,10 +47,20 @@ impl Images {
         }
         Ok(Images { images })
     }
+
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        writer.write_event(Event::Start(BytesStart::new("images")))?;
+        for image in &self.images {
+            image.write_one(writer)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("images")))?;
+        Ok(())
+    }
 }
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Image {
     pub key: String,
     pub path: String,
@@ -57,8 +68,13 @@ pub struct Image {
     pub dtype: String,
     pub verify: String,
     pub extraction_id: u64,
+    /// Attributes other than the ones above, in document order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub other_attributes: Vec<(String, String)>,
 }
 
+const IMAGE_ATTRIBUTES: [&str; 6] = ["key", "path", "size", "type", "verify", "extractionId"];
+
 impl Image {
     pub fn parse_one(e: &BytesStart) -> Result<Self, Box<dyn std::error::Error>> {
         let map = attributes_to_map(e)?;
@@ -69,8 +85,27 @@ impl Image {
             dtype: map.get("type").ok_or("missing type")?.parse()?,
             verify: map.get("verify").ok_or("missing verify")?.parse()?,
             extraction_id: map.get("extractionId").ok_or("missing extractionId")?.parse()?,
+            other_attributes: other_attributes(e, &IMAGE_ATTRIBUTES)?,
         })
     }
+
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        let size = self.size.to_string();
+        let extraction_id = self.extraction_id.to_string();
+        writer.write_event(Event::Empty(BytesStart::new("image").with_attributes([
+            ("key", self.key.as_str()),
+            ("path", self.path.as_str()),
+            ("size", size.as_str()),
+            ("type", self.dtype.as_str()),
+            ("verify", self.verify.as_str()),
+            ("extractionId", extraction_id.as_str()),
+        ]).with_attributes(attribute_pairs(&self.other_attributes))))?;
+        Ok(())
+    }
+
+    pub fn extraction<'a>(&self, project: &'a Project) -> Option<&'a ExtractionInfo> {
+        project.source_extractions.get(self.extraction_id)
+    }
 }
 
 
@@ -123,6 +158,7 @@ mod test {
             dtype: "File".to_string(),
             verify: "NoSourceValues".to_string(),
             extraction_id: 2,
+            other_attributes: vec![],
         }]})
     }
 
@@ -134,7 +170,7 @@ mod test {
                 Ok(Event::Empty(e)) if e.name().as_ref() == b"image" => {
                     let uwu = Image::parse_one(&e);
                     if let Ok(image) = uwu {
-                        let known_keys = vec![
+                        let known_keys = [
                             "key",
                             "path",
                             "size",
@@ -172,6 +208,7 @@ mod test {
             dtype: "File".to_string(),
             verify: "NoSourceValues".to_string(),
             extraction_id: 2,
+            other_attributes: vec![],
         })
     }
 }
\ No newline at end of file
@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use super::{ExtractionInfo, Model, Project};
+
+
+
+/// Fields deciding whether two models of a type are the same item. Paths
+/// may go through nested models, e.g. `From.Identifier`. Types without an
+/// entry are compared on all of their own fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeKeys {
+    keys: HashMap<String, Vec<String>>,
+}
+
+impl Default for MergeKeys {
+    fn default() -> Self {
+        MergeKeys::empty()
+            .with("InstantMessage", &["TimeStamp", "Body", "From.Identifier"])
+            .with("Chat", &["Source", "Id", "Name"])
+            .with("Contact", &["Name", "Entries.Value"])
+            .with("Call", &["TimeStamp", "Direction", "Duration", "Parties.Identifier"])
+            .with("Email", &["TimeStamp", "Subject", "From.Identifier"])
+            .with("CalendarEntry", &["StartDate", "Subject"])
+            .with("Location", &["TimeStamp", "Position.Latitude", "Position.Longitude"])
+    }
+}
+
+impl MergeKeys {
+    pub fn empty() -> Self {
+        MergeKeys { keys: HashMap::new() }
+    }
+
+    pub fn with(mut self, dtype: &str, paths: &[&str]) -> Self {
+        self.keys.insert(dtype.to_string(), paths.iter().map(|path| path.to_string()).collect());
+        self
+    }
+
+    /// Key of `model`, `None` when any key field is empty, or for types
+    /// without an entry when the model has no fields, so that unrelated
+    /// sparse models are never merged.
+    pub fn key(&self, model: &Model) -> Option<Vec<String>> {
+        let key: Vec<String> = match self.keys.get(&model.dtype) {
+            Some(paths) => paths
+                .iter()
+                .map(|path| {
+                    let mut values = path_values(model, path);
+                    values.sort_unstable();
+                    values.join("\u{1f}")
+                })
+                .collect(),
+            None => {
+                let mut fields: Vec<String> = model
+                    .fields
+                    .iter()
+                    .filter(|field| !field.value_text().is_empty())
+                    .map(|field| format!("{}={}", field.name, field.value_text()))
+                    .collect();
+                fields.sort_unstable();
+                fields
+            }
+        };
+        if key.is_empty() || key.iter().any(String::is_empty) {
+            None
+        } else {
+            Some(key)
+        }
+    }
+}
+
+fn path_values<'a>(model: &'a Model, path: &str) -> Vec<&'a str> {
+    match path.split_once('.') {
+        None => model.field_text(path).into_iter().collect(),
+        Some((head, rest)) => model
+            .nested_models(head)
+            .iter()
+            .flat_map(|nested| path_values(nested, rest))
+            .collect(),
+    }
+}
+
+
+#[derive(Debug, PartialEq)]
+pub struct Provenance<'a> {
+    pub extraction_id: u64,
+    pub extraction: Option<&'a ExtractionInfo>,
+    pub model: &'a Model,
+}
+
+
+/// One item of a report together with every copy of it.
+#[derive(Debug, PartialEq)]
+pub struct MergedModel<'a> {
+    pub dtype: &'a str,
+    pub key: Option<Vec<String>>,
+    pub copies: Vec<Provenance<'a>>,
+}
+
+impl<'a> MergedModel<'a> {
+    /// The first copy in document order.
+    pub fn representative(&self) -> &'a Model {
+        self.copies[0].model
+    }
+
+    pub fn extraction_ids(&self) -> Vec<u64> {
+        let mut ids: Vec<u64> = self.copies.iter().map(|copy| copy.extraction_id).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    pub fn is_duplicated(&self) -> bool {
+        self.copies.len() > 1
+    }
+}
+
+
+/// Top-level models grouped across extractions. A group holds at most one
+/// copy per extraction; equal models within one extraction stay apart.
+#[derive(Debug, PartialEq)]
+pub struct MergedView<'a> {
+    pub groups: Vec<MergedModel<'a>>,
+}
+
+impl<'a> MergedView<'a> {
+    pub fn new(project: &'a Project, keys: &MergeKeys) -> Self {
+        let mut groups: Vec<MergedModel<'a>> = Vec::new();
+        let mut index: HashMap<(&'a str, Vec<String>), Vec<usize>> = HashMap::new();
+        let models = project
+            .decoded_data
+            .iter()
+            .flat_map(|decoded_data| decoded_data.model_types.iter())
+            .flat_map(|model_type| model_type.models.iter());
+        for model in models {
+            let copy = Provenance {
+                extraction_id: model.extraction_id,
+                extraction: model.extraction(project),
+                model,
+            };
+            let key = keys.key(model);
+            let candidates = match &key {
+                Some(key) => index.entry((model.dtype.as_str(), key.clone())).or_default(),
+                None => {
+                    groups.push(MergedModel { dtype: &model.dtype, key, copies: vec![copy] });
+                    continue;
+                }
+            };
+            let existing = candidates
+                .iter()
+                .copied()
+                .find(|&position| groups[position].copies.iter().all(|other| other.extraction_id != model.extraction_id));
+            match existing {
+                Some(position) => groups[position].copies.push(copy),
+                None => {
+                    candidates.push(groups.len());
+                    groups.push(MergedModel { dtype: &model.dtype, key, copies: vec![copy] });
+                }
+            }
+        }
+        MergedView { groups }
+    }
+
+    /// Every model, or one representative per group with `unique_only`.
+    pub fn models(&self, unique_only: bool) -> Vec<&'a Model> {
+        if unique_only {
+            self.groups.iter().map(MergedModel::representative).collect()
+        } else {
+            self.groups.iter().flat_map(|group| group.copies.iter().map(|copy| copy.model)).collect()
+        }
+    }
+
+    pub fn duplicates(&self) -> impl Iterator<Item = &MergedModel<'a>> {
+        self.groups.iter().filter(|group| group.is_duplicated())
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use quick_xml::{events::Event, Reader};
+    use crate::models::{ModelBuilder, ProjectBuilder};
+    use super::*;
+
+    fn parse_project(xml_str: &str) -> Result<Project, String> {
+        let mut reader = Reader::from_reader(Cursor::new(xml_str));
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(e)) if e.name().as_ref() == b"project" => {
+                    return Project::parse_one(&e, &mut reader).map_err(|e| e.to_string());
+                },
+                Ok(Event::Eof) => {
+                    return Err("eof".to_string());
+                }
+                _ => (),
+            }
+            buf.clear();
+        }
+    }
+
+    const XML_STR: &str = r#"
+    <project id="00000000-0000-0000-0000-000000000000" name="Test" reportVersion="7.0.0.0" licenseID="0" containsGarbage="False" extractionType="Logical" NodeCount="5" ModelCount="5" xmlns="http://pa.cellebrite.com/report/2.0">
+        <sourceExtractions>
+            <extractionInfo id="0" name="Logical" isCustomName="False" type="Logical" deviceName="Report" fullName="Cellebrite UFED Reports" index="0" IsPartialData="False" />
+            <extractionInfo id="1" name="File System" isCustomName="False" type="FileSystem" deviceName="Report" fullName="Cellebrite UFED Reports" index="1" IsPartialData="False" />
+        </sourceExtractions>
+        <caseInformation>
+        </caseInformation>
+        <decodedData>
+            <modelType type="InstantMessage">
+                <model type="InstantMessage" id="message-0" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                    <field name="Body" type="String"><value type="String"><![CDATA[hi]]></value></field>
+                    <field name="TimeStamp" type="TimeStamp"><value type="TimeStamp">2020-07-01T07:45:53.000+00:00</value></field>
+                    <modelField name="From" type="Party">
+                        <model type="Party" id="party-0" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                            <field name="Identifier" type="String"><value type="String"><![CDATA[+43123]]></value></field>
+                        </model>
+                    </modelField>
+                </model>
+                <model type="InstantMessage" id="message-1" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="1">
+                    <field name="Body" type="String"><value type="String"><![CDATA[hi]]></value></field>
+                    <field name="TimeStamp" type="TimeStamp"><value type="TimeStamp">2020-07-01T07:45:53.000+00:00</value></field>
+                    <modelField name="From" type="Party">
+                        <model type="Party" id="party-1" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="1">
+                            <field name="Identifier" type="String"><value type="String"><![CDATA[+43123]]></value></field>
+                        </model>
+                    </modelField>
+                </model>
+                <model type="InstantMessage" id="message-2" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="1">
+                    <field name="Body" type="String"><value type="String"><![CDATA[bye]]></value></field>
+                </model>
+            </modelType>
+            <modelType type="Note">
+                <model type="Note" id="note-0" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                </model>
+                <model type="Note" id="note-1" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="1">
+                </model>
+            </modelType>
+        </decodedData>
+    </project>
+    "#;
+
+    #[test]
+    fn test_merged_view_0() -> Result<(), String> {
+        let project = parse_project(XML_STR)?;
+        let view = MergedView::new(&project, &MergeKeys::default());
+        assert_eq!(view.groups.len(), 4);
+        let duplicates: Vec<&MergedModel> = view.duplicates().collect();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].representative().id, "message-0");
+        assert_eq!(duplicates[0].extraction_ids(), vec![0, 1]);
+        assert_eq!(duplicates[0].copies[1].extraction.map(|info| info.name.as_str()), Some("File System"));
+        let unique: Vec<&str> = view.models(true).iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(unique, vec!["message-0", "message-2", "note-0", "note-1"]);
+        assert_eq!(view.models(false).len(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_keys_0() -> Result<(), String> {
+        let project = parse_project(XML_STR)?;
+        let keys = MergeKeys::empty().with("InstantMessage", &["Body"]);
+        let view = MergedView::new(&project, &keys);
+        let model = view.groups[0].representative();
+        assert_eq!(keys.key(model), Some(vec!["hi".to_string()]));
+        assert_eq!(MergeKeys::default().key(model), Some(vec![
+            "2020-07-01T07:45:53.000+00:00".to_string(),
+            "hi".to_string(),
+            "+43123".to_string(),
+        ]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_merged_view_1() {
+        let message = |extraction_id: u64| {
+            ModelBuilder::new("InstantMessage")
+                .extraction_id(extraction_id)
+                .field("Body", "hi")
+                .typed_field("TimeStamp", "TimeStamp", "2020-07-01T07:45:53.000+00:00")
+                .model("From", ModelBuilder::new("Party").field("Identifier", "+43123"))
+        };
+        let body_only = |extraction_id: u64| ModelBuilder::new("InstantMessage").extraction_id(extraction_id).field("Body", "ok");
+        let project = ProjectBuilder::new("Test")
+            .extraction("Logical", "Logical", "iPhone")
+            .extraction("File System", "FileSystem", "iPhone")
+            .models([message(0), message(0), message(1), body_only(0), body_only(1)])
+            .build();
+        let view = MergedView::new(&project, &MergeKeys::default());
+        let groups: Vec<Vec<u64>> = view.groups.iter().map(|group| group.copies.iter().map(|copy| copy.extraction_id).collect()).collect();
+        assert_eq!(groups, vec![vec![0, 1], vec![0], vec![0], vec![1]]);
+    }
+}
@@ -1,13 +1,17 @@
-use std::io::BufRead;
-use quick_xml::{events::{BytesStart, Event}, Reader};
-use super::{attributes_to_map, Item, ParseError};
+use std::io::{BufRead, Write};
+use quick_xml::{events::{BytesEnd, BytesStart, Event}, Reader, Writer};
+use super::{attribute_pairs, attributes_to_map, other_attributes, Item, ParseError};
 
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Metadata {
     pub section: String,
-    pub items: Vec<Item>
+    pub items: Vec<Item>,
+    /// Attributes other than the ones above, in document order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub other_attributes: Vec<(String, String)>,
 }
 
 impl Metadata {
@@ -51,8 +55,18 @@ impl Metadata {
         Ok(Metadata {
             section: map.get("section").cloned().ok_or("missing section")?,
             items,
+            other_attributes: other_attributes(e, &["section"])?,
         })
     }
+
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        writer.write_event(Event::Start(BytesStart::new("metadata").with_attributes([("section", self.section.as_str())]).with_attributes(attribute_pairs(&self.other_attributes))))?;
+        for item in &self.items {
+            item.write_one(writer)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("metadata")))?;
+        Ok(())
+    }
 }
 
 
@@ -71,7 +85,7 @@ mod test {
                 Ok(Event::Start(e)) if e.name().as_ref() == b"metadata" => {
                     let uwu = Metadata::parse_one(&e, &mut reader);
                     if let Ok(metadata) = uwu {
-                        let known_keys = vec![
+                        let known_keys = [
                             "section",
                         ];
                         for key in attributes_to_map(&e).unwrap().keys() {
@@ -109,6 +123,7 @@ mod test {
                     group: None,
                     source_extraction: None,
                     text: "10/10/2020 13:37:00".to_string(),
+                    other_attributes: vec![],
                 },
                 Item {
                     id: None,
@@ -116,8 +131,10 @@ mod test {
                     group: None,
                     source_extraction: None,
                     text: "1.2.3.4".to_string(),
+                    other_attributes: vec![],
                 },
-            ]
+            ],
+            other_attributes: vec![],
         })
     }
 
@@ -142,6 +159,7 @@ mod test {
                     group: None,
                     source_extraction: Some("0".to_string()),
                     text: "Apple".to_string(),
+                    other_attributes: vec![],
                 },
                 Item {
                     id: None,
@@ -149,6 +167,7 @@ mod test {
                     group: None,
                     source_extraction: Some("0".to_string()),
                     text: "Apple".to_string(),
+                    other_attributes: vec![],
                 },
                 Item {
                     id: None,
@@ -156,6 +175,7 @@ mod test {
                     group: None,
                     source_extraction: Some("0".to_string()),
                     text: "Logical".to_string(),
+                    other_attributes: vec![],
                 },
                 Item {
                     id: None,
@@ -163,6 +183,7 @@ mod test {
                     group: None,
                     source_extraction: Some("0".to_string()),
                     text: "12345678-a931-45a1-b3a1-1234567890A".to_string(),
+                    other_attributes: vec![],
                 },
                 Item {
                     id: None,
@@ -170,6 +191,7 @@ mod test {
                     group: None,
                     source_extraction: Some("0".to_string()),
                     text: "10/10/2020 13:37:00".to_string(),
+                    other_attributes: vec![],
                 },
                 Item {
                     id: None,
@@ -177,8 +199,10 @@ mod test {
                     group: None,
                     source_extraction: Some("0".to_string()),
                     text: "10/10/2020 14:00:00".to_string(),
+                    other_attributes: vec![],
                 },
-            ]
+            ],
+            other_attributes: vec![],
         })
     }
 
@@ -212,6 +236,7 @@ mod test {
                     group: Some("iPhone of Prof Moriarty".to_string()),
                     source_extraction: Some("0".to_string()),
                     text: "1.2.31".to_string(),
+                    other_attributes: vec![],
                 },
                 Item {
                     id: Some("dc8707a8-d227-4715-ba39-b59632543eb6".to_string()),
@@ -219,6 +244,7 @@ mod test {
                     group: Some("iPhone of Prof Moriarty".to_string()),
                     source_extraction: Some("0".to_string()),
                     text: "1.2.3".to_string(),
+                    other_attributes: vec![],
                 },
                 Item {
                     id: Some("0da47208-0b0d-46ad-9eb3-b3ebd9eb8f69".to_string()),
@@ -226,6 +252,7 @@ mod test {
                     group: Some("iPhone of Prof Moriarty".to_string()),
                     source_extraction: Some("0".to_string()),
                     text: "Activated".to_string(),
+                    other_attributes: vec![],
                 },
                 Item {
                     id: Some("579d8e32-8255-4286-870a-93efecce33de".to_string()),
@@ -233,6 +260,7 @@ mod test {
                     group: Some("iPhone of Prof Moriarty".to_string()),
                     source_extraction: Some("0".to_string()),
                     text: "12:34:56:78:90:ab".to_string(),
+                    other_attributes: vec![],
                 },
                 Item {
                     id: Some("e56c40e2-40af-4ffc-9197-58a4e0e64e58".to_string()),
@@ -240,6 +268,7 @@ mod test {
                     group: Some("iPhone of Prof Moriarty".to_string()),
                     source_extraction: Some("".to_string()),
                     text: "Ready".to_string(),
+                    other_attributes: vec![],
                 },
                 Item {
                     id: Some("dbb39d9d-1288-4546-9e71-e7ac5e03b441".to_string()),
@@ -247,6 +276,7 @@ mod test {
                     group: Some("iPhone of Prof Moriarty".to_string()),
                     source_extraction: Some("0".to_string()),
                     text: "12:34:56:78:90:ab".to_string(),
+                    other_attributes: vec![],
                 },
                 Item {
                     id: Some("4db3ebf8-f9b7-4f7a-9912-74e4d26c140b".to_string()),
@@ -254,6 +284,7 @@ mod test {
                     group: Some("iPhone of Prof Moriarty".to_string()),
                     source_extraction: Some("0".to_string()),
                     text: "42 GB".to_string(),
+                    other_attributes: vec![],
                 },
                 Item {
                     id: Some("49381e39-0e49-469e-b1bf-751b0495f652".to_string()),
@@ -261,6 +292,7 @@ mod test {
                     group: Some("iPhone of Prof Moriarty".to_string()),
                     source_extraction: Some("0".to_string()),
                     text: "21 GB".to_string(),
+                    other_attributes: vec![],
                 },
                 Item {
                     id: Some("e0e74416-208e-4aaf-b558-f2156432e65f".to_string()),
@@ -268,6 +300,7 @@ mod test {
                     group: Some("iPhone of Prof Moriarty".to_string()),
                     source_extraction: Some("0".to_string()),
                     text: "Europe/Vienna".to_string(),
+                    other_attributes: vec![],
                 },
                 Item {
                     id: Some("d0c17d7a-7bdb-4c2d-85a3-6154b649879d".to_string()),
@@ -275,6 +308,7 @@ mod test {
                     group: Some("iPhone of Prof Moriarty".to_string()),
                     source_extraction: Some("0".to_string()),
                     text: "ABCDEF".to_string(),
+                    other_attributes: vec![],
                 },
                 Item {
                     id: Some("354c50b2-7757-43fe-9ca7-f8cba8c01e4d".to_string()),
@@ -282,6 +316,7 @@ mod test {
                     group: Some("Metadata".to_string()),
                     source_extraction: Some("0".to_string()),
                     text: "iOS".to_string(),
+                    other_attributes: vec![],
                 },
                 Item {
                     id: Some("210ab633-3908-4c59-b0b9-da29ec4b1da6".to_string()),
@@ -289,6 +324,7 @@ mod test {
                     group: Some("Phone Settings".to_string()),
                     source_extraction: Some("0".to_string()),
                     text: "Europe/Vienna".to_string(),
+                    other_attributes: vec![],
                 },
                 Item {
                     id: Some("806912da-1d09-476e-b3e6-5cdd5b5d6791".to_string()),
@@ -296,6 +332,7 @@ mod test {
                     group: None,
                     source_extraction: Some("0".to_string()),
                     text: "09.09.2020 19:33:13(UTC+0)".to_string(),
+                    other_attributes: vec![],
                 },
                 Item {
                     id: Some("92880fa1-d8ec-43ba-b3a3-c9d7824ae90a".to_string()),
@@ -303,6 +340,7 @@ mod test {
                     group: Some("Phone Settings".to_string()),
                     source_extraction: Some("0".to_string()),
                     text: "de_AT".to_string(),
+                    other_attributes: vec![],
                 },
                 Item {
                     id: Some("6bef83e8-bdd8-48ec-92f1-653122740fc1".to_string()),
@@ -310,8 +348,10 @@ mod test {
                     group: Some("Phone Settings".to_string()),
                     source_extraction: Some("0".to_string()),
                     text: "True".to_string(),
+                    other_attributes: vec![],
                 },
-            ]
+            ],
+            other_attributes: vec![],
         })
     }
 }
\ No newline at end of file
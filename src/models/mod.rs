@@ -1,3 +1,12 @@
+//! Structs mirroring the elements of a UFDR `report.xml`.
+//!
+//! With the `serde` feature every struct derives `Serialize` and
+//! `Deserialize`. Serialized field names are the Rust field names, except
+//! for `dtype` which is serialized as `type` like the XML attribute it comes
+//! from. Timestamps are RFC 3339 strings and MAC addresses use the
+//! `12:34:56:78:90:ab` notation. These names are part of the public API and
+//! only change with a major version.
+
 pub mod case_information;
 pub mod metadata;
 pub mod source_extractions;
@@ -15,6 +24,17 @@ pub mod data_field;
 pub mod source;
 pub mod multi_field;
 pub mod multi_model_field;
+pub mod model_graph;
+pub mod device_info;
+pub mod case_info;
+pub mod extraction_view;
+pub mod merged_view;
+pub mod typed_value;
+pub mod report_stream;
+pub mod builder;
+pub mod transcript;
+pub mod timeline;
+pub mod stats;
 pub mod project;
 pub mod parse_error;
 pub mod utils;
@@ -30,12 +50,23 @@ pub use access_info::AccessInfo;
 pub use timestamp::Timestamp;
 pub use file::File;
 pub use model_type::ModelType;
-pub use model::Model;
+pub use model::{Model, ModelChild};
 pub use model_field::ModelField;
 pub use data_field::DataField;
 pub use source::Source;
 pub use multi_field::MultiField;
 pub use multi_model_field::MultiModelField;
+pub use model_graph::{ModelGraph, ModelNode};
+pub use device_info::{DeviceInfo, MacAddress};
+pub use case_info::{CaseField, CaseInfo};
+pub use extraction_view::{DanglingExtractionRef, ExtractionView};
+pub use merged_view::{MergeKeys, MergedModel, MergedView, Provenance};
+pub use typed_value::{TypedValue, ValueKind};
+pub use report_stream::{ReportItem, ReportStream};
+pub use builder::{FileBuilder, ModelBuilder, ProjectBuilder};
+pub use timeline::{TimelineEvent, TimelineReference};
+pub use stats::{ExtensionStats, ExtractionStats, ModelTypeStats, ReportStats};
+pub use transcript::{NameDirectory, Participant, Transcript, TranscriptAttachment, TranscriptMessage};
 pub use project::Project;
 pub use parse_error::ParseError;
 pub use utils::*;
@@ -1,11 +1,13 @@
-use std::io::BufRead;
-use quick_xml::{events::{BytesStart, Event}, Reader};
-use super::{attributes_to_map, ParseError, Field, ModelField, DataField, MultiField, MultiModelField};
+use std::{collections::{HashMap, VecDeque}, io::{BufRead, Write}};
+use quick_xml::{events::{BytesEnd, BytesStart, Event}, Reader, Writer};
+use super::{attribute_pairs, attributes_to_map, other_attributes, ParseError, ExtractionInfo, Field, ModelField, DataField, MultiField, MultiModelField, Project};
 
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Model {
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
     pub dtype: String,
     pub id: String,
     pub deleted_state: String,
@@ -17,8 +19,40 @@ pub struct Model {
     pub model_fields: Vec<ModelField>,
     pub data_fields: Vec<DataField>,
     pub multi_fields: Vec<MultiField>,
+    /// Order of the children as they appear in the element.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub children: Vec<ModelChild>,
+    /// Attributes other than the ones above, in document order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub other_attributes: Vec<(String, String)>,
 }
 
+/// A child element of a [`Model`], by kind and name. Children of the same
+/// kind and name match the elements of their list in order.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ModelChild {
+    Field(String),
+    MultiField(String),
+    ModelField(String),
+    MultiModelField(String),
+    DataField(String),
+}
+
+impl ModelChild {
+    fn kind_name(&self) -> (usize, &str) {
+        match self {
+            ModelChild::Field(name) => (0, name),
+            ModelChild::MultiField(name) => (1, name),
+            ModelChild::ModelField(name) => (2, name),
+            ModelChild::MultiModelField(name) => (3, name),
+            ModelChild::DataField(name) => (4, name),
+        }
+    }
+}
+
+const MODEL_ATTRIBUTES: [&str; 6] = ["type", "id", "deleted_state", "decoding_confidence", "isrelated", "extractionId"];
+
 impl Model {
     pub fn parse_one<B: BufRead>(e: &BytesStart, reader: &mut Reader<B>) -> Result<Self, Box<dyn std::error::Error>> {
         let map = attributes_to_map(e)?;
@@ -28,28 +62,43 @@ impl Model {
         let mut model_fields = Vec::new();
         let mut data_fields = Vec::new();
         let mut multi_fields = Vec::new();
+        let mut children = Vec::new();
         loop {
             match reader.read_event_into(&mut buf)? {
                 Event::Start(e) if e.name().as_ref() == b"multiModelField" => {
-                    multi_model_fields.push(MultiModelField::parse_one(&e, reader)?);
+                    let multi_model_field = MultiModelField::parse_one(&e, reader)?;
+                    children.push(ModelChild::MultiModelField(multi_model_field.name.clone()));
+                    multi_model_fields.push(multi_model_field);
                 }
                 Event::Empty(e) if e.name().as_ref() == b"multiModelField" => {
-                    multi_model_fields.push(MultiModelField::parse_one_empty(&e)?);
+                    let multi_model_field = MultiModelField::parse_one_empty(&e)?;
+                    children.push(ModelChild::MultiModelField(multi_model_field.name.clone()));
+                    multi_model_fields.push(multi_model_field);
                 }
                 Event::Start(e) if e.name().as_ref() == b"multiField" => {
-                    multi_fields.push(MultiField::parse_one(&e, reader)?);
+                    let multi_field = MultiField::parse_one(&e, reader)?;
+                    children.push(ModelChild::MultiField(multi_field.name.clone()));
+                    multi_fields.push(multi_field);
                 }
                 Event::Empty(e) if e.name().as_ref() == b"multiField" => {
-                    multi_fields.push(MultiField::parse_one_empty(&e)?);
+                    let multi_field = MultiField::parse_one_empty(&e)?;
+                    children.push(ModelChild::MultiField(multi_field.name.clone()));
+                    multi_fields.push(multi_field);
                 }
                 Event::Start(e) if e.name().as_ref() == b"field" => {
-                    fields.push(Field::parse_one(&e, reader)?);
+                    let field = Field::parse_one(&e, reader)?;
+                    children.push(ModelChild::Field(field.name.clone()));
+                    fields.push(field);
                 }
                 Event::Start(e) if e.name().as_ref() == b"modelField" => {
-                    model_fields.push(ModelField::parse_one(&e, reader)?);
+                    let model_field = ModelField::parse_one(&e, reader)?;
+                    children.push(ModelChild::ModelField(model_field.name.clone()));
+                    model_fields.push(model_field);
                 }
                 Event::Start(e) if e.name().as_ref() == b"dataField" => {
-                    data_fields.push(DataField::parse_one(&e, reader)?);
+                    let data_field = DataField::parse_one(&e, reader)?;
+                    children.push(ModelChild::DataField(data_field.name.clone()));
+                    data_fields.push(data_field);
                 }
                 Event::End(e) if e.name().as_ref() == b"model" => break,
                 Event::Eof => {
@@ -87,8 +136,101 @@ impl Model {
             model_fields,
             data_fields,
             multi_fields,
+            children,
+            other_attributes: other_attributes(e, &MODEL_ATTRIBUTES)?,
         })
     }
+
+    /// Children are written in the order of `children`, matched by kind and
+    /// name, so the lists can be edited without updating it. Entries without
+    /// a matching child are skipped, children without an entry follow
+    /// grouped by kind.
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        let extraction_id = self.extraction_id.to_string();
+        let attributes = [
+            ("type", self.dtype.as_str()),
+            ("id", self.id.as_str()),
+            ("deleted_state", self.deleted_state.as_str()),
+            ("decoding_confidence", self.decoding_confidence.as_str()),
+            ("isrelated", self.is_related.as_str()),
+            ("extractionId", extraction_id.as_str()),
+        ];
+        writer.write_event(Event::Start(BytesStart::new("model").with_attributes(attributes).with_attributes(attribute_pairs(&self.other_attributes))))?;
+        let names: [Vec<&str>; 5] = [
+            self.fields.iter().map(|field| field.name.as_str()).collect(),
+            self.multi_fields.iter().map(|multi_field| multi_field.name.as_str()).collect(),
+            self.model_fields.iter().map(|model_field| model_field.name.as_str()).collect(),
+            self.multi_model_fields.iter().map(|multi_model_field| multi_model_field.name.as_str()).collect(),
+            self.data_fields.iter().map(|data_field| data_field.name.as_str()).collect(),
+        ];
+        let mut pending: HashMap<(usize, &str), VecDeque<usize>> = HashMap::new();
+        for (kind, names) in names.iter().enumerate() {
+            for (index, name) in names.iter().enumerate() {
+                pending.entry((kind, name)).or_default().push_back(index);
+            }
+        }
+        let mut written = names.each_ref().map(|names| vec![false; names.len()]);
+        for child in &self.children {
+            let (kind, name) = child.kind_name();
+            if let Some(index) = pending.get_mut(&(kind, name)).and_then(VecDeque::pop_front) {
+                self.write_child(kind, index, writer)?;
+                written[kind][index] = true;
+            }
+        }
+        for (kind, written) in written.iter().enumerate() {
+            for index in written.iter().enumerate().filter(|(_, written)| !**written).map(|(index, _)| index) {
+                self.write_child(kind, index, writer)?;
+            }
+        }
+        writer.write_event(Event::End(BytesEnd::new("model")))?;
+        Ok(())
+    }
+
+    fn write_child<W: Write>(&self, kind: usize, index: usize, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        match kind {
+            0 => self.fields[index].write_one(writer),
+            1 => self.multi_fields[index].write_one(writer),
+            2 => self.model_fields[index].write_one(writer),
+            3 => self.multi_model_fields[index].write_one(writer),
+            _ => self.data_fields[index].write_one(writer),
+        }
+    }
+
+    pub fn extraction<'a>(&self, project: &'a Project) -> Option<&'a ExtractionInfo> {
+        project.source_extractions.get(self.extraction_id)
+    }
+
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    /// Text of the field `name`, `None` if it is missing or empty.
+    pub fn field_text(&self, name: &str) -> Option<&str> {
+        self.field(name).map(Field::value_text).filter(|text| !text.is_empty())
+    }
+
+    pub fn multi_field(&self, name: &str) -> Option<&MultiField> {
+        self.multi_fields.iter().find(|multi_field| multi_field.name == name)
+    }
+
+    pub fn model_field(&self, name: &str) -> Option<&ModelField> {
+        self.model_fields.iter().find(|model_field| model_field.name == name)
+    }
+
+    pub fn multi_model_field(&self, name: &str) -> Option<&MultiModelField> {
+        self.multi_model_fields.iter().find(|multi_model_field| multi_model_field.name == name)
+    }
+
+    /// Models nested under the modelField or multiModelField `name`.
+    pub fn nested_models(&self, name: &str) -> &[Model] {
+        if let Some(model_field) = self.model_field(name) {
+            &model_field.models
+        } else if let Some(multi_model_field) = self.multi_model_field(name) {
+            &multi_model_field.models
+        } else {
+            &[]
+        }
+    }
 }
 
 
@@ -151,12 +293,19 @@ mod test {
             decoding_confidence: "High".to_string(),
             is_related: "False".to_string(),
             extraction_id: 1,
+            children: vec![
+                ModelChild::Field("UserMapping".to_string()),
+                ModelChild::ModelField("From".to_string()),
+                ModelChild::MultiModelField("To".to_string()),
+            ],
+            other_attributes: vec![],
             multi_fields: vec![],
             data_fields: vec![],
             model_fields: vec![ModelField {
                 name: "From".to_string(),
                 dtype: "Party".to_string(),
                 models: vec![],
+                other_attributes: vec![],
             }],
             fields: vec![
                 Field {
@@ -169,8 +318,10 @@ mod test {
                     value: Some(Value {
                         dtype: "Boolean".to_string(),
                         text: "False".to_string(),
+                        other_attributes: vec![],
                     }),
                     field_type: None,
+                    other_attributes: vec![],
                 }
             ],
             multi_model_fields: vec![
@@ -178,8 +329,53 @@ mod test {
                     name: "To".to_string(),
                     dtype: "Party".to_string(),
                     models: vec![],
+                    other_attributes: vec![],
                 }
             ],
         })
     }
-}
\ No newline at end of file
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_model_serde_0() -> Result<(), String> {
+        let model = Model {
+            dtype: "InstantMessage".to_string(),
+            id: "8204cd26-21cc-4510-9bc2-543253465415".to_string(),
+            deleted_state: "Intact".to_string(),
+            decoding_confidence: "High".to_string(),
+            is_related: "False".to_string(),
+            extraction_id: 1,
+            children: vec![ModelChild::Field("Body".to_string())],
+            other_attributes: vec![],
+            multi_fields: vec![],
+            data_fields: vec![],
+            model_fields: vec![],
+            fields: vec![
+                Field {
+                    name: "Body".to_string(),
+                    dtype: Some("String".to_string()),
+                    is_system: None,
+                    is_required: None,
+                    text: "".to_string(),
+                    multiple_lines: None,
+                    value: Some(Value {
+                        dtype: "String".to_string(),
+                        text: "hi".to_string(),
+                        other_attributes: vec![],
+                    }),
+                    field_type: None,
+                    other_attributes: vec![],
+                }
+            ],
+            multi_model_fields: vec![],
+        };
+        let json = serde_json::to_value(&model).map_err(|e| e.to_string())?;
+        assert_eq!(json["type"], "InstantMessage");
+        assert_eq!(json["extraction_id"], 1);
+        assert_eq!(json["fields"][0]["value"]["type"], "String");
+        assert_eq!(json["fields"][0]["value"]["text"], "hi");
+        let parsed: Model = serde_json::from_value(json).map_err(|e| e.to_string())?;
+        assert_eq!(parsed, model);
+        Ok(())
+    }
+}
@@ -1,14 +1,19 @@
-use std::io::BufRead;
-use quick_xml::{events::{BytesStart, Event}, Reader};
-use super::{attributes_to_map, Model, ParseError};
+use std::io::{BufRead, Write};
+use quick_xml::{events::{BytesEnd, BytesStart, Event}, Reader, Writer};
+use super::{attribute_pairs, attributes_to_map, other_attributes, Model, ParseError};
 
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ModelField {
     pub name: String,
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
     pub dtype: String,
     pub models: Vec<Model>,
+    /// Attributes other than the ones above, in document order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub other_attributes: Vec<(String, String)>,
 }
 
 impl ModelField {
@@ -50,15 +55,31 @@ impl ModelField {
             name: map.get("name").cloned().ok_or("missing name")?,
             dtype: map.get("type").cloned().ok_or("missing type")?,
             models,
+            other_attributes: other_attributes(e, &["name", "type"])?,
         })
     }
+
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        writer.write_event(Event::Start(BytesStart::new("modelField").with_attributes([
+            ("name", self.name.as_str()),
+            ("type", self.dtype.as_str()),
+        ]).with_attributes(attribute_pairs(&self.other_attributes))))?;
+        if self.models.is_empty() {
+            writer.write_event(Event::Empty(BytesStart::new("empty")))?;
+        }
+        for model in &self.models {
+            model.write_one(writer)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("modelField")))?;
+        Ok(())
+    }
 }
 
 
 #[cfg(test)]
 mod test {
     use std::io::Cursor;
-    use crate::models::{Field, Value};
+    use crate::models::{Field, ModelChild, Value};
     use super::*;
 
     fn test_model_field(xml_str: &str, expected: ModelField) -> Result<(), String> {
@@ -113,6 +134,8 @@ mod test {
                     decoding_confidence: "High".to_string(),
                     is_related: "False".to_string(),
                     extraction_id: 1,
+                    children: vec![ModelChild::Field("UserMapping".to_string())],
+                    other_attributes: vec![],
                     fields: vec![Field {
                         name: "UserMapping".to_string(),
                         dtype: Some("Boolean".to_string()),
@@ -123,15 +146,18 @@ mod test {
                         value: Some(Value {
                             dtype: "Boolean".to_string(),
                             text: "False".to_string(),
+                            other_attributes: vec![],
                         }),
                         field_type: None,
+                        other_attributes: vec![],
                     }],
                     model_fields: vec![],
                     multi_model_fields: vec![],
                     multi_fields: vec![],
                     data_fields: vec![],
                 }
-            ]
+            ],
+            other_attributes: vec![],
         })
     }
 }
\ No newline at end of file
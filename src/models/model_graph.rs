@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use super::{DecodedData, Model, Project};
+
+
+
+#[derive(Debug, PartialEq)]
+pub struct ModelNode<'a> {
+    pub model: &'a Model,
+    pub parent: Option<usize>,
+    pub field_name: Option<&'a str>,
+    pub depth: usize,
+}
+
+
+/// Index over every model of a report, nested ones included.
+///
+/// Nodes are stored in document order; `parent` and the values of the id map
+/// are indices into that list.
+#[derive(Debug, Default)]
+pub struct ModelGraph<'a> {
+    nodes: Vec<ModelNode<'a>>,
+    children: Vec<Vec<usize>>,
+    by_id: HashMap<&'a str, Vec<usize>>,
+}
+
+impl<'a> ModelGraph<'a> {
+    pub fn new(decoded_data: &'a DecodedData) -> Self {
+        let mut graph = ModelGraph::default();
+        for model_type in &decoded_data.model_types {
+            for model in &model_type.models {
+                graph.insert(model, None, None, 0);
+            }
+        }
+        graph
+    }
+
+    pub fn from_project(project: &'a Project) -> Self {
+        match &project.decoded_data {
+            Some(decoded_data) => ModelGraph::new(decoded_data),
+            None => ModelGraph::default(),
+        }
+    }
+
+    fn insert(&mut self, model: &'a Model, parent: Option<usize>, field_name: Option<&'a str>, depth: usize) {
+        let index = self.nodes.len();
+        self.nodes.push(ModelNode { model, parent, field_name, depth });
+        self.children.push(Vec::new());
+        self.by_id.entry(model.id.as_str()).or_default().push(index);
+        if let Some(parent) = parent {
+            self.children[parent].push(index);
+        }
+        for model_field in &model.model_fields {
+            for child in &model_field.models {
+                self.insert(child, Some(index), Some(model_field.name.as_str()), depth + 1);
+            }
+        }
+        for multi_model_field in &model.multi_model_fields {
+            for child in &multi_model_field.models {
+                self.insert(child, Some(index), Some(multi_model_field.name.as_str()), depth + 1);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn node(&self, index: usize) -> Option<&ModelNode<'a>> {
+        self.nodes.get(index)
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &ModelNode<'a>> {
+        self.nodes.iter()
+    }
+
+    /// All models at any depth, in document order.
+    pub fn models(&self) -> impl Iterator<Item = &'a Model> + '_ {
+        self.nodes.iter().map(|node| node.model)
+    }
+
+    pub fn roots(&self) -> impl Iterator<Item = &'a Model> + '_ {
+        self.nodes.iter().filter(|node| node.parent.is_none()).map(|node| node.model)
+    }
+
+    /// Indices of every node carrying `id`. Ids are unique in well-formed
+    /// reports, but the same model can be repeated under several parents.
+    pub fn indices(&self, id: &str) -> &[usize] {
+        self.by_id.get(id).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn get(&self, id: &str) -> Option<&'a Model> {
+        self.indices(id).first().map(|&index| self.nodes[index].model)
+    }
+
+    pub fn parent(&self, id: &str) -> Option<&'a Model> {
+        let index = *self.indices(id).first()?;
+        self.nodes[index].parent.map(|parent| self.nodes[parent].model)
+    }
+
+    pub fn children(&self, id: &str) -> Vec<&'a Model> {
+        self.indices(id)
+            .iter()
+            .flat_map(|&index| self.children[index].iter())
+            .map(|&child| self.nodes[child].model)
+            .collect()
+    }
+
+    /// Ancestors of the first occurrence of `id`, closest first.
+    pub fn ancestors(&self, id: &str) -> Vec<&'a Model> {
+        let mut ancestors = Vec::new();
+        if let Some(&index) = self.indices(id).first() {
+            let mut current = self.nodes[index].parent;
+            while let Some(parent) = current {
+                ancestors.push(self.nodes[parent].model);
+                current = self.nodes[parent].parent;
+            }
+        }
+        ancestors
+    }
+
+    /// Models of type `dtype` containing `id` at any depth, e.g. the chats a
+    /// party takes part in. Each referrer is returned once.
+    pub fn referrers(&self, id: &str, dtype: &str) -> Vec<&'a Model> {
+        let mut referrers: Vec<&'a Model> = Vec::new();
+        for &index in self.indices(id) {
+            let mut current = self.nodes[index].parent;
+            while let Some(parent) = current {
+                let model = self.nodes[parent].model;
+                if model.dtype == dtype && !referrers.iter().any(|r| r.id == model.id) {
+                    referrers.push(model);
+                }
+                current = self.nodes[parent].parent;
+            }
+        }
+        referrers
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use quick_xml::{events::Event, Reader};
+    use super::*;
+
+    fn parse_decoded_data(xml_str: &str) -> Result<DecodedData, String> {
+        let mut reader = Reader::from_reader(Cursor::new(xml_str));
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(e)) if e.name().as_ref() == b"decodedData" => {
+                    return DecodedData::parse_one(&mut reader).map_err(|e| e.to_string());
+                },
+                Ok(Event::Eof) => {
+                    return Err("eof".to_string());
+                }
+                _ => (),
+            }
+            buf.clear();
+        }
+    }
+
+    const XML_STR: &str = r#"
+    <decodedData>
+        <modelType type="Chat">
+            <model type="Chat" id="chat-0" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                <multiModelField name="Participants" type="Party">
+                    <model type="Party" id="party-0" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                    </model>
+                </multiModelField>
+                <multiModelField name="Messages" type="InstantMessage">
+                    <model type="InstantMessage" id="message-0" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                        <modelField name="From" type="Party">
+                            <model type="Party" id="party-1" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                            </model>
+                        </modelField>
+                    </model>
+                </multiModelField>
+            </model>
+        </modelType>
+        <modelType type="Contact">
+            <model type="Contact" id="contact-0" deleted_state="Deleted" decoding_confidence="High" isrelated="False" extractionId="0">
+            </model>
+        </modelType>
+    </decodedData>
+    "#;
+
+    #[test]
+    fn test_model_graph_0() -> Result<(), String> {
+        let decoded_data = parse_decoded_data(XML_STR)?;
+        let graph = ModelGraph::new(&decoded_data);
+        let ids: Vec<&str> = graph.models().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["chat-0", "party-0", "message-0", "party-1", "contact-0"]);
+        let roots: Vec<&str> = graph.roots().map(|m| m.id.as_str()).collect();
+        assert_eq!(roots, vec!["chat-0", "contact-0"]);
+        assert_eq!(graph.node(graph.indices("party-1")[0]).map(|n| (n.field_name, n.depth)), Some((Some("From"), 2)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_model_graph_1() -> Result<(), String> {
+        let decoded_data = parse_decoded_data(XML_STR)?;
+        let graph = ModelGraph::new(&decoded_data);
+        assert_eq!(graph.parent("party-1").map(|m| m.id.as_str()), Some("message-0"));
+        assert_eq!(graph.parent("chat-0"), None);
+        let ancestors: Vec<&str> = graph.ancestors("party-1").iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ancestors, vec!["message-0", "chat-0"]);
+        let children: Vec<&str> = graph.children("chat-0").iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(children, vec!["party-0", "message-0"]);
+        let chats: Vec<&str> = graph.referrers("party-1", "Chat").iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(chats, vec!["chat-0"]);
+        assert!(graph.referrers("contact-0", "Chat").is_empty());
+        assert!(graph.get("missing").is_none());
+        Ok(())
+    }
+}
@@ -1,13 +1,18 @@
-use std::{io::BufRead};
-use quick_xml::{events::{BytesStart, Event}, Reader};
-use super::{attributes_to_map, ParseError, Model};
+use std::io::{BufRead, Write};
+use quick_xml::{events::{BytesEnd, BytesStart, Event}, Reader, Writer};
+use super::{attribute_pairs, attributes_to_map, other_attributes, ParseError, Model};
 
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ModelType {
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
     pub dtype: String,
     pub models: Vec<Model>,
+    /// Attributes other than the ones above, in document order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub other_attributes: Vec<(String, String)>,
 }
 
 impl ModelType {
@@ -49,15 +54,25 @@ impl ModelType {
         Ok(ModelType {
             dtype: map.get("type").cloned().ok_or("missing type")?,
             models,
+            other_attributes: other_attributes(e, &["type"])?,
         })
     }
+
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        writer.write_event(Event::Start(BytesStart::new("modelType").with_attributes([("type", self.dtype.as_str())]).with_attributes(attribute_pairs(&self.other_attributes))))?;
+        for model in &self.models {
+            model.write_one(writer)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("modelType")))?;
+        Ok(())
+    }
 }
 
 
 #[cfg(test)]
 mod test {
     use std::io::Cursor;
-    use crate::models::{Field, Value};
+    use crate::models::{Field, ModelChild, Value};
     use super::*;
 
     fn test_model_type(xml_str: &str, expected: ModelType) -> Result<(), String> {
@@ -109,6 +124,8 @@ mod test {
                 decoding_confidence: "High".to_string(),
                 is_related: "False".to_string(),
                 extraction_id: 1,
+                children: vec![ModelChild::Field("UserMapping".to_string())],
+                other_attributes: vec![],
                 multi_model_fields: vec![],
                 model_fields: vec![],
                 multi_fields: vec![],
@@ -124,9 +141,12 @@ mod test {
                     value: Some(Value {
                         dtype: "Boolean".to_string(),
                         text: "False".to_string(),
-                    })
+                        other_attributes: vec![],
+                    }),
+                    other_attributes: vec![],
                 }]
-            }]
+            }],
+            other_attributes: vec![],
         })
     }
 }
\ No newline at end of file
@@ -1,20 +1,30 @@
-use std::io::BufRead;
-use quick_xml::{events::{BytesStart, Event}, Reader};
-use super::{attributes_to_map, ParseError};
+use std::io::{BufRead, Write};
+use quick_xml::{events::{BytesEnd, BytesStart, Event}, Reader, Writer};
+use super::{attribute_pairs, attributes_to_map, other_attributes, ParseError, Value};
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MultiField {
     pub name: String,
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
     pub dtype: String,
+    pub values: Vec<Value>,
+    /// Attributes other than the ones above, in document order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub other_attributes: Vec<(String, String)>,
 }
 
 impl MultiField {
     pub fn parse_one<B: BufRead>(e: &BytesStart, reader: &mut Reader<B>) -> Result<Self, Box<dyn std::error::Error>> {
         let map = attributes_to_map(e)?;
         let mut buf = Vec::new();
+        let mut values = Vec::new();
         loop {
             match reader.read_event_into(&mut buf)? {
+                Event::Start(e) if e.name().as_ref() == b"value" => {
+                    values.push(Value::parse_one(&e, reader)?);
+                }
                 Event::Empty(e) if e.name().as_ref() == b"empty" => {}
                 Event::End(e) if e.name().as_ref() == b"multiField" => break,
                 Event::Eof => {
@@ -43,6 +53,8 @@ impl MultiField {
         Ok(MultiField {
             name: map.get("name").cloned().ok_or("missing name")?,
             dtype: map.get("type").cloned().ok_or("missing type")?,
+            values,
+            other_attributes: other_attributes(e, &["name", "type"])?,
         })
     }
 
@@ -51,8 +63,27 @@ impl MultiField {
         Ok(MultiField {
             name: map.get("name").cloned().ok_or("missing name")?,
             dtype: map.get("type").cloned().ok_or("missing type")?,
+            values: vec![],
+            other_attributes: other_attributes(e, &["name", "type"])?,
         })
     }
+
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        let start = BytesStart::new("multiField").with_attributes([
+            ("name", self.name.as_str()),
+            ("type", self.dtype.as_str()),
+        ]).with_attributes(attribute_pairs(&self.other_attributes));
+        if self.values.is_empty() {
+            writer.write_event(Event::Empty(start))?;
+            return Ok(());
+        }
+        writer.write_event(Event::Start(start))?;
+        for value in &self.values {
+            value.write_one(writer)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("multiField")))?;
+        Ok(())
+    }
 }
 
 
@@ -114,7 +145,9 @@ mod test {
         "#;
         test_multi_field(xml_str, MultiField {
             name: "IPAddresses".to_string(),
-            dtype: "String".to_string()
+            dtype: "String".to_string(),
+            values: vec![],
+            other_attributes: vec![],
         })
     }
 
@@ -127,7 +160,28 @@ mod test {
         "#;
         test_multi_field(xml_str, MultiField {
             name: "Notes".to_string(),
-            dtype: "String".to_string()
+            dtype: "String".to_string(),
+            values: vec![],
+            other_attributes: vec![],
         })
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_multi_field_2() -> Result<(), String> {
+        let xml_str = r#"
+        <multiField name="IPAddresses" type="String">
+            <value type="String"><![CDATA[10.0.0.1]]></value>
+            <value type="String"><![CDATA[10.0.0.2]]></value>
+        </multiField>
+        "#;
+        test_multi_field(xml_str, MultiField {
+            name: "IPAddresses".to_string(),
+            dtype: "String".to_string(),
+            values: vec![
+                Value { dtype: "String".to_string(), text: "10.0.0.1".to_string(), other_attributes: vec![] },
+                Value { dtype: "String".to_string(), text: "10.0.0.2".to_string(), other_attributes: vec![] },
+            ],
+            other_attributes: vec![],
+        })
+    }
+}
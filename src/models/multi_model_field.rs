@@ -1,14 +1,19 @@
-use std::io::BufRead;
-use quick_xml::{events::{BytesStart, Event}, Reader};
-use super::{attributes_to_map, ParseError, Model};
+use std::io::{BufRead, Write};
+use quick_xml::{events::{BytesEnd, BytesStart, Event}, Reader, Writer};
+use super::{attribute_pairs, attributes_to_map, other_attributes, ParseError, Model};
 
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MultiModelField {
     pub name: String,
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
     pub dtype: String,
-    pub models: Vec<Model>
+    pub models: Vec<Model>,
+    /// Attributes other than the ones above, in document order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub other_attributes: Vec<(String, String)>,
 }
 
 impl MultiModelField {
@@ -50,6 +55,7 @@ impl MultiModelField {
             name: map.get("name").cloned().ok_or("missing name")?,
             dtype: map.get("type").cloned().ok_or("missing type")?,
             models,
+            other_attributes: other_attributes(e, &["name", "type"])?,
         })
     }
 
@@ -59,6 +65,24 @@ impl MultiModelField {
             name: map.get("name").cloned().ok_or("missing name")?,
             dtype: map.get("type").cloned().ok_or("missing type")?,
             models: vec![],
+            other_attributes: other_attributes(e, &["name", "type"])?,
         })
     }
+
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        let start = BytesStart::new("multiModelField").with_attributes([
+            ("name", self.name.as_str()),
+            ("type", self.dtype.as_str()),
+        ]).with_attributes(attribute_pairs(&self.other_attributes));
+        if self.models.is_empty() {
+            writer.write_event(Event::Empty(start))?;
+            return Ok(());
+        }
+        writer.write_event(Event::Start(start))?;
+        for model in &self.models {
+            model.write_one(writer)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("multiModelField")))?;
+        Ok(())
+    }
 }
\ No newline at end of file
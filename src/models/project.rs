@@ -1,10 +1,11 @@
-use std::io::BufRead;
-use quick_xml::{events::{BytesStart, Event}, Reader};
-use super::{attributes_to_map, ParseError, CaseInformation, DecodedData, Images, Metadata, SourceExtractions, TaggedFiles};
+use std::io::{BufRead, Write};
+use quick_xml::{events::{BytesDecl, BytesEnd, BytesStart, Event}, Reader, Writer};
+use super::{attribute_pairs, attributes_to_map, other_attributes, ParseError, CaseInformation, DanglingExtractionRef, DecodedData, DeviceInfo, ExtractionView, Images, Item, Metadata, Model, ModelGraph, SourceExtractions, TaggedFiles};
 
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Project {
     pub id: String,
     pub name: String,
@@ -21,8 +22,24 @@ pub struct Project {
     pub images: Option<Images>,
     pub tagged_files: Option<TaggedFiles>,
     pub decoded_data: Option<DecodedData>,
+    /// Attributes other than the ones above, e.g. further namespace
+    /// declarations, in document order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub other_attributes: Vec<(String, String)>,
 }
 
+const PROJECT_ATTRIBUTES: [&str; 9] = [
+    "id",
+    "name",
+    "reportVersion",
+    "licenseID",
+    "containsGarbage",
+    "extractionType",
+    "NodeCount",
+    "ModelCount",
+    "xmlns",
+];
+
 impl Project {
     pub fn parse_one<B: BufRead>(
         e: &BytesStart,
@@ -96,8 +113,125 @@ impl Project {
             images,
             tagged_files,
             decoded_data,
+            other_attributes: other_attributes(e, &PROJECT_ATTRIBUTES)?,
         })
     }
+
+    /// `NodeCount` and `ModelCount` are recomputed from `decoded_data`, so
+    /// they stay right for a project whose models were filtered or added.
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        let models = || self.decoded_data.iter().flat_map(|decoded_data| &decoded_data.model_types).flat_map(|model_type| &model_type.models);
+        let node_count = models().map(node_count).sum::<usize>().to_string();
+        let model_count = models().count().to_string();
+        let attributes = [
+            ("id", self.id.as_str()),
+            ("name", self.name.as_str()),
+            ("reportVersion", self.report_version.as_str()),
+            ("licenseID", self.license_id.as_str()),
+            ("containsGarbage", self.contains_garbage.as_str()),
+            ("extractionType", self.extraction_type.as_str()),
+            ("NodeCount", node_count.as_str()),
+            ("ModelCount", model_count.as_str()),
+            ("xmlns", self.xmlns.as_str()),
+        ];
+        writer.write_event(Event::Start(BytesStart::new("project").with_attributes(attributes).with_attributes(attribute_pairs(&self.other_attributes))))?;
+        self.source_extractions.write_one(writer)?;
+        self.case_information.write_one(writer)?;
+        for metadata in &self.metadata {
+            metadata.write_one(writer)?;
+        }
+        if let Some(images) = &self.images {
+            images.write_one(writer)?;
+        }
+        if let Some(tagged_files) = &self.tagged_files {
+            tagged_files.write_one(writer)?;
+        }
+        if let Some(decoded_data) = &self.decoded_data {
+            decoded_data.write_one(writer)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("project")))?;
+        Ok(())
+    }
+
+    /// Writes a complete, indented `report.xml`. The children of a model keep
+    /// the order they were parsed in, see [`Model::write_one`].
+    pub fn write_report<W: Write>(&self, inner: W) -> Result<(), Box<dyn std::error::Error>> {
+        let mut writer = Writer::new_with_indent(inner, b' ', 2);
+        writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("utf-8"), None)))?;
+        self.write_one(&mut writer)?;
+        writer.get_mut().write_all(b"\n")?;
+        Ok(())
+    }
+
+    pub fn model_graph(&self) -> ModelGraph<'_> {
+        ModelGraph::from_project(self)
+    }
+
+    fn device_info_items(&self) -> impl Iterator<Item = &Item> {
+        self.metadata
+            .iter()
+            .filter(|metadata| metadata.section == "Device Info" || metadata.section == "Extraction Data")
+            .flat_map(|metadata| metadata.items.iter())
+    }
+
+    /// Device info gathered from the "Device Info" and "Extraction Data"
+    /// metadata sections, or `None` if no item is keyed to `extraction_id`.
+    pub fn device_info(&self, extraction_id: u64) -> Option<DeviceInfo> {
+        let keyed = self
+            .device_info_items()
+            .any(|item| item.source_extraction.as_deref().and_then(|id| id.parse().ok()) == Some(extraction_id));
+        if keyed {
+            Some(DeviceInfo::from_items(extraction_id, self.device_info_items()))
+        } else {
+            None
+        }
+    }
+
+    pub fn device_infos(&self) -> Vec<DeviceInfo> {
+        self.source_extractions
+            .infos
+            .iter()
+            .filter_map(|info| self.device_info(info.id))
+            .collect()
+    }
+
+    pub fn extraction_view(&self, extraction_id: u64) -> Option<ExtractionView<'_>> {
+        let info = self.source_extractions.get(extraction_id)?;
+        Some(ExtractionView::new(self, info))
+    }
+
+    pub fn extraction_views(&self) -> Vec<ExtractionView<'_>> {
+        self.source_extractions
+            .infos
+            .iter()
+            .map(|info| ExtractionView::new(self, info))
+            .collect()
+    }
+
+    /// Models, files, images and metadata items whose extraction id does not
+    /// match any `extractionInfo`.
+    pub fn dangling_extraction_refs(&self) -> Vec<DanglingExtractionRef> {
+        DanglingExtractionRef::collect(self)
+    }
+
+    pub fn validate_extraction_refs(&self) -> Result<(), ParseError> {
+        let dangling = self.dangling_extraction_refs();
+        match dangling.first() {
+            None => Ok(()),
+            Some(first) => Err(ParseError::new(&format!(
+                "{} reference(s) to unknown extractions, first: {}",
+                dangling.len(),
+                first
+            ))),
+        }
+    }
+}
+
+/// `model` and every model nested under it.
+fn node_count(model: &Model) -> usize {
+    let nested = model.model_fields.iter().flat_map(|model_field| &model_field.models);
+    let multi_nested = model.multi_model_fields.iter().flat_map(|multi_model_field| &multi_model_field.models);
+    1 + nested.chain(multi_nested).map(node_count).sum::<usize>()
 }
 
 #[cfg(test)]
@@ -129,4 +263,133 @@ mod test {
         }
         Ok(())
     }
-}
\ No newline at end of file
+
+    fn parse_project(xml: &[u8]) -> Result<Project, Box<dyn std::error::Error>> {
+        let mut reader = Reader::from_reader(xml);
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf)? {
+                Event::Start(ref e) if e.name().as_ref() == b"project" => return Project::parse_one(e, &mut reader),
+                Event::Eof => return Err("eof".into()),
+                _ => {}
+            }
+            buf.clear();
+        }
+    }
+
+    #[test]
+    fn test_project_write_0() -> Result<(), Box<dyn std::error::Error>> {
+        let xml_str = r#"<?xml version="1.0" encoding="utf-8"?>
+        <project id="00000000-0000-0000-0000-000000000000" name="Test &amp; Co" reportVersion="7.0.0.0" licenseID="0" containsGarbage="False" extractionType="Logical" NodeCount="2" ModelCount="1" xmlns="http://pa.cellebrite.com/report/2.0">
+            <sourceExtractions>
+                <extractionInfo id="0" name="Logical" isCustomName="False" type="Logical" deviceName="Report" fullName="Cellebrite UFED Reports" index="0" IsPartialData="False" />
+            </sourceExtractions>
+            <caseInformation>
+                <field name="Case Number" isSystem="True" isRequired="False" fieldType="CaseNumber" multipleLines="False"><![CDATA[2024-001]]></field>
+                <field name="Notes" isSystem="False" isRequired="False" fieldType="None" multipleLines="True"></field>
+            </caseInformation>
+            <metadata section="Additional Fields">
+                <item id="a" name="UFED_PA_Version" sourceExtraction="0"><![CDATA[1.2.3.4]]></item>
+            </metadata>
+            <images>
+                <image key="FileDump" path="iPhoneBackup.tar" size="12345678" type="File" verify="NoSourceValues" extractionId="0" />
+            </images>
+            <taggedFiles>
+                <file fs="iPhone" fsid="fsid" path="/a &lt;b&gt;.jpg" size="1" id="file-0" extractionId="0" deleted="Intact" embedded="false" isrelated="False">
+                    <accessInfo>
+                        <timestamp name="CreationTime">2020-07-01T07:45:53.000+00:00</timestamp>
+                    </accessInfo>
+                    <metadata section="File">
+                        <item name="Local Path"><![CDATA[files\Image\a.jpg]]></item>
+                    </metadata>
+                </file>
+            </taggedFiles>
+            <decodedData>
+                <modelType type="Chat">
+                    <model type="Chat" id="chat-0" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                        <field name="Source" type="String"><value type="String"><![CDATA[WhatsApp]]></value></field>
+                        <field name="Id" type="String"><empty /></field>
+                        <multiField name="Participants" type="String" />
+                        <modelField name="Photo" type="Attachment"><empty /></modelField>
+                        <multiModelField name="Messages" type="InstantMessage">
+                            <model type="InstantMessage" id="message-0" deleted_state="Deleted" decoding_confidence="High" isrelated="False" extractionId="0">
+                                <field name="Body" type="String"><value type="String">a ]]&gt; b</value></field>
+                                <multiField name="Tags" type="String"><value type="String"><![CDATA[work]]></value></multiField>
+                                <multiModelField name="To" type="Party" />
+                                <dataField name="Data" type="Data"><source length="3" /></dataField>
+                            </model>
+                        </multiModelField>
+                    </model>
+                </modelType>
+            </decodedData>
+        </project>
+        "#;
+        let project = parse_project(xml_str.as_bytes())?;
+        let mut out = Vec::new();
+        project.write_report(&mut out)?;
+        assert_eq!(parse_project(&out)?, project);
+        let out = String::from_utf8(out)?;
+        assert!(out.starts_with("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<project "));
+        assert!(out.contains(r#"xmlns="http://pa.cellebrite.com/report/2.0""#));
+        assert!(out.contains(r#"<value type="String"><![CDATA[a ]]]]><![CDATA[> b]]></value>"#));
+        Ok(())
+    }
+
+    #[test]
+    fn test_project_write_1() -> Result<(), Box<dyn std::error::Error>> {
+        let xml_str = r#"<?xml version="1.0" encoding="utf-8"?>
+<project id="p" name="Test" reportVersion="7.0.0.0" licenseID="0" containsGarbage="False" extractionType="Logical" NodeCount="3" ModelCount="1" xmlns="http://pa.cellebrite.com/report/2.0" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <sourceExtractions>
+    <extractionInfo id="0" name="Logical" isCustomName="False" type="Logical" deviceName="Report" fullName="Cellebrite UFED Reports" index="0" IsPartialData="False" IsEncrypted="False"/>
+  </sourceExtractions>
+  <caseInformation>
+    <field name="Case Number" isSystem="True" isRequired="False" fieldType="CaseNumber" multipleLines="False" isReadOnly="True"><![CDATA[2024-001]]></field>
+  </caseInformation>
+  <decodedData>
+    <modelType type="InstantMessage">
+      <model type="InstantMessage" id="message-0" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0" sourceIndex="7">
+        <field name="Source" type="String">
+          <value type="String"><![CDATA[WhatsApp]]></value>
+        </field>
+        <modelField name="From" type="Party">
+          <model type="Party" id="party-0" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+            <field name="Identifier" type="String">
+              <value type="String"><![CDATA[+43123]]></value>
+            </field>
+          </model>
+        </modelField>
+        <field name="Body" type="String" sourceIndex="8">
+          <value type="String" format="Text"><![CDATA[hi]]></value>
+        </field>
+        <multiModelField name="To" type="Party">
+          <model type="Party" id="party-1" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+            <field name="Identifier" type="String">
+              <value type="String"><![CDATA[+43456]]></value>
+            </field>
+          </model>
+        </multiModelField>
+        <field name="TimeStamp" type="TimeStamp">
+          <value type="TimeStamp"><![CDATA[2020-07-01T07:45:53.000+00:00]]></value>
+        </field>
+      </model>
+    </modelType>
+  </decodedData>
+</project>
+"#;
+        let mut project = parse_project(xml_str.as_bytes())?;
+        let mut out = Vec::new();
+        project.write_report(&mut out)?;
+        assert_eq!(String::from_utf8(out)?, xml_str);
+
+        let model = &mut project.decoded_data.as_mut().ok_or("missing decodedData")?.model_types[0].models[0];
+        model.fields.remove(0);
+        model.model_fields.clear();
+        let mut out = Vec::new();
+        project.write_report(&mut out)?;
+        let source = xml_str.find(r#"        <field name="Source""#).ok_or("missing Source")?;
+        let body = xml_str.find(r#"        <field name="Body""#).ok_or("missing Body")?;
+        let expected = format!("{}{}", &xml_str[..source], &xml_str[body..]).replace(r#"NodeCount="3""#, r#"NodeCount="2""#);
+        assert_eq!(String::from_utf8(out)?, expected);
+        Ok(())
+    }
+}
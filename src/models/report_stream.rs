@@ -0,0 +1,167 @@
+use std::{collections::HashMap, fs, io::{BufRead, BufReader}, path::Path};
+use quick_xml::{events::Event, Reader};
+use super::{attributes_to_map, CaseInformation, File, Image, Metadata, Model, SourceExtractions};
+
+
+
+#[derive(Debug, PartialEq)]
+pub enum ReportItem {
+    SourceExtractions(SourceExtractions),
+    CaseInformation(CaseInformation),
+    Metadata(Metadata),
+    Image(Image),
+    File(File),
+    Model(Model),
+}
+
+
+/// Pull parser yielding the top-level elements of a report one at a time,
+/// so that reports larger than memory can be processed. Models are yielded
+/// individually with their nested models, in document order.
+pub struct ReportStream<B: BufRead> {
+    reader: Reader<B>,
+    buf: Vec<u8>,
+    attributes: HashMap<String, String>,
+    done: bool,
+}
+
+impl ReportStream<BufReader<fs::File>> {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let file = fs::File::open(path)?;
+        Ok(ReportStream::new(Reader::from_reader(BufReader::new(file))))
+    }
+}
+
+impl<B: BufRead> ReportStream<B> {
+    pub fn new(reader: Reader<B>) -> Self {
+        ReportStream {
+            reader,
+            buf: Vec::new(),
+            attributes: HashMap::new(),
+            done: false,
+        }
+    }
+
+    /// Attributes of the `<project>` element, empty until it has been read.
+    pub fn project_attributes(&self) -> &HashMap<String, String> {
+        &self.attributes
+    }
+
+    fn next_item(&mut self) -> Result<Option<ReportItem>, Box<dyn std::error::Error>> {
+        loop {
+            self.buf.clear();
+            let item = match self.reader.read_event_into(&mut self.buf)? {
+                Event::Start(e) => match e.name().as_ref() {
+                    b"project" => {
+                        self.attributes = attributes_to_map(&e)?;
+                        None
+                    }
+                    b"sourceExtractions" => Some(ReportItem::SourceExtractions(SourceExtractions::parse_one(&mut self.reader)?)),
+                    b"caseInformation" => Some(ReportItem::CaseInformation(CaseInformation::parse_one(&mut self.reader)?)),
+                    b"metadata" => Some(ReportItem::Metadata(Metadata::parse_one(&e, &mut self.reader)?)),
+                    b"file" => Some(ReportItem::File(File::parse_one(&e, &mut self.reader)?)),
+                    b"model" => Some(ReportItem::Model(Model::parse_one(&e, &mut self.reader)?)),
+                    _ => None,
+                },
+                Event::Empty(e) if e.name().as_ref() == b"image" => Some(ReportItem::Image(Image::parse_one(&e)?)),
+                Event::End(e) if e.name().as_ref() == b"project" => return Ok(None),
+                Event::Eof => return Ok(None),
+                _ => None,
+            };
+            if item.is_some() {
+                return Ok(item);
+            }
+        }
+    }
+}
+
+impl<B: BufRead> Iterator for ReportStream<B> {
+    type Item = Result<ReportItem, Box<dyn std::error::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.next_item() {
+            Ok(Some(item)) => Some(Ok(item)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use super::*;
+
+    #[test]
+    fn test_report_stream_0() -> Result<(), Box<dyn std::error::Error>> {
+        let xml_str = r#"<?xml version="1.0" encoding="utf-8"?>
+        <project id="00000000-0000-0000-0000-000000000000" name="Test" reportVersion="7.0.0.0" licenseID="0" containsGarbage="False" extractionType="Logical" NodeCount="3" ModelCount="2" xmlns="http://pa.cellebrite.com/report/2.0">
+            <sourceExtractions>
+                <extractionInfo id="0" name="Logical" isCustomName="False" type="Logical" deviceName="Report" fullName="Cellebrite UFED Reports" index="0" IsPartialData="False" />
+            </sourceExtractions>
+            <caseInformation>
+            </caseInformation>
+            <metadata section="Additional Fields">
+                <item name="UFED_PA_Version"><![CDATA[1.2.3.4]]></item>
+            </metadata>
+            <images>
+                <image key="FileDump" path="iPhoneBackup.tar" size="12345678" type="File" verify="NoSourceValues" extractionId="0" />
+            </images>
+            <taggedFiles>
+                <file fs="iPhone" fsid="fsid" path="/a.jpg" size="1" id="file-0" extractionId="0" deleted="Intact" embedded="false" isrelated="False">
+                    <metadata section="File">
+                        <item name="Local Path"><![CDATA[files\Image\a.jpg]]></item>
+                    </metadata>
+                </file>
+            </taggedFiles>
+            <decodedData>
+                <modelType type="Chat">
+                    <model type="Chat" id="chat-0" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                        <multiModelField name="Messages" type="InstantMessage">
+                            <model type="InstantMessage" id="message-0" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                            </model>
+                        </multiModelField>
+                    </model>
+                </modelType>
+                <modelType type="Contact">
+                    <model type="Contact" id="contact-0" deleted_state="Intact" decoding_confidence="High" isrelated="False" extractionId="0">
+                    </model>
+                </modelType>
+            </decodedData>
+        </project>
+        "#;
+        let mut stream = ReportStream::new(Reader::from_reader(Cursor::new(xml_str)));
+        let mut kinds = Vec::new();
+        for item in &mut stream {
+            kinds.push(match item? {
+                ReportItem::SourceExtractions(_) => "sourceExtractions".to_string(),
+                ReportItem::CaseInformation(_) => "caseInformation".to_string(),
+                ReportItem::Metadata(m) => format!("metadata:{}", m.section),
+                ReportItem::Image(i) => format!("image:{}", i.key),
+                ReportItem::File(f) => format!("file:{}:{}", f.id, f.metadata.len()),
+                ReportItem::Model(m) => format!("model:{}:{}", m.id, m.multi_model_fields.len()),
+            });
+        }
+        assert_eq!(kinds, vec![
+            "sourceExtractions",
+            "caseInformation",
+            "metadata:Additional Fields",
+            "image:FileDump",
+            "file:file-0:1",
+            "model:chat-0:1",
+            "model:contact-0:0",
+        ]);
+        assert_eq!(stream.project_attributes().get("name").map(String::as_str), Some("Test"));
+        Ok(())
+    }
+}
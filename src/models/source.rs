@@ -1,17 +1,31 @@
-use quick_xml::events::BytesStart;
-use super::attributes_to_map;
+use std::io::Write;
+use quick_xml::{events::{BytesStart, Event}, Writer};
+use super::{attribute_pairs, attributes_to_map, other_attributes};
 
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Source {
     pub length: u64,
+    /// Attributes other than the ones above, in document order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub other_attributes: Vec<(String, String)>,
 }
 
 impl Source {
     pub fn parse_one(e: &BytesStart) -> Result<Self, Box<dyn std::error::Error>> {
         let map = attributes_to_map(e)?;
-        Ok(Source { length: map.get("length").cloned().ok_or("missing length")?.parse()? })
+        Ok(Source {
+            length: map.get("length").cloned().ok_or("missing length")?.parse()?,
+            other_attributes: other_attributes(e, &["length"])?,
+        })
+    }
+
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        let length = self.length.to_string();
+        writer.write_event(Event::Empty(BytesStart::new("source").with_attributes([("length", length.as_str())]).with_attributes(attribute_pairs(&self.other_attributes))))?;
+        Ok(())
     }
 }
 
@@ -30,7 +44,7 @@ mod test {
                 Ok(Event::Empty(e)) if e.name().as_ref() == b"source" => {
                     let uwu = Source::parse_one(&e);
                     if let Ok(source) = uwu {
-                        let known_keys = vec![
+                        let known_keys = [
                             "length",
                         ];
                         for key in attributes_to_map(&e).unwrap().keys() {
@@ -56,6 +70,6 @@ mod test {
         let xml_str = r#"
         <source length="11159817" />
         "#;
-        test_source(xml_str, Source { length: 11159817 })
+        test_source(xml_str, Source { length: 11159817, other_attributes: vec![] })
     }
 }
\ No newline at end of file
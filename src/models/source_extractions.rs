@@ -1,10 +1,11 @@
-use std::{io::BufRead};
-use quick_xml::{events::{BytesStart, Event}, Reader};
-use super::{attributes_to_map, ParseError};
+use std::io::{BufRead, Write};
+use quick_xml::{events::{BytesEnd, BytesStart, Event}, Reader, Writer};
+use super::{attribute_pairs, attributes_to_map, other_attributes, ParseError};
 
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SourceExtractions {
     pub infos: Vec<ExtractionInfo>
 }
@@ -46,21 +47,41 @@ impl SourceExtractions {
         }
         Ok(SourceExtractions { infos })
     }
+
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        writer.write_event(Event::Start(BytesStart::new("sourceExtractions")))?;
+        for info in &self.infos {
+            info.write_one(writer)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("sourceExtractions")))?;
+        Ok(())
+    }
+
+    pub fn get(&self, id: u64) -> Option<&ExtractionInfo> {
+        self.infos.iter().find(|info| info.id == id)
+    }
 }
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExtractionInfo {
-    pub id: u32,
+    pub id: u64,
     pub name: String,
     pub is_custom_name: String,
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
     pub dtype: String,
     pub device_name: String,
     pub full_name: String,
     pub index: u32,
     pub is_partial_data: String,
+    /// Attributes other than the ones above, in document order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub other_attributes: Vec<(String, String)>,
 }
 
+const EXTRACTION_INFO_ATTRIBUTES: [&str; 8] = ["id", "name", "isCustomName", "type", "deviceName", "fullName", "index", "IsPartialData"];
+
 impl ExtractionInfo {
     pub fn parse_one(e: &BytesStart) -> Result<Self, Box<dyn std::error::Error>> {
         let map = attributes_to_map(e)?;
@@ -73,8 +94,25 @@ impl ExtractionInfo {
             full_name: map.get("fullName").cloned().ok_or("missing fullName")?,
             index: map.get("index").ok_or("missing index")?.parse()?,
             is_partial_data: map.get("IsPartialData").cloned().ok_or("missing IsPartialData")?,
+            other_attributes: other_attributes(e, &EXTRACTION_INFO_ATTRIBUTES)?,
         })
     }
+
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        let id = self.id.to_string();
+        let index = self.index.to_string();
+        writer.write_event(Event::Empty(BytesStart::new("extractionInfo").with_attributes([
+            ("id", id.as_str()),
+            ("name", self.name.as_str()),
+            ("isCustomName", self.is_custom_name.as_str()),
+            ("type", self.dtype.as_str()),
+            ("deviceName", self.device_name.as_str()),
+            ("fullName", self.full_name.as_str()),
+            ("index", index.as_str()),
+            ("IsPartialData", self.is_partial_data.as_str()),
+        ]).with_attributes(attribute_pairs(&self.other_attributes))))?;
+        Ok(())
+    }
 }
 
 
@@ -103,6 +141,7 @@ mod test {
                             full_name: "Cellebrite UFED Reports".to_string(),
                             index: 0,
                             is_partial_data: "False".to_string(),
+                            other_attributes: vec![],
                         });
                         return Ok(());
                     } else {
@@ -141,6 +180,7 @@ mod test {
                                 full_name: "Cellebrite UFED Reports".to_string(),
                                 index: 0,
                                 is_partial_data: "False".to_string(),
+                                other_attributes: vec![],
                             }]
                         });
                         return Ok(());
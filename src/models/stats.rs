@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+use chrono::{DateTime, FixedOffset};
+use super::{timeline::{file_times, model_times}, Project};
+
+
+
+/// Number of top-level models of one type, by `deleted_state`.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModelTypeStats {
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub dtype: String,
+    pub count: usize,
+    pub deleted_states: BTreeMap<String, usize>,
+}
+
+impl ModelTypeStats {
+    /// Share of models that are not `Intact`, 0 for an empty type.
+    pub fn deleted_ratio(&self) -> f64 {
+        let intact = self.deleted_states.get("Intact").copied().unwrap_or_default();
+        if self.count == 0 {
+            0.0
+        } else {
+            (self.count - intact) as f64 / self.count as f64
+        }
+    }
+}
+
+/// Models and tagged files of one source extraction. Ids no
+/// `extractionInfo` is declared for get an empty name.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtractionStats {
+    pub extraction_id: u64,
+    pub name: String,
+    pub models: usize,
+    pub files: usize,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtensionStats {
+    pub count: usize,
+    pub bytes: u64,
+}
+
+/// Counts for triaging a report without reading through it.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReportStats {
+    /// Top-level model types in report order.
+    pub model_types: Vec<ModelTypeStats>,
+    /// Top-level models by `deleted_state`.
+    pub deleted_states: BTreeMap<String, usize>,
+    /// Top-level models by `decodingConfidence`, empty when unset.
+    pub decoding_confidence: BTreeMap<String, usize>,
+    /// Declared extractions first, in report order.
+    pub extractions: Vec<ExtractionStats>,
+    pub files: usize,
+    pub file_bytes: u64,
+    /// Tagged files by lowercase extension of their path, empty for none.
+    pub file_extensions: BTreeMap<String, ExtensionStats>,
+    /// Earliest and latest model or file timestamp. Extraction start and
+    /// end are left out, they date the extraction rather than the data.
+    pub first: Option<DateTime<FixedOffset>>,
+    pub last: Option<DateTime<FixedOffset>>,
+}
+
+impl ReportStats {
+    pub fn models(&self) -> usize {
+        self.model_types.iter().map(|model_type| model_type.count).sum()
+    }
+
+    fn add_time(&mut self, time: DateTime<FixedOffset>) {
+        self.first = Some(self.first.map_or(time, |first| first.min(time)));
+        self.last = Some(self.last.map_or(time, |last| last.max(time)));
+    }
+}
+
+impl Project {
+    pub fn stats(&self) -> ReportStats {
+        let extractions = self
+            .source_extractions
+            .infos
+            .iter()
+            .map(|info| ExtractionStats { extraction_id: info.id, name: info.name.clone(), ..Default::default() })
+            .collect();
+        let mut stats = ReportStats { extractions, ..Default::default() };
+
+        for model_type in self.decoded_data.iter().flat_map(|decoded_data| decoded_data.model_types.iter()) {
+            let position = match stats.model_types.iter().position(|stats| stats.dtype == model_type.dtype) {
+                Some(position) => position,
+                None => {
+                    stats.model_types.push(ModelTypeStats { dtype: model_type.dtype.clone(), ..Default::default() });
+                    stats.model_types.len() - 1
+                }
+            };
+            for model in &model_type.models {
+                let type_stats = &mut stats.model_types[position];
+                type_stats.count += 1;
+                *type_stats.deleted_states.entry(model.deleted_state.clone()).or_default() += 1;
+                *stats.deleted_states.entry(model.deleted_state.clone()).or_default() += 1;
+                *stats.decoding_confidence.entry(model.decoding_confidence.clone()).or_default() += 1;
+                extraction_stats(&mut stats.extractions, model.extraction_id).models += 1;
+            }
+        }
+
+        for file in self.tagged_files.iter().flat_map(|tagged_files| tagged_files.files.iter()) {
+            stats.files += 1;
+            stats.file_bytes += file.size;
+            let name = file.path.rsplit(['/', '\\']).next().unwrap_or_default();
+            let extension = match name.rsplit_once('.') {
+                Some((stem, extension)) if !stem.is_empty() => extension.to_lowercase(),
+                _ => String::new(),
+            };
+            let extension_stats = stats.file_extensions.entry(extension).or_default();
+            extension_stats.count += 1;
+            extension_stats.bytes += file.size;
+            extraction_stats(&mut stats.extractions, file.extraction_id).files += 1;
+            for (_, time) in file_times(file) {
+                stats.add_time(time);
+            }
+        }
+
+        for model in self.model_graph().models() {
+            for (_, time) in model_times(model) {
+                stats.add_time(time);
+            }
+        }
+        stats
+    }
+}
+
+fn extraction_stats(extractions: &mut Vec<ExtractionStats>, extraction_id: u64) -> &mut ExtractionStats {
+    let position = match extractions.iter().position(|stats| stats.extraction_id == extraction_id) {
+        Some(position) => position,
+        None => {
+            extractions.push(ExtractionStats { extraction_id, ..Default::default() });
+            extractions.len() - 1
+        }
+    };
+    &mut extractions[position]
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::models::{FileBuilder, ModelBuilder, ProjectBuilder};
+    use super::*;
+
+    #[test]
+    fn test_stats_0() {
+        let file = |id: &str, path: &str, size: u64, extraction_id: u64| {
+            FileBuilder::new(path).id(id).size(size).extraction_id(extraction_id).timestamp("CreationTime", "2019-12-31T23:00:00.000+00:00")
+        };
+        let project = ProjectBuilder::new("Test")
+            .extraction("Logical", "Logical", "iPhone")
+            .metadata_item("Extraction Data", "DeviceInfoExtractionStartDateTime", "2021-01-01T00:00:00+00:00", Some(0))
+            .file(file("file-0", "/DCIM/IMG_0001.JPG", 100, 0))
+            .file(file("file-1", "/DCIM/img_0002.jpg", 50, 0))
+            .file(file("file-2", "/var/.hidden", 7, 3))
+            .model(ModelBuilder::new("Chat").decoding_confidence("High"))
+            .model(ModelBuilder::new("Chat").deleted_state("Deleted").decoding_confidence("Low"))
+            .model(ModelBuilder::new("Chat").decoding_confidence("High").typed_field("StartTime", "TimeStamp", "2020-07-01T09:30:00.000+02:00"))
+            .model(ModelBuilder::new("Contact").deleted_state("Deleted").decoding_confidence(""))
+            .build();
+        let stats = project.stats();
+
+        assert_eq!(stats.models(), 4);
+        assert_eq!(stats.model_types[0].dtype, "Chat");
+        assert_eq!(stats.model_types[0].count, 3);
+        assert!((stats.model_types[0].deleted_ratio() - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(stats.model_types[1].deleted_ratio(), 1.0);
+        assert_eq!(stats.deleted_states, BTreeMap::from([("Deleted".to_string(), 2), ("Intact".to_string(), 2)]));
+        assert_eq!(stats.decoding_confidence, BTreeMap::from([(String::new(), 1), ("High".to_string(), 2), ("Low".to_string(), 1)]));
+        assert_eq!(stats.extractions, vec![
+            ExtractionStats { extraction_id: 0, name: "Logical".to_string(), models: 4, files: 2 },
+            ExtractionStats { extraction_id: 3, name: String::new(), models: 0, files: 1 },
+        ]);
+        assert_eq!((stats.files, stats.file_bytes), (3, 157));
+        assert_eq!(stats.file_extensions["jpg"], ExtensionStats { count: 2, bytes: 150 });
+        assert_eq!(stats.file_extensions[""], ExtensionStats { count: 1, bytes: 7 });
+        assert_eq!(stats.first.map(|time| time.to_rfc3339()).as_deref(), Some("2019-12-31T23:00:00+00:00"));
+        assert_eq!(stats.last.map(|time| time.to_rfc3339()).as_deref(), Some("2020-07-01T09:30:00+02:00"));
+    }
+}
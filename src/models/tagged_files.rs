@@ -1,10 +1,11 @@
-use std::{io::BufRead};
-use quick_xml::{Reader, events::Event};
+use std::io::{BufRead, Write};
+use quick_xml::{Reader, Writer, events::{BytesEnd, BytesStart, Event}};
 use super::{File, ParseError};
 
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TaggedFiles {
     pub files: Vec<File>
 }
@@ -46,6 +47,15 @@ impl TaggedFiles {
         }
         Ok(TaggedFiles { files })
     }
+
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        writer.write_event(Event::Start(BytesStart::new("taggedFiles")))?;
+        for file in &self.files {
+            file.write_one(writer)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("taggedFiles")))?;
+        Ok(())
+    }
 }
 
 
@@ -115,10 +125,12 @@ mod test {
                     Timestamp {
                         name: "CreationTime".to_string(),
                         text: "2020-08-08T15:50:58.000+00:00".to_string(),
+                        other_attributes: vec![],
                     },
                     Timestamp {
                         name: "ModifyTime".to_string(),
                         text: "2020-08-08T15:50:58.000+00:00".to_string(),
+                        other_attributes: vec![],
                     }
                 ] }),
                 metadata: vec![
@@ -129,10 +141,13 @@ mod test {
                             group: None,
                             id: None,
                             source_extraction: None,
-                            text: r"files\Image\4454825783_dbcb233af5_b.jpg".to_string()
-                        }]
+                            text: r"files\Image\4454825783_dbcb233af5_b.jpg".to_string(),
+                            other_attributes: vec![],
+                        }],
+                        other_attributes: vec![],
                     }
                 ],
+                other_attributes: vec![],
             }]
         })
     }
@@ -0,0 +1,174 @@
+use chrono::{DateTime, FixedOffset};
+use super::{parse_datetime, File, Model, Project, TypedValue};
+
+
+
+/// Fields tried in order for the description of a model event.
+const DESCRIPTION_FIELDS: [&str; 7] = ["Body", "Subject", "Name", "Description", "Title", "Value", "Identifier"];
+
+
+/// What a timeline event was taken from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimelineReference<'a> {
+    File(&'a File),
+    Model(&'a Model),
+    Extraction(u64),
+}
+
+impl TimelineReference<'_> {
+    /// The file or model id, or the extraction id.
+    pub fn id(&self) -> String {
+        match self {
+            TimelineReference::File(file) => file.id.clone(),
+            TimelineReference::Model(model) => model.id.clone(),
+            TimelineReference::Extraction(extraction_id) => extraction_id.to_string(),
+        }
+    }
+}
+
+
+/// One point in time, e.g. the `ModifyTime` of a tagged file or the
+/// `TimeStamp` field of a message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelineEvent<'a> {
+    pub time: DateTime<FixedOffset>,
+    /// The timestamp or field name, e.g. `CreationTime` or `StartDate`.
+    pub event_type: String,
+    /// `File`, `Device Info` or the model type.
+    pub source: String,
+    pub description: String,
+    pub reference: TimelineReference<'a>,
+}
+
+impl Project {
+    /// Every timestamp in the report: extraction start and end from the
+    /// device info, tagged file access times and `TimeStamp` values of
+    /// models at any depth. Sorted by time; events at the same instant keep
+    /// that order.
+    pub fn timeline(&self) -> Vec<TimelineEvent<'_>> {
+        let mut events = Vec::new();
+        for device_info in self.device_infos() {
+            let name = self
+                .source_extractions
+                .get(device_info.extraction_id)
+                .map(|info| info.name.as_str())
+                .unwrap_or_default();
+            for (event_type, time) in [("ExtractionStart", device_info.extraction_start), ("ExtractionEnd", device_info.extraction_end)] {
+                if let Some(time) = time {
+                    events.push(TimelineEvent {
+                        time,
+                        event_type: event_type.to_string(),
+                        source: "Device Info".to_string(),
+                        description: name.to_string(),
+                        reference: TimelineReference::Extraction(device_info.extraction_id),
+                    });
+                }
+            }
+        }
+
+        for file in self.tagged_files.iter().flat_map(|tagged_files| tagged_files.files.iter()) {
+            for (name, time) in file_times(file) {
+                events.push(TimelineEvent {
+                    time,
+                    event_type: name.to_string(),
+                    source: "File".to_string(),
+                    description: file.path.clone(),
+                    reference: TimelineReference::File(file),
+                });
+            }
+        }
+
+        for model in self.model_graph().models() {
+            for (name, time) in model_times(model) {
+                events.push(TimelineEvent {
+                    time,
+                    event_type: name.to_string(),
+                    source: model.dtype.clone(),
+                    description: model_description(model),
+                    reference: TimelineReference::Model(model),
+                });
+            }
+        }
+
+        events.sort_by_key(|event| event.time);
+        events
+    }
+}
+
+/// Parseable access timestamps of a tagged file, by name.
+pub(crate) fn file_times(file: &File) -> impl Iterator<Item = (&str, DateTime<FixedOffset>)> {
+    file.access_info
+        .iter()
+        .flat_map(|access_info| access_info.timestamps.iter())
+        .filter_map(|timestamp| Some((timestamp.name.as_str(), parse_datetime(&timestamp.text)?)))
+}
+
+/// `TimeStamp` values of a model's fields and multiFields, by field name.
+/// Nested models are not included.
+pub(crate) fn model_times(model: &Model) -> impl Iterator<Item = (&str, DateTime<FixedOffset>)> {
+    model
+        .fields
+        .iter()
+        .filter_map(|field| Some((field.name.as_str(), field.typed_value()?)))
+        .chain(
+            model
+                .multi_fields
+                .iter()
+                .flat_map(|multi_field| multi_field.values.iter().map(|value| (multi_field.name.as_str(), value.typed()))),
+        )
+        .filter_map(|(name, value)| match value {
+            TypedValue::TimeStamp(time) => Some((name, time)),
+            _ => None,
+        })
+}
+
+/// `Source: text` from the first descriptive field, e.g. `WhatsApp: hi`.
+fn model_description(model: &Model) -> String {
+    let text = DESCRIPTION_FIELDS.iter().find_map(|name| model.field_text(name));
+    match (model.field_text("Source"), text) {
+        (Some(source), Some(text)) => format!("{}: {}", source, text),
+        (Some(source), None) => source.to_string(),
+        (None, Some(text)) => text.to_string(),
+        (None, None) => String::new(),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::models::{FileBuilder, ModelBuilder, ProjectBuilder};
+
+    #[test]
+    fn test_timeline_0() {
+        let file = FileBuilder::new("/DCIM/IMG_0001.JPG")
+            .id("file-0")
+            .size(4)
+            .timestamp("CreationTime", "2020-07-01T08:00:00.000+00:00")
+            .timestamp("ModifyTime", "");
+        let project = ProjectBuilder::new("Test")
+            .extraction("Logical", "Logical", "iPhone")
+            .metadata_item("Extraction Data", "DeviceInfoExtractionStartDateTime", "2020-08-01T10:00:00+00:00", Some(0))
+            .file(file)
+            .model(
+                ModelBuilder::new("InstantMessage")
+                    .id("message-0")
+                    .field("Source", "WhatsApp")
+                    .field("Body", "hi")
+                    .typed_field("TimeStamp", "TimeStamp", "2020-07-01T09:30:00.000+02:00")
+                    .typed_field("Status", "String", "2020-07-01T09:30:00.000+02:00")
+                    .model("Position", ModelBuilder::new("Coordinate").id("coordinate-0").typed_field("TimeStamp", "TimeStamp", "2020-07-02T00:00:00+00:00")),
+            )
+            .build();
+        let timeline = project.timeline();
+        let events: Vec<(String, &str, &str, &str, String)> = timeline
+            .iter()
+            .map(|event| (event.time.to_rfc3339(), event.event_type.as_str(), event.source.as_str(), event.description.as_str(), event.reference.id()))
+            .collect();
+        assert_eq!(events, vec![
+            ("2020-07-01T09:30:00+02:00".to_string(), "TimeStamp", "InstantMessage", "WhatsApp: hi", "message-0".to_string()),
+            ("2020-07-01T08:00:00+00:00".to_string(), "CreationTime", "File", "/DCIM/IMG_0001.JPG", "file-0".to_string()),
+            ("2020-07-02T00:00:00+00:00".to_string(), "TimeStamp", "Coordinate", "", "coordinate-0".to_string()),
+            ("2020-08-01T10:00:00+00:00".to_string(), "ExtractionStart", "Device Info", "Logical", "0".to_string()),
+        ]);
+    }
+}
@@ -1,13 +1,17 @@
-use std::io::BufRead;
-use quick_xml::{events::BytesStart, Reader};
-use super::{attributes_to_map, read_text};
+use std::io::{BufRead, Write};
+use quick_xml::{events::{BytesEnd, BytesStart, Event}, Reader, Writer};
+use super::{attribute_pairs, attributes_to_map, other_attributes, read_text, write_cdata};
 
 
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Timestamp {
     pub name: String,
     pub text: String,
+    /// Attributes other than the ones above, in document order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub other_attributes: Vec<(String, String)>,
 }
 
 impl Timestamp {
@@ -17,8 +21,16 @@ impl Timestamp {
         Ok(Timestamp {
             name: map.get("name").cloned().ok_or("missing name")?,
             text,
+            other_attributes: other_attributes(e, &["name"])?,
         })
     }
+
+    pub fn write_one<W: Write>(&self, writer: &mut Writer<W>) -> Result<(), Box<dyn std::error::Error>> {
+        writer.write_event(Event::Start(BytesStart::new("timestamp").with_attributes([("name", self.name.as_str())]).with_attributes(attribute_pairs(&self.other_attributes))))?;
+        write_cdata(writer, &self.text)?;
+        writer.write_event(Event::End(BytesEnd::new("timestamp")))?;
+        Ok(())
+    }
 }
 
 
@@ -36,7 +48,7 @@ mod test {
                 Ok(Event::Start(e)) if e.name().as_ref() == b"timestamp" => {
                     let uwu = Timestamp::parse_one(&e, &mut reader);
                     if let Ok(timestamp) = uwu {
-                        let known_keys = vec![
+                        let known_keys = [
                             "name",
                         ];
                         for key in attributes_to_map(&e).unwrap().keys() {
@@ -65,6 +77,7 @@ mod test {
         test_timestamp(xml_str, Timestamp {
             name: "CreationTime".to_string(),
             text: "2020-08-08T15:50:58.000+00:00".to_string(),
+            other_attributes: vec![],
         })
     }
 
@@ -76,6 +89,7 @@ mod test {
         test_timestamp(xml_str, Timestamp {
             name: "ModifyTime".to_string(),
             text: "2020-08-08T15:50:58.000+00:00".to_string(),
+            other_attributes: vec![],
         })
     }
 }
\ No newline at end of file
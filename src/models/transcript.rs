@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use chrono::{DateTime, FixedOffset};
+use super::{parse_datetime, Model, Project};
+
+
+
+/// Identifier to display name lookup, filled from chat participants and
+/// `Contact` models. Phone numbers are compared on their digits only.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NameDirectory {
+    names: HashMap<String, String>,
+}
+
+impl NameDirectory {
+    pub fn new() -> Self {
+        NameDirectory::default()
+    }
+
+    /// Names of every `Contact` in the project, keyed by their entries.
+    pub fn from_project(project: &Project) -> Self {
+        let mut directory = NameDirectory::new();
+        for contact in project.model_graph().models().filter(|model| model.dtype == "Contact") {
+            if let Some(name) = contact.field_text("Name") {
+                for entry in contact.nested_models("Entries") {
+                    if let Some(value) = entry.field_text("Value") {
+                        directory.insert(value, name);
+                    }
+                }
+            }
+        }
+        directory
+    }
+
+    /// Keeps the first name seen for an identifier.
+    pub fn insert(&mut self, identifier: &str, name: &str) {
+        let key = normalize_identifier(identifier);
+        if !key.is_empty() && !name.is_empty() {
+            self.names.entry(key).or_insert_with(|| name.to_string());
+        }
+    }
+
+    pub fn get(&self, identifier: &str) -> Option<&str> {
+        self.names.get(&normalize_identifier(identifier)).map(String::as_str)
+    }
+}
+
+/// Names of one chat's participants layered under a shared directory, which
+/// takes precedence like an earlier [`NameDirectory::insert`] would.
+struct ChatNames<'d> {
+    directory: &'d NameDirectory,
+    participants: NameDirectory,
+}
+
+impl ChatNames<'_> {
+    fn get(&self, identifier: &str) -> Option<&str> {
+        self.directory.get(identifier).or_else(|| self.participants.get(identifier))
+    }
+}
+
+fn normalize_identifier(identifier: &str) -> String {
+    let identifier = identifier.trim();
+    let phone_like = !identifier.is_empty()
+        && identifier.chars().all(|c| c.is_ascii_digit() || " +-().".contains(c));
+    if phone_like {
+        identifier.chars().filter(|c| c.is_ascii_digit() || *c == '+').collect()
+    } else {
+        identifier.to_lowercase()
+    }
+}
+
+
+/// A chat participant or message party with its name resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Participant {
+    pub identifier: Option<String>,
+    pub name: Option<String>,
+}
+
+impl Participant {
+    fn from_party(party: &Model, names: &ChatNames) -> Self {
+        let identifier = party.field_text("Identifier").map(str::to_string);
+        let name = party
+            .field_text("Name")
+            .or_else(|| identifier.as_deref().and_then(|identifier| names.get(identifier)))
+            .map(str::to_string);
+        Participant { identifier, name }
+    }
+
+    /// `Name (identifier)`, or whichever of the two is known.
+    pub fn label(&self) -> String {
+        match (&self.name, &self.identifier) {
+            (Some(name), Some(identifier)) if name != identifier => format!("{} ({})", name, identifier),
+            (Some(name), _) => name.clone(),
+            (None, Some(identifier)) => identifier.clone(),
+            (None, None) => "Unknown".to_string(),
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscriptAttachment<'a> {
+    pub model: &'a Model,
+    pub filename: Option<&'a str>,
+    pub content_type: Option<&'a str>,
+    pub url: Option<&'a str>,
+}
+
+impl TranscriptAttachment<'_> {
+    pub fn label(&self) -> String {
+        let name = self.filename.or(self.url).unwrap_or(&self.model.id);
+        match self.content_type {
+            Some(content_type) => format!("{} ({})", name, content_type),
+            None => name.to_string(),
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscriptMessage<'a> {
+    pub model: &'a Model,
+    pub timestamp: Option<DateTime<FixedOffset>>,
+    pub from: Option<Participant>,
+    pub to: Vec<Participant>,
+    pub body: Option<&'a str>,
+    pub deleted: bool,
+    pub attachments: Vec<TranscriptAttachment<'a>>,
+}
+
+
+/// The messages of a `Chat` model in chronological order, with party names
+/// resolved from the chat participants and a [`NameDirectory`]. Messages
+/// without a timestamp follow the dated ones in their original order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transcript<'a> {
+    pub chat: &'a Model,
+    pub title: String,
+    pub source: Option<&'a str>,
+    pub participants: Vec<Participant>,
+    pub messages: Vec<TranscriptMessage<'a>>,
+}
+
+impl<'a> Transcript<'a> {
+    pub fn new(chat: &'a Model, directory: &NameDirectory) -> Self {
+        let mut names = ChatNames { directory, participants: NameDirectory::new() };
+        for party in chat.nested_models("Participants") {
+            if let (Some(identifier), Some(name)) = (party.field_text("Identifier"), party.field_text("Name")) {
+                names.participants.insert(identifier, name);
+            }
+        }
+        let participants: Vec<Participant> = chat
+            .nested_models("Participants")
+            .iter()
+            .map(|party| Participant::from_party(party, &names))
+            .collect();
+        let mut messages: Vec<TranscriptMessage<'a>> = chat
+            .nested_models("Messages")
+            .iter()
+            .map(|message| TranscriptMessage {
+                model: message,
+                timestamp: message.field_text("TimeStamp").and_then(parse_datetime),
+                from: message.nested_models("From").first().map(|party| Participant::from_party(party, &names)),
+                to: message.nested_models("To").iter().map(|party| Participant::from_party(party, &names)).collect(),
+                body: message.field_text("Body"),
+                deleted: message.deleted_state == "Deleted",
+                attachments: message
+                    .nested_models("Attachments")
+                    .iter()
+                    .chain(message.nested_models("Attachment"))
+                    .map(|attachment| TranscriptAttachment {
+                        model: attachment,
+                        filename: attachment.field_text("Filename"),
+                        content_type: attachment.field_text("ContentType"),
+                        url: attachment.field_text("URL"),
+                    })
+                    .collect(),
+            })
+            .collect();
+        messages.sort_by_key(|message| (message.timestamp.is_none(), message.timestamp));
+        let title = chat
+            .field_text("Name")
+            .map(str::to_string)
+            .unwrap_or_else(|| {
+                let labels: Vec<String> = participants.iter().map(Participant::label).collect();
+                if labels.is_empty() { chat.id.clone() } else { labels.join(", ") }
+            });
+        Transcript {
+            chat,
+            title,
+            source: chat.field_text("Source"),
+            participants,
+            messages,
+        }
+    }
+}
+
+impl Project {
+    /// Transcripts of every `Chat` model, names resolved against the
+    /// project's contacts.
+    pub fn transcripts(&self) -> Vec<Transcript<'_>> {
+        let directory = NameDirectory::from_project(self);
+        self.model_graph()
+            .models()
+            .filter(|model| model.dtype == "Chat")
+            .map(|chat| Transcript::new(chat, &directory))
+            .collect()
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::models::{ModelBuilder, ProjectBuilder};
+    use super::*;
+
+    #[test]
+    fn test_transcript_0() {
+        let party = |identifier: &str| ModelBuilder::new("Party").field("Identifier", identifier);
+        let message = |time: Option<&str>, from: &str, body: &str| {
+            let message = ModelBuilder::new("InstantMessage").field("Body", body).model("From", party(from));
+            match time {
+                Some(time) => message.typed_field("TimeStamp", "TimeStamp", time),
+                None => message,
+            }
+        };
+        let chat = ModelBuilder::new("Chat")
+            .field("Source", "WhatsApp")
+            .models("Participants", "Party", [party("+43 123").field("Name", "Irene"), party("+43456")])
+            .models("Messages", "InstantMessage", [
+                message(Some("2020-07-01T09:00:00.000+00:00"), "+43456", "second"),
+                message(None, "+43123", "undated"),
+                message(Some("2020-07-01T08:00:00.000+00:00"), "+43-123", "first")
+                    .deleted_state("Deleted")
+                    .models("Attachments", "Attachment", [
+                        ModelBuilder::new("Attachment").field("Filename", "a.jpg").field("ContentType", "image/jpeg"),
+                    ]),
+            ]);
+        let contact = ModelBuilder::new("Contact")
+            .field("Name", "Sherlock")
+            .models("Entries", "ContactEntry", [ModelBuilder::new("PhoneNumber").field("Value", "+43 456")]);
+        let project = ProjectBuilder::new("Test").model(chat).model(contact).build();
+
+        let transcripts = project.transcripts();
+        assert_eq!(transcripts.len(), 1);
+        let transcript = &transcripts[0];
+        assert_eq!(transcript.title, "Irene (+43 123), Sherlock (+43456)");
+        let bodies: Vec<Option<&str>> = transcript.messages.iter().map(|message| message.body).collect();
+        assert_eq!(bodies, vec![Some("first"), Some("second"), Some("undated")]);
+        let first = &transcript.messages[0];
+        assert_eq!(first.from.as_ref().map(Participant::label).as_deref(), Some("Irene (+43-123)"));
+        assert!(first.deleted);
+        assert_eq!(first.attachments[0].label(), "a.jpg (image/jpeg)");
+        assert_eq!(transcript.messages[1].from.as_ref().and_then(|from| from.name.as_deref()), Some("Sherlock"));
+    }
+}
@@ -0,0 +1,122 @@
+use std::fmt;
+use chrono::{DateTime, FixedOffset};
+use super::{parse_datetime, Field, Value};
+
+
+
+/// A `<value>` converted according to its `type` attribute. Values whose
+/// text does not parse as their declared type are kept as strings.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypedValue {
+    String(String),
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    TimeStamp(DateTime<FixedOffset>),
+}
+
+/// Broad category of a report `type` attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueKind {
+    String,
+    Boolean,
+    Integer,
+    Float,
+    TimeStamp,
+}
+
+impl ValueKind {
+    pub fn from_dtype(dtype: &str) -> Self {
+        match dtype {
+            "Boolean" => ValueKind::Boolean,
+            "Byte" | "SByte" | "Int16" | "UInt16" | "Int32" | "UInt32" | "Int64" | "UInt64" | "Int" | "Long" => ValueKind::Integer,
+            "Single" | "Double" | "Float" | "Decimal" => ValueKind::Float,
+            "TimeStamp" | "DateTime" | "Date" => ValueKind::TimeStamp,
+            _ => ValueKind::String,
+        }
+    }
+}
+
+impl TypedValue {
+    pub fn parse(dtype: &str, text: &str) -> Self {
+        let trimmed = text.trim();
+        let typed = match ValueKind::from_dtype(dtype) {
+            ValueKind::Boolean => match trimmed.to_ascii_lowercase().as_str() {
+                "true" => Some(TypedValue::Boolean(true)),
+                "false" => Some(TypedValue::Boolean(false)),
+                _ => None,
+            },
+            ValueKind::Integer => trimmed.parse().ok().map(TypedValue::Integer),
+            ValueKind::Float => trimmed.replace(',', ".").parse().ok().map(TypedValue::Float),
+            ValueKind::TimeStamp => parse_datetime(trimmed).map(TypedValue::TimeStamp),
+            ValueKind::String => None,
+        };
+        typed.unwrap_or_else(|| TypedValue::String(text.to_string()))
+    }
+
+    pub fn kind(&self) -> ValueKind {
+        match self {
+            TypedValue::String(_) => ValueKind::String,
+            TypedValue::Boolean(_) => ValueKind::Boolean,
+            TypedValue::Integer(_) => ValueKind::Integer,
+            TypedValue::Float(_) => ValueKind::Float,
+            TypedValue::TimeStamp(_) => ValueKind::TimeStamp,
+        }
+    }
+}
+
+impl fmt::Display for TypedValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypedValue::String(s) => write!(f, "{}", s),
+            TypedValue::Boolean(b) => write!(f, "{}", b),
+            TypedValue::Integer(i) => write!(f, "{}", i),
+            TypedValue::Float(x) => write!(f, "{}", x),
+            TypedValue::TimeStamp(t) => write!(f, "{}", t.to_rfc3339()),
+        }
+    }
+}
+
+impl Value {
+    pub fn typed(&self) -> TypedValue {
+        TypedValue::parse(&self.dtype, &self.text)
+    }
+}
+
+impl Field {
+    /// `None` for fields without a value, e.g. `<empty />` ones.
+    pub fn typed_value(&self) -> Option<TypedValue> {
+        match (&self.value, &self.dtype) {
+            (Some(value), _) => Some(value.typed()),
+            (None, _) if self.text.is_empty() => None,
+            (None, Some(dtype)) => Some(TypedValue::parse(dtype, &self.text)),
+            (None, None) => Some(TypedValue::String(self.text.clone())),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_typed_value_0() {
+        let value = Value { dtype: "Boolean".to_string(), text: "False".to_string(), other_attributes: vec![] };
+        assert_eq!(value.typed(), TypedValue::Boolean(false));
+        let value = Value { dtype: "Int32".to_string(), text: "42".to_string(), other_attributes: vec![] };
+        assert_eq!(value.typed(), TypedValue::Integer(42));
+        let value = Value { dtype: "Double".to_string(), text: "48.2".to_string(), other_attributes: vec![] };
+        assert_eq!(value.typed(), TypedValue::Float(48.2));
+        let value = Value { dtype: "TimeStamp".to_string(), text: "2020-07-01T07:45:53.000+00:00".to_string(), other_attributes: vec![] };
+        assert_eq!(value.typed().to_string(), "2020-07-01T07:45:53+00:00");
+    }
+
+    #[test]
+    fn test_typed_value_1() {
+        let value = Value { dtype: "Int32".to_string(), text: "n/a".to_string(), other_attributes: vec![] };
+        assert_eq!(value.typed(), TypedValue::String("n/a".to_string()));
+        let value = Value { dtype: "String".to_string(), text: "42".to_string(), other_attributes: vec![] };
+        assert_eq!(value.typed(), TypedValue::String("42".to_string()));
+    }
+}
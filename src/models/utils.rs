@@ -1,5 +1,6 @@
-use std::{collections::HashMap, io::BufRead, str};
-use quick_xml::{events::{BytesStart, Event}, Reader};
+use std::{collections::HashMap, io::{BufRead, Write}, str};
+use chrono::{DateTime, FixedOffset, NaiveDateTime};
+use quick_xml::{events::{BytesCData, BytesStart, Event}, Reader, Writer};
 use super::ParseError;
 
 
@@ -18,6 +19,30 @@ pub fn attributes_to_map(
     Ok(map)
 }
 
+/// Attributes of `e` not in `known`, in document order.
+pub fn other_attributes(
+    e: &BytesStart,
+    known: &[&str],
+) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+    let mut attributes = Vec::new();
+
+    for attr in e.attributes() {
+        let attr = attr?;
+        let key = str::from_utf8(attr.key.as_ref())?;
+        if !known.contains(&key) {
+            attributes.push((key.to_string(), attr.unescape_value()?.to_string()));
+        }
+    }
+
+    Ok(attributes)
+}
+
+/// `attributes` kept by [`other_attributes`], as pairs for
+/// [`BytesStart::with_attributes`].
+pub fn attribute_pairs(attributes: &[(String, String)]) -> impl Iterator<Item = (&str, &str)> {
+    attributes.iter().map(|(key, value)| (key.as_str(), value.as_str()))
+}
+
 
 pub fn read_text<B: BufRead>(reader: &mut Reader<B>) -> Result<String, Box<dyn std::error::Error>> {
     let mut text = String::new();
@@ -25,7 +50,7 @@ pub fn read_text<B: BufRead>(reader: &mut Reader<B>) -> Result<String, Box<dyn s
     loop {
         match reader.read_event_into(&mut buf)? {
             Event::Text(e) => {
-                text.push_str(&e.unescape()?.to_string());
+                text.push_str(&e.unescape()?);
             }
             Event::CData(e) => {
                 text.push_str(std::str::from_utf8(&e)?.trim());
@@ -47,3 +72,101 @@ pub fn read_text<B: BufRead>(reader: &mut Reader<B>) -> Result<String, Box<dyn s
     Ok(text)
 }
 
+/// Writes `text` as CDATA, splitting it where it contains `]]>`.
+pub fn write_cdata<W: Write>(writer: &mut Writer<W>, text: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if !text.is_empty() {
+        for cdata in BytesCData::escaped(text) {
+            writer.write_event(Event::CData(cdata))?;
+        }
+    }
+    Ok(())
+}
+
+
+
+
+const DATETIME_FORMATS: [&str; 6] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%d.%m.%Y %H:%M:%S",
+    "%m/%d/%Y %H:%M:%S",
+    "%d/%m/%Y %H:%M:%S",
+    "%m/%d/%Y %I:%M:%S %p",
+];
+
+/// Parses the timestamp flavours found in reports: RFC 3339 values of
+/// `TimeStamp` fields and the localised `10/10/2020 13:37:00` or
+/// `09.09.2020 19:33:13(UTC+0)` strings of metadata items. Times without an
+/// offset are taken as UTC.
+pub fn parse_datetime(text: &str) -> Option<DateTime<FixedOffset>> {
+    let text = text.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(text) {
+        return Some(datetime);
+    }
+    let (naive, offset) = match text.find("(UTC") {
+        Some(pos) => (text[..pos].trim(), parse_utc_offset(text[pos + 4..].trim_end_matches(')'))?),
+        None => (text, FixedOffset::east_opt(0)?),
+    };
+    DATETIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(naive, format).ok())
+        .and_then(|datetime| datetime.and_local_timezone(offset).single())
+}
+
+fn parse_utc_offset(text: &str) -> Option<FixedOffset> {
+    let text = text.trim();
+    if text.is_empty() {
+        return FixedOffset::east_opt(0);
+    }
+    let (sign, rest) = match (text.strip_prefix('+'), text.strip_prefix('-')) {
+        (Some(rest), _) => (1, rest),
+        (_, Some(rest)) => (-1, rest),
+        _ => return None,
+    };
+    let (hours, minutes): (i32, i32) = match rest.split_once(':') {
+        Some((hours, minutes)) => (hours.parse().ok()?, minutes.parse().ok()?),
+        None if rest.len() == 4 && rest.bytes().all(|b| b.is_ascii_digit()) => (rest[..2].parse().ok()?, rest[2..].parse().ok()?),
+        None => (rest.parse().ok()?, 0),
+    };
+    if !(0..=23).contains(&hours) || !(0..=59).contains(&minutes) {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_datetime_0() {
+        let datetime = parse_datetime("2020-07-01T07:45:53.000+00:00").unwrap();
+        assert_eq!(datetime.to_rfc3339(), "2020-07-01T07:45:53+00:00");
+    }
+
+    #[test]
+    fn test_parse_datetime_1() {
+        let datetime = parse_datetime("09.09.2020 19:33:13(UTC+2)").unwrap();
+        assert_eq!(datetime.to_rfc3339(), "2020-09-09T19:33:13+02:00");
+        let datetime = parse_datetime("09.09.2020 19:33:13(UTC-5:30)").unwrap();
+        assert_eq!(datetime.to_rfc3339(), "2020-09-09T19:33:13-05:30");
+    }
+
+    #[test]
+    fn test_parse_datetime_2() {
+        let datetime = parse_datetime("10/10/2020 13:37:00").unwrap();
+        assert_eq!(datetime.to_rfc3339(), "2020-10-10T13:37:00+00:00");
+        let datetime = parse_datetime("25/10/2020 13:37:00").unwrap();
+        assert_eq!(datetime.to_rfc3339(), "2020-10-25T13:37:00+00:00");
+        assert_eq!(parse_datetime("Activated"), None);
+    }
+
+    #[test]
+    fn test_parse_datetime_3() {
+        assert_eq!(parse_datetime("09.09.2020 19:33:13(UTC\u{2212}5)"), None);
+        assert_eq!(parse_datetime("09.09.2020 19:33:13(UTC+99999999)"), None);
+        assert_eq!(parse_datetime("09.09.2020 19:33:13(UTC+5:99)"), None);
+        assert_eq!(parse_datetime("09.09.2020 19:33:13(UTC+1\u{e9}2)"), None);
+    }
+}
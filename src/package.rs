@@ -0,0 +1,129 @@
+use std::{fs, io::{BufRead, BufReader, Cursor, Read}, path::{Component, Path, PathBuf}};
+use quick_xml::{events::Event, Reader};
+use zip::ZipArchive;
+use crate::models::{ParseError, Project, ReportStream};
+
+
+
+/// A UFDR package, either a `.ufdr` zip or the directory it was extracted
+/// to. Holds `report.xml` at its root and the tagged files under the
+/// `Local Path` the report gives them.
+#[derive(Debug)]
+pub enum Package {
+    Directory(PathBuf),
+    Zip(ZipArchive<BufReader<fs::File>>),
+}
+
+impl Package {
+    /// Opens a directory as is and anything else as a zip.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = path.as_ref();
+        if path.is_dir() {
+            return Ok(Package::Directory(path.to_path_buf()));
+        }
+        let file = fs::File::open(path)?;
+        Ok(Package::Zip(ZipArchive::new(BufReader::new(file))?))
+    }
+
+    /// Reads a file by its `Local Path`, e.g. `files\Image\a.jpg`. Paths
+    /// leaving the package are rejected.
+    pub fn read(&mut self, local_path: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let name = entry_name(local_path)?;
+        let mut bytes = Vec::new();
+        match self {
+            Package::Directory(dir) => {
+                bytes = fs::read(dir.join(&name))?;
+            }
+            Package::Zip(archive) => {
+                archive.by_name(&name)?.read_to_end(&mut bytes)?;
+            }
+        }
+        Ok(bytes)
+    }
+
+    pub fn contains(&self, local_path: &str) -> bool {
+        match (self, entry_name(local_path)) {
+            (Package::Directory(dir), Ok(name)) => dir.join(name).is_file(),
+            (Package::Zip(archive), Ok(name)) => archive.index_for_name(&name).is_some(),
+            (_, Err(_)) => false,
+        }
+    }
+
+    /// Parses the package's `report.xml`.
+    pub fn project(&mut self) -> Result<Project, Box<dyn std::error::Error>> {
+        let report = self.read("report.xml")?;
+        let mut reader = Reader::from_reader(Cursor::new(report));
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf)? {
+                Event::Start(ref e) if e.name().as_ref() == b"project" => return Project::parse_one(e, &mut reader),
+                Event::Eof => return Err(Box::new(ParseError::new("missing project"))),
+                _ => (),
+            }
+            buf.clear();
+        }
+    }
+
+    /// Streams the package's `report.xml` instead of parsing it as a whole,
+    /// see [`ReportStream`].
+    pub fn report_stream(&mut self) -> Result<ReportStream<Box<dyn BufRead + '_>>, Box<dyn std::error::Error>> {
+        let reader: Box<dyn BufRead + '_> = match self {
+            Package::Directory(dir) => Box::new(BufReader::new(fs::File::open(dir.join("report.xml"))?)),
+            Package::Zip(archive) => Box::new(BufReader::new(archive.by_name("report.xml")?)),
+        };
+        Ok(ReportStream::new(Reader::from_reader(reader)))
+    }
+}
+
+/// `files\Image\a.jpg` as the zip entry name `files/Image/a.jpg`.
+fn entry_name(local_path: &str) -> Result<String, ParseError> {
+    let name = local_path.replace('\\', "/");
+    let relative = Path::new(&name).components().all(|component| matches!(component, Component::Normal(_)));
+    if name.is_empty() || !relative {
+        return Err(ParseError::new(&format!("invalid package path {}", local_path)));
+    }
+    Ok(name)
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+    use zip::{write::SimpleFileOptions, ZipWriter};
+    use super::*;
+
+    const REPORT: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<project id="p" name="Test" reportVersion="7.0.0.0" licenseID="0" containsGarbage="False" extractionType="Logical" NodeCount="0" ModelCount="0" xmlns="http://pa.cellebrite.com/report/2.0">
+  <sourceExtractions></sourceExtractions>
+  <caseInformation></caseInformation>
+</project>"#;
+
+    #[test]
+    fn test_package_0() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = std::env::temp_dir().join(format!("ufdr-package-{}", std::process::id()));
+        fs::create_dir_all(dir.join("files/Image"))?;
+        fs::write(dir.join("report.xml"), REPORT)?;
+        fs::write(dir.join("files/Image/a.jpg"), b"jpeg")?;
+
+        let path = dir.with_extension("ufdr");
+        let mut zip = ZipWriter::new(fs::File::create(&path)?);
+        zip.start_file("report.xml", SimpleFileOptions::default())?;
+        zip.write_all(REPORT.as_bytes())?;
+        zip.start_file("files/Image/a.jpg", SimpleFileOptions::default())?;
+        zip.write_all(b"jpeg")?;
+        zip.finish()?;
+
+        for package in [Package::open(&dir)?, Package::open(&path)?] {
+            let mut package = package;
+            assert_eq!(package.project()?.name, "Test");
+            assert_eq!(package.report_stream()?.count(), 2);
+            assert_eq!(package.read("files\\Image\\a.jpg")?, b"jpeg");
+            assert!(package.contains("files\\Image\\a.jpg"));
+            assert!(!package.contains("files\\Image\\b.jpg"));
+            assert!(package.read("..\\report.xml").is_err());
+        }
+        fs::remove_dir_all(&dir)?;
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+}